@@ -25,7 +25,11 @@ fn run_loop_benchmark(_c: &mut Criterion) {
         let mut rain = digital_rain::DigitalRain::new(options, (80, 40));
 
         b.iter(|| {
-            let _ = common::run_loop(black_box(&mut stdout), &mut rain, Some(3));
+            let options = common::LoopOptions {
+                iterations: Some(3),
+                ..Default::default()
+            };
+            let _ = common::run_loop(black_box(&mut stdout), &mut rain, &options);
         })
     });
 }
@@ -1,12 +1,25 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::TerminalEffect;
+use crate::config::{Config, Validator};
+use crate::palette::PaletteChoice;
 use crossterm::style;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
 #[derive(Builder, Default, Debug, Clone, Serialize, Deserialize)]
 #[builder(public, setter(into))]
-pub struct BlankOptions {}
+pub struct BlankOptions {
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+impl BlankOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.palette("palette", &self.palette);
+    }
+}
 
 #[allow(dead_code)]
 pub struct Blank {
@@ -20,11 +33,7 @@ impl TerminalEffect for Blank {
         let mut curr_buffer =
             Buffer::new(self.screen_size.0 as usize, self.screen_size.1 as usize);
 
-        curr_buffer.fill_with(&Cell {
-            symbol: '#',
-            color: style::Color::Green,
-            attr: style::Attribute::Reset,
-        });
+        curr_buffer.fill_with(&fill_cell(&self.options));
 
         let diff = self.buffer.diff(&curr_buffer);
         self.buffer = curr_buffer;
@@ -33,13 +42,36 @@ impl TerminalEffect for Blank {
 
     fn update(&mut self) {}
 
+    fn frame_rate(&self) -> f64 {
+        0.0
+    }
+
     fn update_size(&mut self, width: u16, height: u16) {
-        self.screen_size = (width, height)
+        self.screen_size = (width, height);
+        self.reset();
     }
 
     fn reset(&mut self) {
         *self = Self::new(self.options.clone(), self.screen_size);
     }
+
+    fn reconfigure(&mut self, config: &Config) {
+        self.options = config.get_blank_options();
+        self.reset();
+    }
+}
+
+/// Green or the brightest palette color
+fn fill_cell(options: &BlankOptions) -> Cell {
+    let color = match options.palette.palette() {
+        Some(palette) => palette.color(1.0),
+        None => style::Color::Green,
+    };
+    Cell {
+        symbol: '#',
+        color,
+        attr: style::Attribute::Reset,
+    }
 }
 
 impl Blank {
@@ -47,11 +79,7 @@ impl Blank {
         let mut buffer =
             Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
 
-        buffer.fill_with(&Cell {
-            symbol: '#',
-            color: style::Color::Green,
-            attr: style::Attribute::Reset,
-        });
+        buffer.fill_with(&fill_cell(&options));
 
         Self {
             screen_size,
@@ -1,12 +1,16 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::governor::scaled_count;
+use crate::palette::{Palette, PaletteChoice};
+use crate::random;
 use crossterm::style;
 use derive_builder::Builder;
 use rand::RngExt;
 use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub enum BoidCharset {
     #[default]
     Braille,
@@ -26,6 +30,12 @@ impl BoidCharset {
     }
 }
 
+/// `snapshot` of the flock, boids as `[[x, y], [vx, vy]]`
+#[derive(Serialize, Deserialize)]
+struct BoidsState {
+    boids: Vec<((f32, f32), (f32, f32))>,
+}
+
 // Individual boid
 #[derive(Clone)]
 struct Boid {
@@ -35,16 +45,10 @@ struct Boid {
     color: style::Color,  // Color based on velocity/state
 }
 
-#[derive(Builder, Default, Debug, Clone, Serialize, Deserialize)]
+/// User-facing settings, `[boids]` section of `tarts.toml`
+#[derive(Builder, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[builder(public, setter(into))]
-pub struct BoidsOptions {
-    #[builder(default)]
-    #[serde(skip)]
-    pub screen_size: (u16, u16),
-    #[builder(default = "100")]
-    #[serde(skip)]
-    pub boid_count: u16,
-
+pub struct BoidsConfig {
     #[builder(default = "1.0")]
     pub boid_coeff: f32,
 
@@ -81,13 +85,63 @@ pub struct BoidsOptions {
 
     #[builder(default)]
     charset: BoidCharset,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+/// Runtime options resolved from `BoidsConfig` for a screen size
+#[derive(Builder, Debug, Clone, PartialEq)]
+#[builder(public, setter(into))]
+pub struct BoidsOptions {
+    pub boid_count: u16,
+    #[builder(default)]
+    pub config: BoidsConfig,
+}
+
+impl Default for BoidsConfig {
+    fn default() -> Self {
+        BoidsConfigBuilder::default().build().unwrap()
+    }
+}
+
+impl BoidsConfig {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.positive("boid_coeff", self.boid_coeff);
+        check.non_negative("separation_weight", self.separation_weight);
+        check.positive("separation_distance", self.separation_distance);
+        check.non_negative("alignment_weight", self.alignment_weight);
+        check.positive("alignment_distance", self.alignment_distance);
+        check.non_negative("cohesion_weight", self.cohesion_weight);
+        check.positive("cohesion_distance", self.cohesion_distance);
+        check.non_negative("drive_factor", self.drive_factor);
+        check.non_negative("swirl_factor", self.swirl_factor);
+        check.non_negative("border_factor", self.border_factor);
+        check.non_negative("min_speed", self.min_speed);
+        check.positive("max_speed", self.max_speed);
+        check.not_greater("min_speed", self.min_speed, "max_speed", self.max_speed);
+        check.palette("palette", &self.palette);
+    }
+
+    /// Half of the screen cells, 50 to 300 boids
+    pub fn to_runtime(&self, screen_size: (u16, u16)) -> BoidsOptions {
+        let (w, h) = screen_size;
+        BoidsOptions {
+            boid_count: ((w as f32 * h as f32 * 0.5 * self.boid_coeff) as u16)
+                .clamp(50, 300),
+            config: self.clone(),
+        }
+    }
 }
 
 pub struct Boids {
+    pub screen_size: (u16, u16),
     options: BoidsOptions,
     buffer: Buffer,
     boids: Vec<Boid>,
     charset_chars: [char; 8],
+    quality: f32,
 }
 
 impl Boid {
@@ -108,12 +162,16 @@ impl Boid {
         charset[idx as usize]
     }
 
-    fn update_visual(&mut self, charset: &[char; 8]) {
+    fn update_visual(&mut self, charset: &[char; 8], palette: Option<&Palette>) {
         self.character = self.get_direction_char(charset);
 
         // Speed-based color (green to white)
         let speed = (self.velocity.0.powi(2) + self.velocity.1.powi(2)).sqrt();
         let intensity = ((speed * 128.0).clamp(0.0, 255.0)) as u8;
+        if let Some(palette) = palette {
+            self.color = palette.color(intensity as f32 / 255.0);
+            return;
+        }
         self.color = style::Color::Rgb {
             r: intensity,
             g: 200,
@@ -124,17 +182,13 @@ impl Boid {
 
 impl TerminalEffect for Boids {
     fn get_diff(&mut self) -> Vec<(usize, usize, Cell)> {
-        let mut curr_buffer = Buffer::new(
-            self.options.screen_size.0 as usize,
-            self.options.screen_size.1 as usize,
-        );
+        let mut curr_buffer =
+            Buffer::new(self.screen_size.0 as usize, self.screen_size.1 as usize);
 
         // Fill current buffer with boids
         for boid in &self.boids {
-            let x = boid.position.0.round() as usize
-                % self.options.screen_size.0 as usize;
-            let y = boid.position.1.round() as usize
-                % self.options.screen_size.1 as usize;
+            let x = boid.position.0.round() as usize % self.screen_size.0 as usize;
+            let y = boid.position.1.round() as usize % self.screen_size.1 as usize;
 
             curr_buffer.set(
                 x,
@@ -157,30 +211,107 @@ impl TerminalEffect for Boids {
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
-        self.options.screen_size = (width, height);
+        // keep the flock, stretch positions to the new screen
+        let scale_x = width as f32 / self.screen_size.0.max(1) as f32;
+        let scale_y = height as f32 / self.screen_size.1.max(1) as f32;
+        for boid in &mut self.boids {
+            boid.position.0 = (boid.position.0 * scale_x)
+                .clamp(0.0, (width as f32 - 1.0).max(0.0));
+            boid.position.1 = (boid.position.1 * scale_y)
+                .clamp(0.0, (height as f32 - 1.0).max(0.0));
+        }
+        self.screen_size = (width, height);
+        self.buffer = Buffer::new(width as usize, height as usize);
     }
 
     fn reset(&mut self) {
-        *self = Self::new(self.options.clone());
+        let quality = self.quality;
+        *self = Self::new(self.options.clone(), self.screen_size);
+        self.set_quality(quality);
+    }
+
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        vec![("boids", self.boids.len())]
+    }
+
+    fn set_quality(&mut self, quality: f32) {
+        // rules are quadratic in number of boids, flock size is the knob
+        self.quality = quality;
+        let count = scaled_count(self.options.boid_count as usize, quality);
+        self.boids.truncate(count);
+        self.fill_boids(count);
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (10, 5)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        // flock keeps flying with new weights and speeds, size follows count
+        self.options = config.get_boids_options(self.screen_size);
+        self.charset_chars = self.options.config.charset.chars();
+        self.set_quality(self.quality);
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
+
+    fn snapshot(&self) -> Option<toml::Value> {
+        let boids = self
+            .boids
+            .iter()
+            .map(|boid| (boid.position, boid.velocity))
+            .collect();
+        toml::Value::try_from(BoidsState { boids }).ok()
+    }
+
+    fn restore(
+        &mut self,
+        state: toml::Value,
+        screen_size: (u16, u16),
+    ) -> Result<(), String> {
+        let state: BoidsState = state.try_into().map_err(|e| e.to_string())?;
+        // character and color follow from velocity
+        let palette = self.options.config.palette.palette();
+        self.boids = state
+            .boids
+            .into_iter()
+            .map(|(position, velocity)| {
+                let mut boid = Boid::new(position, velocity);
+                boid.update_visual(&self.charset_chars, palette);
+                boid
+            })
+            .collect();
+        self.screen_size = screen_size;
+        self.buffer = Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
+        Ok(())
     }
 }
 
 impl Boids {
-    pub fn new(options: BoidsOptions) -> Self {
-        let mut rng = rand::rng();
-        let buffer = Buffer::new(
-            options.screen_size.0 as usize,
-            options.screen_size.1 as usize,
-        );
-
-        let width = options.screen_size.0 as f32;
-        let height = options.screen_size.1 as f32;
+    pub fn new(options: BoidsOptions, screen_size: (u16, u16)) -> Self {
+        let buffer = Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
+        let charset_chars = options.config.charset.chars();
+        let mut effect = Self {
+            screen_size,
+            options,
+            buffer,
+            boids: Vec::new(),
+            charset_chars,
+            quality: 1.0,
+        };
 
         // Create initial boids with random positions and velocities
-        let charset_chars = options.charset.chars();
+        effect.fill_boids(effect.options.boid_count as usize);
+        effect
+    }
+
+    /// Add random boids until there are `count` of them
+    fn fill_boids(&mut self, count: usize) {
+        let mut rng = random::rng();
+        let width = self.screen_size.0 as f32;
+        let height = self.screen_size.1 as f32;
 
-        let mut boids = Vec::with_capacity(options.boid_count as usize);
-        for _ in 0..options.boid_count {
+        while self.boids.len() < count {
             let position =
                 (rng.random_range(0.0..width), rng.random_range(0.0..height));
 
@@ -188,22 +319,18 @@ impl Boids {
                 (rng.random_range(-1.0..1.0), rng.random_range(-1.0..1.0));
 
             let mut boid = Boid::new(position, velocity);
-            boid.update_visual(&charset_chars);
-            boids.push(boid);
-        }
-
-        Self {
-            options,
-            buffer,
-            boids,
-            charset_chars,
+            boid.update_visual(
+                &self.charset_chars,
+                self.options.config.palette.palette(),
+            );
+            self.boids.push(boid);
         }
     }
 
     // Calculate toroidal difference between two positions
     fn toroidal_diff(&self, a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
-        let width = self.options.screen_size.0 as f32;
-        let height = self.options.screen_size.1 as f32;
+        let width = self.screen_size.0 as f32;
+        let height = self.screen_size.1 as f32;
 
         let mut dx = a.0 - b.0;
         let mut dy = a.1 - b.1;
@@ -255,7 +382,7 @@ impl Boids {
 
                 // Separation
                 #[allow(clippy::collapsible_if)]
-                if distance < self.options.separation_distance {
+                if distance < self.options.config.separation_distance {
                     if distance > 0.0 {
                         let factor = 1.0 / distance;
                         separation.0 -= diff.0 * factor;
@@ -265,14 +392,14 @@ impl Boids {
                 }
 
                 // Alignment
-                if distance < self.options.alignment_distance {
+                if distance < self.options.config.alignment_distance {
                     avg_velocity.0 += self.boids[j].velocity.0;
                     avg_velocity.1 += self.boids[j].velocity.1;
                     align_count += 1;
                 }
 
                 // Cohesion
-                if distance < self.options.cohesion_distance {
+                if distance < self.options.config.cohesion_distance {
                     center.0 += self.boids[j].position.0;
                     center.1 += self.boids[j].position.1;
                     cohesion_count += 1;
@@ -282,8 +409,8 @@ impl Boids {
             // Finalize separation
             if sep_count > 0 {
                 separation_adjustments[i] = (
-                    separation.0 * self.options.separation_weight,
-                    separation.1 * self.options.separation_weight,
+                    separation.0 * self.options.config.separation_weight,
+                    separation.1 * self.options.config.separation_weight,
                 );
             }
 
@@ -296,10 +423,10 @@ impl Boids {
 
                 alignment_adjustments[i] = (
                     (avg_vel.0 - self.boids[i].velocity.0)
-                        * self.options.alignment_weight
+                        * self.options.config.alignment_weight
                         * 0.05,
                     (avg_vel.1 - self.boids[i].velocity.1)
-                        * self.options.alignment_weight
+                        * self.options.config.alignment_weight
                         * 0.05,
                 );
             }
@@ -324,18 +451,22 @@ impl Boids {
                 };
 
                 cohesion_adjustments[i] = (
-                    toward_center.0 * self.options.cohesion_weight * 0.03
-                        + swirl_normalized.0 * self.options.swirl_factor * 0.02,
-                    toward_center.1 * self.options.cohesion_weight * 0.03
-                        + swirl_normalized.1 * self.options.swirl_factor * 0.02,
+                    toward_center.0 * self.options.config.cohesion_weight * 0.03
+                        + swirl_normalized.0
+                            * self.options.config.swirl_factor
+                            * 0.02,
+                    toward_center.1 * self.options.config.cohesion_weight * 0.03
+                        + swirl_normalized.1
+                            * self.options.config.swirl_factor
+                            * 0.02,
                 );
             }
 
             // Apply border avoidance
-            let width = self.options.screen_size.0 as f32;
-            let height = self.options.screen_size.1 as f32;
+            let width = self.screen_size.0 as f32;
+            let height = self.screen_size.1 as f32;
             let border_margin = 5.0;
-            let border_strength = self.options.border_factor;
+            let border_strength = self.options.config.border_factor;
 
             let mut border_force = (0.0, 0.0);
             let pos = self.boids[i].position;
@@ -387,8 +518,8 @@ impl Boids {
             if speed > 0.0 {
                 let normalized_vx = new_vx / speed;
                 let normalized_vy = new_vy / speed;
-                new_vx += normalized_vx * self.options.drive_factor * 0.1;
-                new_vy += normalized_vy * self.options.drive_factor * 0.1;
+                new_vx += normalized_vx * self.options.config.drive_factor * 0.1;
+                new_vy += normalized_vy * self.options.config.drive_factor * 0.1;
             }
 
             // Apply damping for smoother movement
@@ -397,12 +528,12 @@ impl Boids {
 
             // Apply speed limits
             let speed = (new_vx * new_vx + new_vy * new_vy).sqrt();
-            if speed > self.options.max_speed {
-                let scale = self.options.max_speed / speed;
+            if speed > self.options.config.max_speed {
+                let scale = self.options.config.max_speed / speed;
                 new_vx *= scale;
                 new_vy *= scale;
-            } else if speed < self.options.min_speed && speed > 0.0 {
-                let scale = self.options.min_speed / speed;
+            } else if speed < self.options.config.min_speed && speed > 0.0 {
+                let scale = self.options.config.min_speed / speed;
                 new_vx *= scale;
                 new_vy *= scale;
             }
@@ -413,8 +544,8 @@ impl Boids {
     }
 
     fn update_positions(&mut self) {
-        let width = self.options.screen_size.0 as f32;
-        let height = self.options.screen_size.1 as f32;
+        let width = self.screen_size.0 as f32;
+        let height = self.screen_size.1 as f32;
 
         for boid in &mut self.boids {
             // Update position
@@ -435,7 +566,10 @@ impl Boids {
             }
 
             // Update visual representation
-            boid.update_visual(&self.charset_chars);
+            boid.update_visual(
+                &self.charset_chars,
+                self.options.config.palette.palette(),
+            );
         }
     }
 }
@@ -444,12 +578,6 @@ impl DefaultOptions for Boids {
     type Options = BoidsOptions;
 
     fn default_options(width: u16, height: u16) -> Self::Options {
-        let boid_count = ((width * height) as f32 * 0.5) as u16; // About 1% of screen space
-
-        BoidsOptionsBuilder::default()
-            .screen_size((width, height))
-            .boid_count(boid_count.clamp(50, 300))
-            .build()
-            .unwrap()
+        BoidsConfig::default().to_runtime((width, height))
     }
 }
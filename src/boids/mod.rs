@@ -1,3 +1,6 @@
 pub mod effect;
 #[allow(unused)]
-pub use effect::{BoidCharset, Boids, BoidsOptions, BoidsOptionsBuilder};
+pub use effect::{
+    BoidCharset, Boids, BoidsConfig, BoidsConfigBuilder, BoidsOptions,
+    BoidsOptionsBuilder,
+};
@@ -39,10 +39,9 @@ impl Buffer {
     // Keep in mind!
     // Indexing from 0: 0 1 2 3 4  | Square: 16
     // Indexing from 1: 1 2 3 4 5  | Square: 25
-    // Need to check width of height are greater than zero
+    // Zero width or height gives empty buffer, terminals can report it
     pub fn new(width: usize, height: usize) -> Self {
         // fill buffer with default values
-        debug_assert!(width > 0 && height > 0);
         Self {
             width,
             height,
@@ -104,6 +103,52 @@ impl Buffer {
     pub fn iter(&self) -> std::slice::Iter<'_, Cell> {
         self.buffer.iter()
     }
+
+    /// Apply diff produced by `diff` (or by `TerminalEffect::get_diff`),
+    /// cells outside of the buffer are ignored
+    pub fn apply_diff(&mut self, diff: &[(usize, usize, Cell)]) {
+        for (x, y, cell) in diff.iter() {
+            if *x < self.width && *y < self.height {
+                self.set(*x, *y, *cell);
+            }
+        }
+    }
+
+    /// Copy other buffer into this one with top left corner at (x, y),
+    /// everything that doesn't fit is clipped
+    pub fn blit(&mut self, other: &Buffer, x: usize, y: usize) {
+        for oy in 0..other.height {
+            if y + oy >= self.height {
+                break;
+            }
+            for ox in 0..other.width {
+                if x + ox >= self.width {
+                    break;
+                }
+                self.set(x + ox, y + oy, other.get(ox, oy));
+            }
+        }
+    }
+
+    /// Write string starting from (x, y), clipped by the buffer width
+    pub fn put_str(
+        &mut self,
+        x: usize,
+        y: usize,
+        text: &str,
+        color: style::Color,
+        attr: style::Attribute,
+    ) {
+        if y >= self.height {
+            return;
+        }
+        for (offset, symbol) in text.chars().enumerate() {
+            if x + offset >= self.width {
+                break;
+            }
+            self.set(x + offset, y, Cell::new(symbol, color, attr));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -155,6 +200,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn create_empty() {
+        let buf = Buffer::new(0, 5);
+        assert!(buf.buffer.is_empty());
+        assert!(buf.diff(&Buffer::new(0, 5)).is_empty());
+    }
+
     #[test]
     fn get_size() {
         let buf = Buffer::new(10, 20);
@@ -274,6 +326,48 @@ mod tests {
         assert_eq!(diff.len(), 3);
     }
 
+    #[test]
+    fn apply_diff() {
+        let buf1 = Buffer::new(3, 3);
+        let mut buf2 = Buffer::new(3, 3);
+        let cell = Cell::new('X', style::Color::Red, style::Attribute::Bold);
+        buf2.set(2, 1, cell);
+
+        let mut shadow = buf1.clone();
+        shadow.apply_diff(&buf1.diff(&buf2));
+        assert_eq!(shadow.get(2, 1), cell);
+        assert!(shadow.diff(&buf2).is_empty());
+
+        // out of bounds cells are ignored
+        shadow.apply_diff(&[(5, 5, cell)]);
+    }
+
+    #[test]
+    fn blit_clipped() {
+        let mut buf = Buffer::new(4, 3);
+        let mut small = Buffer::new(3, 3);
+        let cell = Cell::new('#', style::Color::Green, style::Attribute::Reset);
+        small.fill_with(&cell);
+
+        buf.blit(&small, 2, 1);
+        assert_eq!(buf.get(2, 1), cell);
+        assert_eq!(buf.get(3, 2), cell);
+        assert_eq!(buf.get(1, 1), Cell::default());
+        assert_eq!(buf.get(2, 0), Cell::default());
+    }
+
+    #[test]
+    fn put_str_clipped() {
+        let mut buf = Buffer::new(4, 2);
+        buf.put_str(1, 1, "hello", style::Color::White, style::Attribute::Bold);
+        assert_eq!(buf.get(1, 1).symbol, 'h');
+        assert_eq!(buf.get(3, 1).symbol, 'l');
+        assert_eq!(buf.get(0, 1), Cell::default());
+
+        // rows below the buffer are ignored
+        buf.put_str(0, 2, "x", style::Color::White, style::Attribute::Bold);
+    }
+
     #[test]
     fn iter() {
         let buf = Buffer::new(2, 2);
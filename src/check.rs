@@ -35,6 +35,7 @@ pub fn test_effect<T: TerminalEffect>(effect: &mut T, frames: usize) -> Result<(
     let mut stdout = io::stdout();
     execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
     terminal::enable_raw_mode()?;
+    crate::crash::set_terminal_active(true);
 
     for frame in 1..=frames {
         // Clear the screen
@@ -68,7 +69,7 @@ pub fn test_effect<T: TerminalEffect>(effect: &mut T, frames: usize) -> Result<(
     }
 
     // Wait for any key press
-    loop {
+    while !crate::crash::terminate_requested() {
         if event::poll(Duration::from_millis(100))?
             && let Event::Key(_) = event::read()?
         {
@@ -78,6 +79,7 @@ pub fn test_effect<T: TerminalEffect>(effect: &mut T, frames: usize) -> Result<(
 
     execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
     terminal::disable_raw_mode()?;
+    crate::crash::set_terminal_active(false);
 
     Ok(())
 }
@@ -99,11 +101,11 @@ pub fn run_test_for_effect(effect_name: &str, frames: usize) -> Result<()> {
             test_effect(&mut digital_rain, frames)
         }
         "life" => {
-            let options = crate::life::ConwayLifeOptionsBuilder::default()
-                .build()
-                .unwrap();
+            let screen_size = terminal::size()?;
+            let options =
+                crate::life::ConwayLifeConfig::default().to_runtime(screen_size);
             let mut conway_life =
-                crate::life::ConwayLife::new(options, terminal::size()?);
+                crate::life::ConwayLife::new(options, screen_size);
             test_effect(&mut conway_life, frames)
         }
         "maze" => {
@@ -1,12 +1,25 @@
-use crate::buffer::Cell;
+use crate::buffer::{Buffer, Cell};
+use crate::config::Config;
+use crate::governor::QualityGovernor;
+use crate::hud::Hud;
+use crate::metrics::{CountingWriter, FrameMetrics, FrameSample, as_micros};
+use crate::record::{CastWriter, RecordOptions, Recorder};
+use crate::reload::ConfigSource;
+use crate::screenshot;
+use crate::snapshot::{self, Snapshot};
+use crate::theme::Theme;
 use crossterm::{
     QueueableCommand, cursor, event,
     style::{self, Stylize},
     terminal,
 };
 use std::{
-    io::{BufWriter, Result, Write},
-    time::Duration,
+    collections::HashMap,
+    io::{self, BufWriter, Result, Write},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender, SyncSender},
+    thread,
+    time::{Duration, Instant},
 };
 
 pub trait DefaultOptions {
@@ -17,108 +30,633 @@ pub trait DefaultOptions {
     fn default_options(width: u16, height: u16) -> Self::Options;
 }
 
-pub trait TerminalEffect {
+/// Effects run on a worker thread of `run_loop`, hence `Send`
+pub trait TerminalEffect: Send {
     /// get difference between frames, this is used to minimize screen updates
     fn get_diff(&mut self) -> Vec<(usize, usize, Cell)>;
     /// Update to next frame
     fn update(&mut self);
-    // Update screen size option, each saver should implement it by itself
+    /// Adapt to new screen size. Simulation state is kept where it makes
+    /// sense (cells, positions), internal buffers are reallocated, the screen
+    /// is cleared by the caller so next `get_diff` has to redraw everything
     fn update_size(&mut self, width: u16, height: u16);
-    /// Reset effect, i think it's useful in case of size/options update
+    /// Start effect from scratch with current size and options
     fn reset(&mut self);
+    /// Smallest screen (width, height) effect looks sane on, below it
+    /// loop shows "terminal too small" notice instead of the effect
+    fn min_size(&self) -> (u16, u16) {
+        (1, 1)
+    }
+    /// Entity counts shown in the debug HUD, e.g. `("drops", 120)`
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        Vec::new()
+    }
+    /// Frames per second the effect looks right at, capped by `MAX_FPS`.
+    /// Zero means static picture: it is drawn once and again only after
+    /// resize or HUD toggle, loop sleeps until input in between
+    fn frame_rate(&self) -> f64 {
+        MAX_FPS
+    }
+    /// Advance animation time by `step` on every `update` instead of by the
+    /// wall clock; `run_headless` asks for it so recordings repeat exactly.
+    /// Effects which count frames anyway ignore it
+    fn set_time_step(&mut self, _step: Duration) {}
+    /// Scale load down (entity count, sampling) when frames are over budget,
+    /// 1.0 is full quality as configured, effects without a knob ignore it
+    fn set_quality(&mut self, _quality: f32) {}
+    /// Take options of a reloaded config. State is kept where new options
+    /// allow, otherwise the effect is built again; the screen is cleared by
+    /// the caller like on resize. Effects without options start from scratch
+    fn reconfigure(&mut self, _config: &Config) {
+        self.reset();
+    }
+    /// Simulation state to continue from later, see `snapshot` module.
+    /// `None` means the effect can't be resumed
+    fn snapshot(&self) -> Option<toml::Value> {
+        None
+    }
+    /// Continue from `snapshot` state taken at `screen_size`, the caller
+    /// fits the effect to the current screen with `update_size` afterwards
+    fn restore(
+        &mut self,
+        _state: toml::Value,
+        _screen_size: (u16, u16),
+    ) -> std::result::Result<(), String> {
+        Err("effect has no state to restore".to_string())
+    }
+}
+
+/// Upper bound of the frame rate, effects may ask for less
+pub const MAX_FPS: f64 = 60.0;
+/// Static effects are redrawn at this rate while HUD is shown
+const HUD_MIN_FPS: f64 = 2.0;
+/// How often waiting static effects wake up to check termination flag
+const IDLE_WAKEUP: Duration = Duration::from_millis(250);
+/// Frames the effect worker may compute ahead of the terminal output
+const FRAME_QUEUE: usize = 2;
+/// How long config reload notices stay on screen
+const NOTICE_TIME: Duration = Duration::from_secs(3);
+
+/// What `run_loop` shows besides the effect itself
+#[derive(Debug, Clone, Default)]
+pub struct LoopOptions {
+    /// Stop after this many frames, runs until exit key otherwise
+    pub iterations: Option<usize>,
+    /// Effect name shown in the HUD
+    pub name: String,
+    /// Show the HUD from the first frame, F1 toggles it anyway
+    pub hud: bool,
+    pub seed: Option<u64>,
+    /// Let the quality governor scale effect load to keep the frame rate
+    pub adaptive_quality: bool,
+    /// Where the config came from, effect follows changes of its files
+    pub config: Option<ConfigSource>,
+    /// Colors every cell goes through on its way to the terminal
+    pub theme: Theme,
+    /// Save a screenshot once this many frames are drawn and stop
+    pub screenshot_at: Option<usize>,
+    /// Copy everything written to the terminal into an asciicast file
+    pub record: Option<RecordOptions>,
+    /// Where `w` saves the effect state, a timestamped file otherwise
+    pub snapshot: Option<PathBuf>,
 }
 
-pub fn process_input() -> Result<bool> {
-    if event::poll(Duration::from_millis(10))?
-        && let event::Event::Key(keyevent) = event::read()?
-        && (keyevent
+/// Keys which stop the effect: q, Esc and Ctrl+C
+pub fn is_exit_key(keyevent: &event::KeyEvent) -> bool {
+    *keyevent
+        == event::KeyEvent::new(
+            event::KeyCode::Char('q'),
+            event::KeyModifiers::NONE,
+        )
+        || *keyevent
+            == event::KeyEvent::new(event::KeyCode::Esc, event::KeyModifiers::NONE)
+        || *keyevent
             == event::KeyEvent::new(
-                event::KeyCode::Char('q'),
-                event::KeyModifiers::NONE,
+                event::KeyCode::Char('c'),
+                event::KeyModifiers::CONTROL,
             )
-            || keyevent
-                == event::KeyEvent::new(
-                    event::KeyCode::Esc,
-                    event::KeyModifiers::NONE,
-                )
-            || keyevent
-                == event::KeyEvent::new(
-                    event::KeyCode::Char('c'),
-                    event::KeyModifiers::CONTROL,
-                ))
-    {
-        return Ok(false);
-    }
-    Ok(true)
 }
 
+#[inline]
+pub fn fits_screen(screen_size: (u16, u16), min_size: (u16, u16)) -> bool {
+    screen_size.0 >= min_size.0 && screen_size.1 >= min_size.1
+}
+
+/// Clear screen and print centered notice about too small terminal
+fn draw_too_small<W: Write>(
+    stdout: &mut W,
+    screen_size: (u16, u16),
+    min_size: (u16, u16),
+) -> Result<()> {
+    let (width, height) = screen_size;
+    let lines = [
+        "Terminal too small".to_string(),
+        format!(
+            "need {}x{}, got {}x{}",
+            min_size.0, min_size.1, width, height
+        ),
+    ];
+
+    stdout.queue(terminal::Clear(terminal::ClearType::All))?;
+    let top = height.saturating_sub(lines.len() as u16) / 2;
+    for (index, line) in lines.iter().enumerate() {
+        let y = top + index as u16;
+        if y >= height {
+            break;
+        }
+        let text: String = line.chars().take(width as usize).collect();
+        let x = width.saturating_sub(text.chars().count() as u16) / 2;
+        stdout.queue(cursor::MoveTo(x, y))?;
+        stdout.queue(style::PrintStyledContent(
+            text.with(style::Color::White)
+                .attribute(style::Attribute::Bold),
+        ))?;
+    }
+    Ok(())
+}
+
+/// Queue diff cells into the writer in theme colors, caller is responsible
+/// for flushing
+pub fn draw_diff<W: Write>(
+    stdout: &mut W,
+    diff: &[(usize, usize, Cell)],
+    theme: &Theme,
+) -> Result<()> {
+    for (x, y, cell) in diff.iter() {
+        stdout.queue(cursor::MoveTo(*x as u16, *y as u16))?;
+        stdout.queue(style::PrintStyledContent(theme.style(cell)))?;
+    }
+    Ok(())
+}
+
+/// Run effect for `frames` frames without a terminal, `on_frame` gets each
+/// diff with the time it is due at the effect frame rate. Stops early on a
+/// termination signal.
+pub fn run_headless<TE, F>(
+    effect: &mut TE,
+    frames: usize,
+    mut on_frame: F,
+) -> Result<()>
+where
+    TE: TerminalEffect + ?Sized,
+    F: FnMut(Duration, &[(usize, usize, Cell)]) -> Result<()>,
+{
+    // static effects show one frame per second
+    let frame_rate = match effect.frame_rate().min(MAX_FPS) {
+        rate if rate > 0.0 => rate,
+        _ => 1.0,
+    };
+    effect.set_time_step(Duration::from_secs_f64(1.0 / frame_rate));
+    for frame in 0..frames {
+        if crate::crash::terminate_requested() {
+            break;
+        }
+        let diff = effect.get_diff();
+        on_frame(Duration::from_secs_f64(frame as f64 / frame_rate), &diff)?;
+        effect.update();
+    }
+    Ok(())
+}
+
+/// Save what the effect has drawn next to the current directory, the
+/// returned notice is shown on top of the effect
+fn take_screenshot(
+    screen: &Buffer,
+    options: &LoopOptions,
+    theme: &Theme,
+    metrics: &mut FrameMetrics,
+) -> String {
+    let stem = screenshot::timestamped_stem(&options.name);
+    match screenshot::save(screen, theme, &stem) {
+        Ok(paths) => {
+            metrics.record_screenshot(paths);
+            format!("Screenshot saved\n{}.{{ans,txt,html}}", stem.display())
+        }
+        Err(e) => format!("Screenshot not saved\n{}", e),
+    }
+}
+
+/// Save the effect state for `--resume`, the returned notice is shown on
+/// top of the effect
+fn save_snapshot(
+    state: Option<toml::Value>,
+    options: &LoopOptions,
+    screen_size: (u16, u16),
+) -> String {
+    let Some(state) = state else {
+        return format!("{} can't save its state", options.name);
+    };
+    let path = options
+        .snapshot
+        .clone()
+        .unwrap_or_else(|| snapshot::timestamped_path(&options.name));
+    match Snapshot::new(&options.name, screen_size, state).save(&path) {
+        Ok(()) => format!("State saved\n{}", path.display()),
+        Err(e) => format!("State not saved\n{}", e),
+    }
+}
+
+/// Requests sent from the terminal thread to the effect worker
+enum Command {
+    /// Compute the next frame, with HUD stats when asked
+    Frame {
+        stats: bool,
+    },
+    Resize(u16, u16),
+    Quality(f32),
+    Reconfigure(Box<Config>),
+    /// Answer with `TerminalEffect::snapshot` of the latest frame
+    Snapshot(Sender<Option<toml::Value>>),
+}
+
+/// Frame computed by the effect worker
+struct Frame {
+    /// Number of resizes and reloads seen by the worker, frames drawn
+    /// before them are thrown away by the terminal thread
+    generation: usize,
+    diff: Vec<(usize, usize, Cell)>,
+    stats: Vec<(&'static str, usize)>,
+    frame_rate: f64,
+    diff_us: u64,
+    update_us: u64,
+}
+
+impl Frame {
+    /// Fold a newer frame into this one, cells changed by both are written
+    /// once with the newer value
+    fn merge(&mut self, newer: Frame) {
+        let mut positions: HashMap<(usize, usize), usize> = self
+            .diff
+            .iter()
+            .enumerate()
+            .map(|(index, (x, y, _))| ((*x, *y), index))
+            .collect();
+        for (x, y, cell) in newer.diff {
+            match positions.get(&(x, y)) {
+                Some(index) => self.diff[*index].2 = cell,
+                None => {
+                    positions.insert((x, y), self.diff.len());
+                    self.diff.push((x, y, cell));
+                }
+            }
+        }
+        self.stats = newer.stats;
+        self.frame_rate = newer.frame_rate;
+        self.diff_us += newer.diff_us;
+        self.update_us += newer.update_us;
+    }
+}
+
+/// Worker side of `run_loop`: runs commands in order until the terminal
+/// thread hangs up
+fn render_worker<TE>(
+    effect: &mut TE,
+    commands: Receiver<Command>,
+    frames: SyncSender<Frame>,
+) where
+    TE: TerminalEffect + ?Sized,
+{
+    let mut generation = 0;
+    let mut quality = 1.0;
+    for command in commands {
+        match command {
+            Command::Frame { stats } => {
+                let phase = Instant::now();
+                let diff = effect.get_diff();
+                let diff_us = as_micros(phase.elapsed());
+                let stats = if stats { effect.stats() } else { Vec::new() };
+
+                let phase = Instant::now();
+                effect.update();
+                let frame = Frame {
+                    generation,
+                    diff,
+                    stats,
+                    frame_rate: effect.frame_rate(),
+                    diff_us,
+                    update_us: as_micros(phase.elapsed()),
+                };
+                if frames.send(frame).is_err() {
+                    break;
+                }
+            }
+            Command::Resize(width, height) => {
+                effect.update_size(width, height);
+                generation += 1;
+            }
+            Command::Quality(new_quality) => {
+                quality = new_quality;
+                effect.set_quality(quality);
+            }
+            Command::Reconfigure(config) => {
+                effect.reconfigure(&config);
+                // rebuilt effect starts at full quality
+                if quality < 1.0 {
+                    effect.set_quality(quality);
+                }
+                generation += 1;
+            }
+            Command::Snapshot(reply) => {
+                let _ = reply.send(effect.snapshot());
+            }
+        }
+    }
+}
+
+/// Run effect until exit key or termination signal, returns frame timings.
+///
+/// The effect lives on a worker thread which computes frames ahead, this
+/// thread only reads input and writes to the terminal. When writing falls
+/// behind the frame rate, ready frames are merged and only the latest state
+/// of every cell is written.
 pub fn run_loop<W, TE>(
     stdout: &mut W,
     effect: &mut TE,
-    iterations: Option<usize>,
-) -> Result<f64>
+    options: &LoopOptions,
+) -> Result<FrameMetrics>
 where
     W: Write,
-    TE: TerminalEffect,
+    TE: TerminalEffect + ?Sized,
 {
-    let (width, height) = terminal::size()?;
+    let min_size = effect.min_size();
+    let frame_rate = effect.frame_rate();
+    let (command_tx, command_rx) = mpsc::channel();
+    let (frame_tx, frame_rx) = mpsc::sync_channel(FRAME_QUEUE);
+
+    thread::scope(|scope| {
+        scope.spawn(move || render_worker(effect, command_rx, frame_tx));
+        // both channel ends are dropped on return, worker stops on its own
+        output_loop(stdout, command_tx, frame_rx, min_size, frame_rate, options)
+    })
+}
+
+fn output_loop<W: Write>(
+    stdout: &mut W,
+    commands: Sender<Command>,
+    frames: Receiver<Frame>,
+    min_size: (u16, u16),
+    mut effect_frame_rate: f64,
+    options: &LoopOptions,
+) -> Result<FrameMetrics> {
+    let worker_stopped = || io::Error::other("effect worker stopped");
+    let send =
+        |command: Command| commands.send(command).map_err(|_| worker_stopped());
+
+    let iterations = options.iterations;
+    let (mut width, mut height) = terminal::size()?;
+    let mut too_small = !fits_screen((width, height), min_size);
 
     // #[cfg(test)]
     let mut iters: usize = 0;
 
     let mut is_running = true;
-    let mut frames_per_second = 0.0;
-    let target_frame_duration = Duration::from_secs_f64(1.0 / 60.0_f64);
+    let mut metrics = FrameMetrics::new();
+    let target_fps = effect_frame_rate.min(MAX_FPS);
+    let budget = Duration::from_secs_f64(1.0 / target_fps.max(HUD_MIN_FPS));
+    let mut hud = Hud::new(&options.name, options.seed, target_fps, options.hud);
+    let mut governor = QualityGovernor::new(budget);
+    // what effect has drawn on screen, HUD restores cells from it
+    let mut screen = Buffer::new(width as usize, height as usize);
+    // static effects are drawn only after something has changed
+    let mut redraw = true;
+    // frames asked from the worker and not received yet
+    let mut requested: usize = 0;
+    let mut generation: usize = 0;
+    let mut deadline: Option<Instant> = None;
+    let mut config_source = options.config.clone();
+    let mut theme = options.theme.clone();
+    let mut drawn_frames: usize = 0;
 
     // wrap in buffer due to tests "run_loop_fps_gte_0" failing on CI/CD
     // NOTE: 12/Dec/2023 issue with tests of CI/CD still not resolved
-    let mut buffered_stdout = BufWriter::new(stdout);
+    let cast = match options.record.as_ref() {
+        Some(record) => Some(CastWriter::create(record, (width, height))?),
+        None => None,
+    };
+    let mut buffered_stdout =
+        BufWriter::new(CountingWriter::new(Recorder::new(stdout, cast)));
+    if too_small {
+        draw_too_small(&mut buffered_stdout, (width, height), min_size)?;
+    } else if theme != Theme::default() {
+        theme.clear(&mut buffered_stdout)?;
+    }
 
     // main loop
     while is_running {
-        let started_at: std::time::SystemTime = std::time::SystemTime::now();
-        is_running = process_input()?;
+        let started_at = Instant::now();
+        let bytes_before = buffered_stdout.get_ref().bytes();
+        let mut sample = FrameSample::default();
 
-        #[allow(clippy::single_match)]
-        while event::poll(Duration::from_millis(10))? {
-            match event::read()? {
-                event::Event::Resize(new_width, new_height) => {
-                    // Update size and reset effect
-                    effect.update_size(new_width, new_height);
-                    effect.reset();
-                }
-                _ => {}
-            }
+        let mut frame_rate = effect_frame_rate.min(MAX_FPS);
+        if hud.visible {
+            frame_rate = frame_rate.max(HUD_MIN_FPS);
         }
+        let animated = frame_rate > 0.0;
 
         // draw diff
-        let queue = effect.get_diff();
-        for item in queue.iter() {
-            let (x, y, cell) = item;
-            debug_assert!(*x < width as usize && *y < height as usize);
-            buffered_stdout.queue(cursor::MoveTo(*x as u16, *y as u16))?;
-            buffered_stdout.queue(style::PrintStyledContent(
-                cell.symbol.with(cell.color).attribute(cell.attr),
-            ))?;
+        let mut write_time = Duration::ZERO;
+        let drawn = !too_small && (animated || redraw);
+        if drawn {
+            let stats = hud.visible;
+            let mut frame = loop {
+                if requested == 0 {
+                    send(Command::Frame { stats })?;
+                    requested += 1;
+                }
+                let frame = frames.recv().map_err(|_| worker_stopped())?;
+                requested -= 1;
+                if frame.generation == generation {
+                    break frame;
+                }
+            };
+
+            // frames missed since the deadline are merged into this one,
+            // static effects draw everything computed so far
+            let behind = match deadline {
+                Some(deadline) if animated => {
+                    (started_at.saturating_duration_since(deadline).as_secs_f64()
+                        * frame_rate) as usize
+                }
+                _ => usize::MAX,
+            };
+            while sample.dropped < behind {
+                let Ok(newer) = frames.try_recv() else {
+                    break;
+                };
+                requested -= 1;
+                if newer.generation == generation {
+                    frame.merge(newer);
+                    sample.dropped += 1;
+                }
+            }
+            effect_frame_rate = frame.frame_rate;
+
+            // worker computes next frames while this one is written
+            if animated {
+                while requested < FRAME_QUEUE {
+                    send(Command::Frame { stats })?;
+                    requested += 1;
+                }
+            }
+
+            sample.diff_us = frame.diff_us;
+            sample.update_us = frame.update_us;
+            sample.changed_cells = frame.diff.len();
+            debug_assert!(
+                frame
+                    .diff
+                    .iter()
+                    .all(|(x, y, _)| *x < width as usize && *y < height as usize)
+            );
+            screen.apply_diff(&frame.diff);
+            let queue = hud.compose(&screen, frame.diff, &frame.stats);
+
+            let phase = Instant::now();
+            draw_diff(&mut buffered_stdout, &queue, &theme)?;
+            write_time = phase.elapsed();
+            drawn_frames += 1;
+
+            // static effects won't change until something happens
+            if options
+                .screenshot_at
+                .is_some_and(|at| drawn_frames >= at || !animated)
+            {
+                take_screenshot(&screen, options, &theme, &mut metrics);
+                is_running = false;
+            }
         }
+        redraw = false;
+        let phase = Instant::now();
         buffered_stdout.flush()?;
-        effect.update();
+        sample.write_us = as_micros(write_time + phase.elapsed());
+        sample.bytes = buffered_stdout.get_ref().bytes() - bytes_before;
 
-        // stabilize fps if required
-        let ended_at = std::time::SystemTime::now();
-        let delta = ended_at
-            .duration_since(started_at)
-            .unwrap_or(std::time::Duration::from_secs(0));
-        if delta < target_frame_duration {
-            std::thread::sleep(target_frame_duration - delta);
-        };
+        if options.adaptive_quality && drawn {
+            // next frame is computed while this one is written, the
+            // slower side limits the frame rate
+            let work = Duration::from_micros(
+                (sample.update_us + sample.diff_us).max(sample.write_us),
+            );
+            if let Some(quality) = governor.observe(work, started_at.elapsed()) {
+                send(Command::Quality(quality))?;
+            }
+        }
+        sample.quality = governor.quality();
 
-        // calculate actual frame rate
-        let ended_at = std::time::SystemTime::now();
-        let delta = ended_at
-            .duration_since(started_at)
-            .unwrap_or(std::time::Duration::from_secs(0));
-        frames_per_second = (frames_per_second + (1.0 / delta.as_secs_f64())) / 2.0;
+        // single wait for input until the next frame is due, static effects
+        // and too small screen sleep until something happens
+        deadline = (animated && !too_small)
+            .then(|| started_at + Duration::from_secs_f64(1.0 / frame_rate));
+        let waiting_since = Instant::now();
+        while is_running {
+            if crate::crash::terminate_requested() {
+                is_running = false;
+                break;
+            }
+            if let Some(source) = config_source.as_mut()
+                && let Some(reloaded) = source.poll(Instant::now(), &options.name)
+            {
+                let notice = match reloaded {
+                    Ok(config) => {
+                        theme = config.theme.clone();
+                        send(Command::Reconfigure(Box::new(config)))?;
+                        generation += 1;
+                        if !too_small {
+                            screen = Buffer::new(width as usize, height as usize);
+                            hud.invalidate();
+                            theme.clear(&mut buffered_stdout)?;
+                        }
+                        "Config reloaded".to_string()
+                    }
+                    Err(e) => format!("Config not reloaded\n{}", e),
+                };
+                hud.notify(notice, Instant::now() + NOTICE_TIME);
+                redraw = true;
+            }
+            if hud.expire_notice(Instant::now()) {
+                redraw = true;
+            }
+            let timeout = match deadline {
+                Some(deadline) => {
+                    deadline.saturating_duration_since(Instant::now())
+                }
+                None => IDLE_WAKEUP,
+            };
+            if !event::poll(timeout)? {
+                // idle wakeups count as frames, so `iterations` always ends
+                if deadline.is_some() || iterations.is_some() {
+                    break;
+                }
+                continue;
+            }
+            match event::read()? {
+                event::Event::Resize(new_width, new_height) => {
+                    (width, height) = (new_width, new_height);
+                    buffered_stdout
+                        .get_mut()
+                        .get_mut()
+                        .resize((width, height))?;
+                    screen = Buffer::new(width as usize, height as usize);
+                    hud.invalidate();
+                    redraw = true;
+                    too_small = !fits_screen((width, height), min_size);
+                    if too_small {
+                        // wait for the next resize, effect stays untouched
+                        draw_too_small(
+                            &mut buffered_stdout,
+                            (width, height),
+                            min_size,
+                        )?;
+                        buffered_stdout.flush()?;
+                    } else {
+                        theme.clear(&mut buffered_stdout)?;
+                        send(Command::Resize(new_width, new_height))?;
+                        generation += 1;
+                    }
+                }
+                event::Event::Key(keyevent) if is_exit_key(&keyevent) => {
+                    is_running = false;
+                }
+                event::Event::Key(event::KeyEvent {
+                    code: event::KeyCode::F(1),
+                    kind: event::KeyEventKind::Press,
+                    ..
+                }) => {
+                    hud.toggle();
+                    redraw = true;
+                }
+                event::Event::Key(event::KeyEvent {
+                    code: event::KeyCode::Char('s'),
+                    kind: event::KeyEventKind::Press,
+                    ..
+                }) if !too_small => {
+                    let notice =
+                        take_screenshot(&screen, options, &theme, &mut metrics);
+                    hud.notify(notice, Instant::now() + NOTICE_TIME);
+                    redraw = true;
+                }
+                event::Event::Key(event::KeyEvent {
+                    code: event::KeyCode::Char('w'),
+                    kind: event::KeyEventKind::Press,
+                    ..
+                }) if !too_small => {
+                    let (reply_tx, reply_rx) = mpsc::channel();
+                    send(Command::Snapshot(reply_tx))?;
+                    let state = reply_rx.recv().map_err(|_| worker_stopped())?;
+                    let notice = save_snapshot(state, options, (width, height));
+                    hud.notify(notice, Instant::now() + NOTICE_TIME);
+                    redraw = true;
+                }
+                _ => {}
+            }
+            if !is_running || (deadline.is_none() && redraw) {
+                break;
+            }
+        }
+        sample.input_us = as_micros(waiting_since.elapsed());
+        sample.frame_us = as_micros(started_at.elapsed());
+        hud.record(&sample);
+        metrics.record(sample);
 
         // #[cfg(test)]
         if let Some(iterations) = iterations {
@@ -128,5 +666,83 @@ where
             }
         };
     }
-    Ok(frames_per_second)
+    metrics.finish();
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn cell(symbol: char) -> Cell {
+        Cell::new(symbol, style::Color::Green, style::Attribute::Bold)
+    }
+
+    fn frame(diff: Vec<(usize, usize, Cell)>, diff_us: u64) -> Frame {
+        Frame {
+            generation: 0,
+            diff,
+            stats: Vec::new(),
+            frame_rate: MAX_FPS,
+            diff_us,
+            update_us: 1,
+        }
+    }
+
+    #[test]
+    fn merged_frame_keeps_latest_cells() {
+        let mut merged = frame(vec![(0, 0, cell('a')), (1, 0, cell('b'))], 10);
+        merged.merge(frame(vec![(1, 0, cell('c')), (2, 0, cell('d'))], 5));
+        assert_eq!(
+            merged.diff,
+            vec![(0, 0, cell('a')), (1, 0, cell('c')), (2, 0, cell('d'))]
+        );
+        assert_eq!(merged.diff_us, 15);
+        assert_eq!(merged.update_us, 2);
+    }
+
+    #[test]
+    fn worker_answers_commands_in_order() {
+        let mut effect =
+            crate::registry::create("matrix", &Config::default(), (40, 20))
+                .unwrap();
+        let (command_tx, command_rx) = mpsc::channel();
+        let (frame_tx, frame_rx) = mpsc::sync_channel(FRAME_QUEUE);
+        thread::scope(|scope| {
+            scope.spawn(|| render_worker(effect.as_mut(), command_rx, frame_tx));
+            command_tx.send(Command::Frame { stats: true }).unwrap();
+            command_tx.send(Command::Resize(10, 5)).unwrap();
+            command_tx.send(Command::Frame { stats: false }).unwrap();
+
+            let first = frame_rx.recv().unwrap();
+            assert_eq!(first.generation, 0);
+            assert!(!first.stats.is_empty());
+
+            let second = frame_rx.recv().unwrap();
+            assert_eq!(second.generation, 1);
+            assert!(second.stats.is_empty());
+            assert!(second.diff.iter().all(|(x, y, _)| *x < 10 && *y < 5));
+            drop(command_tx);
+        });
+    }
+
+    #[test]
+    fn headless_runs_repeat_exactly() {
+        let run = || {
+            let mut effect =
+                crate::registry::create("cube", &Config::default(), (40, 20))
+                    .unwrap();
+            let mut frames = Vec::new();
+            run_headless(effect.as_mut(), 20, |_, diff| {
+                frames.push(diff.to_vec());
+                // wall clock would move on between frames
+                thread::sleep(Duration::from_millis(2));
+                Ok(())
+            })
+            .unwrap();
+            frames
+        };
+        assert_eq!(run(), run());
+    }
 }
@@ -1,23 +1,48 @@
 use crate::{
     blank::{BlankOptions, BlankOptionsBuilder},
-    boids::{BoidsOptions, BoidsOptionsBuilder},
+    boids::{BoidsConfig, BoidsOptions},
     constellation::{ConstellationOptions, ConstellationOptionsBuilder},
-    crab::{CrabOptions, CrabOptionsBuilder},
+    crab::{CrabConfig, CrabOptions},
     cube::{CubeOptions, CubeOptionsBuilder},
-    donut::{DonutOptions, DonutOptionsBuilder},
+    donut::{DonutConfig, DonutOptions},
     error::{ConfigError, Result, TartsError},
     fire::{FireOptions, FireOptionsBuilder},
-    life::{ConwayLifeOptions, ConwayLifeOptionsBuilder},
+    life::{ConwayLifeConfig, ConwayLifeOptions},
     maze::{MazeOptions, MazeOptionsBuilder},
+    migrate,
+    palette::{Palette, PaletteChoice},
+    particles::{ParticlesOptions, ParticlesOptionsBuilder},
     pipes::{PipesOptions, PipesOptionsBuilder},
     plasma::{PlasmaOptions, PlasmaOptionsBuilder},
-    rain::digital_rain::{DigitalRainOptions, DigitalRainOptionsBuilder},
+    presets::Presets,
+    rain::digital_rain::{DigitalRainConfig, DigitalRainOptions},
     terrain::{TerrainOptions, TerrainOptionsBuilder},
+    theme::{Theme, ThemeBuilder},
 };
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 
-fn config_path() -> PathBuf {
+/// Project-local config, looked up in the current directory
+const PROJECT_CONFIG: &str = ".tarts.toml";
+
+fn system_config_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let program_data = std::env::var("PROGRAMDATA")
+            .unwrap_or_else(|_| "C:\\ProgramData".to_string());
+        PathBuf::from(program_data).join("tarts.toml")
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        PathBuf::from("/etc/tarts.toml")
+    }
+}
+
+fn user_config_path() -> PathBuf {
     #[cfg(target_os = "windows")]
     {
         let appdata = std::env::var("APPDATA").unwrap_or_else(|_| {
@@ -32,98 +57,334 @@ fn config_path() -> PathBuf {
     }
     #[cfg(not(target_os = "windows"))]
     {
-        directories::BaseDirs::new()
-            .unwrap()
-            .home_dir()
-            .join(".config/tarts.toml")
+        // relative XDG_CONFIG_HOME is invalid per spec and ignored
+        match std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+            Some(dir) if dir.is_absolute() => dir.join("tarts.toml"),
+            _ => directories::BaseDirs::new()
+                .unwrap()
+                .home_dir()
+                .join(".config/tarts.toml"),
+        }
+    }
+}
+
+/// Config files merged in order, keys of later files win
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigPaths {
+    /// System-wide defaults, `/etc/tarts.toml`
+    pub system: PathBuf,
+    /// `--config`, `TARTS_CONFIG` or the user file under the XDG config dir
+    pub user: PathBuf,
+    /// `.tarts.toml` in the current directory
+    pub project: PathBuf,
+    /// User file was given explicitly and must exist
+    pub explicit: bool,
+}
+
+impl ConfigPaths {
+    /// Standard locations, `explicit` (`--config`) beats `TARTS_CONFIG`
+    pub fn discover(explicit: Option<&Path>) -> Self {
+        let explicit = explicit.map(Path::to_path_buf).or_else(|| {
+            std::env::var_os("TARTS_CONFIG")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        });
+        Self {
+            system: system_config_path(),
+            user: explicit.clone().unwrap_or_else(user_config_path),
+            project: PathBuf::from(PROJECT_CONFIG),
+            explicit: explicit.is_some(),
+        }
+    }
+
+    pub fn layers(&self) -> [&Path; 3] {
+        [&self.system, &self.user, &self.project]
+    }
+}
+
+/// Merge `layer` into `base`, tables key by key, other values replaced
+fn merge_tables(base: &mut toml::Table, layer: toml::Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(layer)) => {
+                merge_tables(base, layer)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+// missing sections take builder defaults from `Config::default`
+#[serde(default)]
 pub struct Config {
-    #[serde(default)]
-    pub matrix: DigitalRainOptions,
-    #[serde(default)]
-    pub life: ConwayLifeOptions,
-    #[serde(default)]
+    pub matrix: DigitalRainConfig,
+    pub life: ConwayLifeConfig,
     pub maze: MazeOptions,
-    #[serde(default)]
-    pub boids: BoidsOptions,
-    #[serde(default)]
+    pub boids: BoidsConfig,
     pub blank: BlankOptions,
-    #[serde(default)]
     pub cube: CubeOptions,
-    #[serde(default)]
-    pub crab: CrabOptions,
-    #[serde(default)]
-    pub donut: DonutOptions,
-    #[serde(default)]
+    pub crab: CrabConfig,
+    pub donut: DonutConfig,
     pub pipes: PipesOptions,
-    #[serde(default)]
     pub plasma: PlasmaOptions,
-    #[serde(default)]
     pub fire: FireOptions,
-    #[serde(default)]
     pub terrain: TerrainOptions,
-    #[serde(default)]
     pub constellation: ConstellationOptions,
+    pub particles: ParticlesOptions,
+    /// Colors and background applied over every effect
+    pub theme: Theme,
+    /// User palettes, `[palettes.<name>]` tables with a `colors` list
+    pub palettes: BTreeMap<String, Palette>,
+    /// Built-in presets and `[<effect>.presets.<name>]` tables of the file
+    #[serde(skip)]
+    pub presets: Presets,
 }
 
 impl Config {
     /// Print default config as TOML to stdout (for piping into a file).
     pub fn print_default_config() -> Result<()> {
-        let contents = toml::to_string_pretty(&Config::default())
+        let mut document = toml::Table::try_from(Config::default())
+            .map_err(|e| TartsError::Config(ConfigError::SerializeFormat(e)))?;
+        document.insert(
+            "version".to_string(),
+            toml::Value::Integer(migrate::CURRENT_VERSION),
+        );
+        let contents = toml::to_string_pretty(&document)
             .map_err(|e| TartsError::Config(ConfigError::SerializeFormat(e)))?;
         println!("{}", contents);
         Ok(())
     }
 
-    /// Load config from platform path. Returns the config and a status message.
+    /// Load config layers from standard locations, `explicit` is `--config`.
+    /// Returns the config and a status message listing contributing files.
     /// If no config file exists, returns default config in memory (does NOT write to disk).
-    pub fn load() -> Result<(Self, String)> {
-        let path = config_path();
-        if path.exists() {
-            let contents = std::fs::read_to_string(&path)?;
-            let config = toml::from_str(&contents).map_err(|e| {
-                TartsError::Config(ConfigError::DeserializeFormat(e))
-            })?;
-            Ok((config, format!("Loaded config from {}", path.display())))
+    pub fn load(explicit: Option<&Path>) -> Result<(Self, String)> {
+        Self::load_from(&ConfigPaths::discover(explicit))
+    }
+
+    pub fn load_from(paths: &ConfigPaths) -> Result<(Self, String)> {
+        let mut document = toml::Table::new();
+        let mut loaded = Vec::new();
+        let mut warnings = Vec::new();
+        for path in paths.layers() {
+            let in_file = |source: ConfigError| ConfigError::InFile {
+                path: path.to_path_buf(),
+                source: Box::new(source),
+            };
+            let contents = match std::fs::read_to_string(path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    if paths.explicit && path == paths.user {
+                        return Err(in_file(e.into()).into());
+                    }
+                    continue;
+                }
+                Err(e) => return Err(in_file(e.into()).into()),
+            };
+            let mut layer: toml::Table =
+                toml::from_str(&contents).map_err(|e| in_file(e.into()))?;
+            // every layer is brought to the current layout on its own
+            let notes = migrate::migrate(&mut layer).map_err(in_file)?;
+            if !notes.is_empty() {
+                warnings.push(format!(
+                    "{} uses an old layout ({}), update it with `tarts --migrate-config`",
+                    path.display(),
+                    notes.join(", ")
+                ));
+            }
+            for key in migrate::unknown_keys(&layer) {
+                warnings.push(format!(
+                    "Unknown key `{}` in {} is ignored",
+                    key,
+                    path.display()
+                ));
+            }
+            layer.remove("version");
+            merge_tables(&mut document, layer);
+            loaded.push(path.display().to_string());
+        }
+
+        let config = Config::from_table(document)?;
+        let mut status = if loaded.is_empty() {
+            format!(
+                "No config found at {}, using defaults",
+                paths.user.display()
+            )
         } else {
-            Ok((
-                Config::default(),
-                format!("No config found at {}, using defaults", path.display()),
-            ))
+            format!("Loaded config from {}", loaded.join(", "))
+        };
+        for warning in warnings {
+            status.push_str(&format!("\nWarning: {}", warning));
         }
+        Ok((config, status))
     }
 }
 
 impl Config {
+    /// Validate config document merged over defaults, presets tables are
+    /// taken out of effect sections before deserialization
+    fn from_table(
+        mut document: toml::Table,
+    ) -> std::result::Result<Self, ConfigError> {
+        let mut presets = Presets::builtin();
+        for (effect, section) in document.iter_mut() {
+            let Some(named) = section
+                .as_table_mut()
+                .and_then(|section| section.remove("presets"))
+            else {
+                continue;
+            };
+            let named: BTreeMap<String, toml::Table> =
+                named.try_into().map_err(|_| ConfigError::InvalidValue {
+                    path: format!("{}.presets", effect),
+                    reason: "must be a table of presets".to_string(),
+                })?;
+            presets.insert_all(effect, named, false);
+        }
+        // missing sections and fields keep their defaults
+        let mut merged = toml::Table::try_from(Config::default())?;
+        merge_tables(&mut merged, document);
+
+        let mut config = Config::deserialize(toml::Value::Table(merged))?;
+        config.resolve_palettes();
+        config.validate()?;
+        config.presets = presets;
+        config.check_presets()?;
+        Ok(config)
+    }
+
+    /// Every user preset must apply cleanly over its base section
+    fn check_presets(&self) -> std::result::Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        for (effect, name, preset) in self.presets.iter() {
+            if preset.builtin {
+                continue;
+            }
+            let mut config = self.clone();
+            let failed = match config.apply_overrides(&preset.overrides(effect)) {
+                Ok(()) => continue,
+                Err(ConfigError::Validation(failed)) => failed,
+                Err(e) => vec![e],
+            };
+            errors.extend(failed.into_iter().map(|e| ConfigError::InvalidValue {
+                path: format!("{}.presets.{}", effect, name),
+                reason: e.to_string(),
+            }));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Validation(errors))
+        }
+    }
+
+    /// Merge named preset over the effect section
+    pub fn apply_preset(
+        &mut self,
+        effect: &str,
+        name: &str,
+    ) -> std::result::Result<(), ConfigError> {
+        let preset = self.presets.get(effect, name).ok_or_else(|| {
+            ConfigError::UnknownPreset(format!("{}.{}", effect, name))
+        })?;
+        self.apply_overrides(&preset.overrides(effect))
+    }
+
+    /// Palette options of all effect sections
+    fn palette_choices(&mut self) -> [&mut PaletteChoice; 14] {
+        [
+            &mut self.matrix.palette,
+            &mut self.life.palette,
+            &mut self.maze.palette,
+            &mut self.boids.palette,
+            &mut self.blank.palette,
+            &mut self.cube.palette,
+            &mut self.crab.palette,
+            &mut self.donut.palette,
+            &mut self.pipes.palette,
+            &mut self.plasma.palette,
+            &mut self.fire.palette,
+            &mut self.terrain.palette,
+            &mut self.constellation.palette,
+            &mut self.particles.palette,
+        ]
+    }
+
+    /// Point `palette` options at user palettes, unknown names are left for
+    /// `validate` to report
+    fn resolve_palettes(&mut self) {
+        let user = std::mem::take(&mut self.palettes);
+        for choice in self.palette_choices() {
+            choice.resolve(&user);
+        }
+        self.palettes = user;
+    }
+
+    /// Check values deserialization can't catch: ranges, min/max pairs,
+    /// empty lists. Reports every invalid field at once
+    pub fn validate(&self) -> std::result::Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        self.matrix
+            .validate(&mut Validator::new("matrix", &mut errors));
+        self.life.validate(&mut Validator::new("life", &mut errors));
+        self.maze.validate(&mut Validator::new("maze", &mut errors));
+        self.boids
+            .validate(&mut Validator::new("boids", &mut errors));
+        self.blank
+            .validate(&mut Validator::new("blank", &mut errors));
+        self.cube.validate(&mut Validator::new("cube", &mut errors));
+        self.crab.validate(&mut Validator::new("crab", &mut errors));
+        self.donut
+            .validate(&mut Validator::new("donut", &mut errors));
+        self.pipes
+            .validate(&mut Validator::new("pipes", &mut errors));
+        self.plasma
+            .validate(&mut Validator::new("plasma", &mut errors));
+        self.fire.validate(&mut Validator::new("fire", &mut errors));
+        self.terrain
+            .validate(&mut Validator::new("terrain", &mut errors));
+        self.constellation
+            .validate(&mut Validator::new("constellation", &mut errors));
+        self.particles
+            .validate(&mut Validator::new("particles", &mut errors));
+        self.theme
+            .validate(&mut Validator::new("theme", &mut errors));
+        for (name, palette) in &self.palettes {
+            let section = format!("palettes.{}", name);
+            Validator::new(&section, &mut errors).at_least(
+                "colors",
+                palette.colors.len(),
+                2,
+            );
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Validation(errors))
+        }
+    }
+
+    /// Options section of the effect as TOML, used in diagnostics
+    pub fn effect_options_toml(&self, name: &str) -> String {
+        toml::Value::try_from(self)
+            .ok()
+            .and_then(|value| value.get(name).cloned())
+            .and_then(|section| toml::to_string_pretty(&section).ok())
+            .unwrap_or_default()
+    }
+
     pub fn get_matrix_options(
         &self,
         screen_size: (u16, u16),
     ) -> DigitalRainOptions {
-        let mut options = self.matrix.clone();
-        let (w, h) = screen_size;
-        let area = w as f32 * h as f32;
-        options.drops_range = {
-            let min = (area / 160.0 * options.drops_coeff) as u16;
-            let max = (area / 80.0 * options.drops_coeff) as u16;
-            (min.max(10), max.max(20))
-        };
-        options.speed_range = {
-            let min = ((h as f32 / 20.0 * options.speed_coeff) as u16).max(2);
-            let max = ((h as f32 / 10.0 * options.speed_coeff) as u16).max(16);
-            (min, max)
-        };
-        options
+        self.matrix.to_runtime(screen_size)
     }
 
     pub fn get_life_options(&self, screen_size: (u16, u16)) -> ConwayLifeOptions {
-        let mut options = self.life.clone();
-        let (w, h) = screen_size;
-        options.initial_cells =
-            (w as f32 * h as f32 * 0.15 * options.cells_coeff) as u32;
-        options
+        self.life.to_runtime(screen_size)
     }
 
     pub fn get_maze_options(&self, _screen_size: (u16, u16)) -> MazeOptions {
@@ -131,13 +392,7 @@ impl Config {
     }
 
     pub fn get_boids_options(&self, screen_size: (u16, u16)) -> BoidsOptions {
-        let mut options = self.boids.clone();
-        options.screen_size = screen_size;
-        let (w, h) = screen_size;
-        options.boid_count = ((w as f32 * h as f32 * 0.5 * options.boid_coeff)
-            as u16)
-            .clamp(50, 300);
-        options
+        self.boids.to_runtime(screen_size)
     }
 
     pub fn get_blank_options(&self) -> BlankOptions {
@@ -149,18 +404,11 @@ impl Config {
     }
 
     pub fn get_crab_options(&self, screen_size: (u16, u16)) -> CrabOptions {
-        let mut options = self.crab.clone();
-        let screen_area = screen_size.0 as f32 * screen_size.1 as f32;
-        options.crab_count =
-            (screen_area / 800.0 * options.crab_coeff).clamp(3.0, 15.0) as u16;
-        options
+        self.crab.to_runtime(screen_size)
     }
 
     pub fn get_donut_options(&self, screen_size: (u16, u16)) -> DonutOptions {
-        let mut options = self.donut.clone();
-        let min_dim = screen_size.0.min(screen_size.1) as f32;
-        options.k1 = min_dim * 0.8 * options.k1_coeff;
-        options
+        self.donut.to_runtime(screen_size)
     }
 
     pub fn get_pipes_options(&self) -> PipesOptions {
@@ -182,24 +430,613 @@ impl Config {
     pub fn get_constellation_options(&self) -> ConstellationOptions {
         self.constellation.clone()
     }
+
+    pub fn get_particles_options(&self) -> ParticlesOptions {
+        self.particles.clone()
+    }
+}
+
+/// Single `section.key=value` from the command line, value is parsed as
+/// TOML and falls back to a plain string (`charset=Arrow`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigOverride {
+    pub path: String,
+    pub value: toml::Value,
+}
+
+impl ConfigOverride {
+    pub fn parse(arg: &str) -> std::result::Result<Self, ConfigError> {
+        let (path, raw) = arg
+            .split_once('=')
+            .map(|(path, raw)| (path.trim(), raw.trim()))
+            .filter(|(path, raw)| !path.is_empty() && !raw.is_empty())
+            .ok_or_else(|| ConfigError::InvalidOverride(arg.to_string()))?;
+        let value = toml::from_str::<toml::Table>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(raw.to_string()));
+        Ok(Self {
+            path: path.to_string(),
+            value,
+        })
+    }
+
+    /// Key inside the section of the running effect, `-o drops_coeff=2.5`
+    pub fn parse_for_section(
+        section: &str,
+        arg: &str,
+    ) -> std::result::Result<Self, ConfigError> {
+        Self::parse(&format!("{}.{}", section, arg))
+    }
+}
+
+impl std::fmt::Display for ConfigOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.path, self.value)
+    }
+}
+
+impl Config {
+    /// Apply overrides on top of the loaded config. Keys must exist, values
+    /// go through the same deserialization and validation as the file
+    pub fn apply_overrides(
+        &mut self,
+        overrides: &[ConfigOverride],
+    ) -> std::result::Result<(), ConfigError> {
+        if overrides.is_empty() {
+            return Ok(());
+        }
+        let mut document = toml::Value::try_from(&*self)?;
+        for item in overrides {
+            let slot = item
+                .path
+                .split('.')
+                .try_fold(&mut document, |value, key| value.get_mut(key))
+                .filter(|slot| !slot.is_table())
+                .ok_or_else(|| ConfigError::UnknownKey(item.path.clone()))?;
+            *slot = match (&*slot, &item.value) {
+                // `max_speed=3` means 3.0 for float fields
+                (toml::Value::Float(_), toml::Value::Integer(value)) => {
+                    toml::Value::Float(*value as f64)
+                }
+                (current, value) if current.same_type(value) => value.clone(),
+                (current, value) => {
+                    return Err(ConfigError::InvalidValue {
+                        path: item.path.clone(),
+                        reason: format!(
+                            "must be {}, got {} {}",
+                            current.type_str(),
+                            value.type_str(),
+                            value
+                        ),
+                    });
+                }
+            };
+            // report bad enum variants and chars against the override
+            Config::deserialize(document.clone()).map_err(|e| {
+                ConfigError::InvalidValue {
+                    path: item.path.clone(),
+                    reason: e.message().to_string(),
+                }
+            })?;
+        }
+        let mut config = Config::deserialize(document)?;
+        config.resolve_palettes();
+        config.validate()?;
+        config.presets = std::mem::take(&mut self.presets);
+        *self = config;
+        Ok(())
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
-            matrix: DigitalRainOptionsBuilder::default().build().unwrap(),
-            life: ConwayLifeOptionsBuilder::default().build().unwrap(),
+            matrix: DigitalRainConfig::default(),
+            life: ConwayLifeConfig::default(),
             maze: MazeOptionsBuilder::default().build().unwrap(),
-            boids: BoidsOptionsBuilder::default().build().unwrap(),
+            boids: BoidsConfig::default(),
             blank: BlankOptionsBuilder::default().build().unwrap(),
             cube: CubeOptionsBuilder::default().build().unwrap(),
-            crab: CrabOptionsBuilder::default().build().unwrap(),
-            donut: DonutOptionsBuilder::default().build().unwrap(),
+            crab: CrabConfig::default(),
+            donut: DonutConfig::default(),
             pipes: PipesOptionsBuilder::default().build().unwrap(),
             plasma: PlasmaOptionsBuilder::default().build().unwrap(),
             fire: FireOptionsBuilder::default().build().unwrap(),
             terrain: TerrainOptionsBuilder::default().build().unwrap(),
             constellation: ConstellationOptionsBuilder::default().build().unwrap(),
+            particles: ParticlesOptionsBuilder::default().build().unwrap(),
+            theme: ThemeBuilder::default().build().unwrap(),
+            palettes: BTreeMap::new(),
+            presets: Presets::builtin(),
+        }
+    }
+}
+
+/// Collects invalid values of one config section, checks are named after
+/// what the value must be
+pub struct Validator<'a> {
+    section: String,
+    errors: &'a mut Vec<ConfigError>,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(section: &str, errors: &'a mut Vec<ConfigError>) -> Self {
+        Self {
+            section: section.to_string(),
+            errors,
+        }
+    }
+
+    /// Checks of a table inside the section, `emitters[0]` or `forces`
+    pub fn nested(&mut self, field: &str) -> Validator<'_> {
+        Validator {
+            section: format!("{}.{}", self.section, field),
+            errors: self.errors,
+        }
+    }
+
+    fn fail(&mut self, field: &str, reason: String) {
+        self.errors.push(ConfigError::InvalidValue {
+            path: format!("{}.{}", self.section, field),
+            reason,
+        });
+    }
+
+    /// Greater than zero, NaN fails every check
+    pub fn positive<T>(&mut self, field: &str, value: T)
+    where
+        T: PartialOrd + Default + Display,
+    {
+        if value.partial_cmp(&T::default()) != Some(Ordering::Greater) {
+            self.fail(field, format!("must be positive, got {}", value));
+        }
+    }
+
+    pub fn non_negative<T>(&mut self, field: &str, value: T)
+    where
+        T: PartialOrd + Default + Display,
+    {
+        if matches!(
+            value.partial_cmp(&T::default()),
+            None | Some(Ordering::Less)
+        ) {
+            self.fail(field, format!("must not be negative, got {}", value));
+        }
+    }
+
+    pub fn in_range<T>(&mut self, field: &str, value: T, range: RangeInclusive<T>)
+    where
+        T: PartialOrd + Display,
+    {
+        if !range.contains(&value) {
+            self.fail(
+                field,
+                format!(
+                    "must be {}..={}, got {}",
+                    range.start(),
+                    range.end(),
+                    value
+                ),
+            );
+        }
+    }
+
+    pub fn at_least<T>(&mut self, field: &str, value: T, min: T)
+    where
+        T: PartialOrd + Display,
+    {
+        if matches!(value.partial_cmp(&min), None | Some(Ordering::Less)) {
+            self.fail(field, format!("must be at least {}, got {}", min, value));
+        }
+    }
+
+    /// Lower bound of a min/max pair, may be equal to the upper one
+    pub fn not_greater<T>(&mut self, field: &str, value: T, max_field: &str, max: T)
+    where
+        T: PartialOrd + Display,
+    {
+        if matches!(value.partial_cmp(&max), None | Some(Ordering::Greater)) {
+            self.fail(
+                field,
+                format!(
+                    "must not be greater than {} ({}), got {}",
+                    max_field, max, value
+                ),
+            );
+        }
+    }
+
+    /// Lower bound of a half-open range, which must not be empty
+    pub fn less_than<T>(&mut self, field: &str, value: T, max_field: &str, max: T)
+    where
+        T: PartialOrd + Display,
+    {
+        if value.partial_cmp(&max) != Some(Ordering::Less) {
+            self.fail(
+                field,
+                format!("must be less than {} ({}), got {}", max_field, max, value),
+            );
+        }
+    }
+
+    pub fn finite(&mut self, field: &str, value: f64) {
+        if !value.is_finite() {
+            self.fail(field, format!("must be a finite number, got {}", value));
+        }
+    }
+
+    pub fn not_empty<T>(&mut self, field: &str, values: &[T]) {
+        if values.is_empty() {
+            self.fail(field, "must not be empty".to_string());
+        }
+    }
+
+    pub fn palette(&mut self, field: &str, choice: &PaletteChoice) {
+        if !choice.is_resolved() {
+            self.fail(
+                field,
+                format!(
+                    "unknown palette `{}`, see `tarts --list-palettes`",
+                    choice.name()
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boids::BoidsConfigBuilder;
+    use crate::gradient::Rgb;
+    use crate::theme::Background;
+
+    fn errors(toml: &str) -> Vec<String> {
+        let config: Config = toml::from_str(toml).unwrap();
+        match config.validate() {
+            Ok(()) => Vec::new(),
+            Err(ConfigError::Validation(errors)) => {
+                errors.iter().map(|error| error.to_string()).collect()
+            }
+            Err(other) => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(Config::default().validate().is_ok());
+        let printed = toml::to_string_pretty(&Config::default()).unwrap();
+        assert!(errors(&printed).is_empty());
+    }
+
+    #[test]
+    fn cli_and_library_resolve_options_the_same() {
+        use crate::common::DefaultOptions;
+        use crate::rain::digital_rain::DigitalRain;
+        use crate::{boids::Boids, crab::Crab, donut::Donut, life::ConwayLife};
+
+        let config = Config::default();
+        for (w, h) in [(1, 1), (80, 24), (400, 120)] {
+            assert_eq!(
+                config.get_matrix_options((w, h)),
+                DigitalRain::default_options(w, h)
+            );
+            assert_eq!(
+                config.get_life_options((w, h)),
+                ConwayLife::default_options(w, h)
+            );
+            assert_eq!(
+                config.get_boids_options((w, h)),
+                Boids::default_options(w, h)
+            );
+            assert_eq!(
+                config.get_crab_options((w, h)),
+                Crab::default_options(w, h)
+            );
+            assert_eq!(
+                config.get_donut_options((w, h)),
+                Donut::default_options(w, h)
+            );
+        }
+    }
+
+    #[test]
+    fn missing_sections_take_builder_defaults() {
+        let config: Config = toml::from_str(
+            "[plasma]\ntime_scale = 2.0\n\
+            spatial_scale = 1.0\ncolor_speed = 150.0\n",
+        )
+        .unwrap();
+        assert_eq!(config.plasma.time_scale, 2.0);
+        assert_eq!(config.constellation.star_count, 65);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_values_are_reported_with_field_path() {
+        let mut config = Config::default();
+        config.pipes.line_type = 99;
+        config.donut.luminance_chars.clear();
+        config.terrain.octaves = 0;
+        config.constellation.min_speed = 2.0;
+        config.matrix.drops_coeff = -1.0;
+        config.theme.saturation = -0.5;
+        let printed = toml::to_string_pretty(&config).unwrap();
+
+        assert_eq!(
+            errors(&printed),
+            vec![
+                "matrix.drops_coeff: must be positive, got -1",
+                "donut.luminance_chars: must not be empty",
+                "pipes.line_type: must be 0..=6, got 99",
+                "terrain.octaves: must be at least 1, got 0",
+                "constellation.min_speed: must be less than max_speed (1.5), got 2",
+                "theme.saturation: must not be negative, got -0.5",
+            ]
+        );
+    }
+
+    #[test]
+    fn particle_emitters_are_checked_one_by_one() {
+        let toml = r#"
+            [particles.forces]
+            drag = -1.0
+
+            [[particles.emitters]]
+
+            [[particles.emitters]]
+            speed = [5.0, 2.0]
+            glyphs = ""
+        "#;
+        let Err(ConfigError::Validation(failed)) = from_toml(toml) else {
+            panic!("invalid emitters were accepted");
+        };
+        let failed: Vec<String> = failed.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            failed,
+            vec![
+                "particles.emitters[1].speed[0]: must not be greater than speed[1] (2), got 5",
+                "particles.emitters[1].glyphs: must not be empty",
+                "particles.forces.drag: must not be negative, got -1",
+            ]
+        );
+        let config = from_toml("[[particles.emitters]]\nrate = 5.0").unwrap();
+        assert_eq!(config.particles.emitters.len(), 1);
+        assert_eq!(config.particles.emitters[0].glyphs, "*·•+");
+        assert_eq!(config.particles.max_particles, 1500);
+    }
+
+    #[test]
+    fn boids_speeds_must_be_ordered() {
+        let config = Config {
+            boids: BoidsConfigBuilder::default()
+                .min_speed(3.0)
+                .max_speed(1.0)
+                .build()
+                .unwrap(),
+            ..Default::default()
+        };
+        let error = config.validate().unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Invalid config values:\n  \
+             boids.min_speed: must not be greater than max_speed (1), got 3"
+        );
+    }
+
+    fn from_toml(contents: &str) -> std::result::Result<Config, ConfigError> {
+        Config::from_table(toml::from_str(contents)?)
+    }
+
+    fn apply(overrides: &[&str]) -> std::result::Result<Config, ConfigError> {
+        let overrides: Vec<ConfigOverride> = overrides
+            .iter()
+            .map(|arg| ConfigOverride::parse(arg).unwrap())
+            .collect();
+        let mut config = Config::default();
+        config.apply_overrides(&overrides)?;
+        Ok(config)
+    }
+
+    #[test]
+    fn overrides_are_typed_like_toml() {
+        let config = apply(&[
+            "boids.max_speed=3",
+            "boids.charset=Arrow",
+            "matrix.drops_coeff = 2.5",
+            "donut.luminance_chars=['.', '#']",
+            "fire.use_colors=false",
+            "theme.background='#fdf6e3'",
+        ])
+        .unwrap();
+        let boids = toml::Value::try_from(&config.boids).unwrap();
+        assert_eq!(boids["max_speed"].as_float(), Some(3.0));
+        assert_eq!(boids["charset"].as_str(), Some("Arrow"));
+        assert_eq!(config.matrix.drops_coeff, 2.5);
+        assert_eq!(config.donut.luminance_chars, vec!['.', '#']);
+        assert!(!config.fire.use_colors);
+        assert_eq!(
+            config.theme.background,
+            Background::Color(Rgb::new(253, 246, 227))
+        );
+
+        let option =
+            ConfigOverride::parse_for_section("matrix", "speed_coeff=2").unwrap();
+        assert_eq!(option.path, "matrix.speed_coeff");
+        assert_eq!(option.to_string(), "matrix.speed_coeff=2");
+    }
+
+    #[test]
+    fn bad_overrides_are_rejected() {
+        let message =
+            |overrides: &[&str]| apply(overrides).unwrap_err().to_string();
+        assert_eq!(
+            message(&["boids.max_sped=3"]),
+            "Unknown config key `boids.max_sped`"
+        );
+        assert_eq!(message(&["boids=3"]), "Unknown config key `boids`");
+        assert_eq!(
+            message(&["pipes.num_lines=fast"]),
+            "pipes.num_lines: must be integer, got string \"fast\""
+        );
+        assert!(
+            message(&["boids.charset=Wings"])
+                .starts_with("boids.charset: unknown variant `Wings`")
+        );
+        assert_eq!(
+            message(&["pipes.line_type=99"]),
+            "Invalid config values:\n  pipes.line_type: must be 0..=6, got 99"
+        );
+        assert!(matches!(
+            ConfigOverride::parse("boids.max_speed"),
+            Err(ConfigError::InvalidOverride(_))
+        ));
+    }
+
+    #[test]
+    fn user_presets_are_taken_out_of_sections() {
+        let mut config = from_toml(
+            "[matrix.presets.red]\nspeed_coeff = 2.0\n\n\
+             [matrix.presets.storm]\ndrops_coeff = 5.0\n",
+        )
+        .unwrap();
+        assert!(!config.presets.get("matrix", "storm").unwrap().builtin);
+        assert!(config.presets.get("boids", "arrows").unwrap().builtin);
+
+        config.apply_preset("matrix", "red").unwrap();
+        assert_eq!(config.matrix.drops_coeff, 1.0);
+        assert_eq!(config.matrix.speed_coeff, 2.0);
+        config.apply_preset("matrix", "storm").unwrap();
+        assert_eq!(config.matrix.drops_coeff, 5.0);
+        assert!(config.presets.get("matrix", "red").is_some());
+
+        assert!(matches!(
+            config.apply_preset("matrix", "blue"),
+            Err(ConfigError::UnknownPreset(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_presets_apply_cleanly() {
+        let config = Config::default();
+        for (effect, name, _) in config.presets.iter() {
+            let mut applied = config.clone();
+            applied
+                .apply_preset(effect, name)
+                .unwrap_or_else(|e| panic!("{}.{}: {}", effect, name, e));
         }
     }
+
+    #[test]
+    fn invalid_user_presets_are_reported_with_path() {
+        let message = |toml: &str| from_toml(toml).unwrap_err().to_string();
+        assert_eq!(
+            message("[pipes.presets.wide]\nline_type = 99\n"),
+            "Invalid config values:\n  \
+             pipes.presets.wide: pipes.line_type: must be 0..=6, got 99"
+        );
+        assert_eq!(
+            message("[pipes]\npresets = 1\n"),
+            "pipes.presets: must be a table of presets"
+        );
+    }
+
+    #[test]
+    fn palette_options_resolve_user_and_builtin_palettes() {
+        let config = from_toml(
+            "[palettes.sea]\ncolors = [\"#001020\", \"#20a0ff\"]\n\
+             [fire]\npalette = \"sea\"\n[plasma]\npalette = \"viridis\"\n",
+        )
+        .unwrap();
+        let sea = config.fire.palette.palette().unwrap().clone();
+        assert_eq!(sea, config.palettes["sea"]);
+        assert_eq!(
+            config.plasma.palette.palette(),
+            Palette::builtin("viridis").as_ref()
+        );
+        assert!(config.matrix.palette.palette().is_none());
+
+        // overrides see user palettes too
+        let mut config = config;
+        config
+            .apply_overrides(&[ConfigOverride::parse("boids.palette=sea").unwrap()])
+            .unwrap();
+        assert_eq!(
+            config.get_boids_options((80, 24)).config.palette.palette(),
+            Some(&sea)
+        );
+
+        let message = |toml: &str| from_toml(toml).unwrap_err().to_string();
+        assert_eq!(
+            message("[life]\npalette = \"neon\"\n"),
+            "Invalid config values:\n  \
+             life.palette: unknown palette `neon`, see `tarts --list-palettes`"
+        );
+        assert_eq!(
+            message("[palettes.one]\ncolors = [\"#ffffff\"]\n"),
+            "Invalid config values:\n  \
+             palettes.one.colors: must be at least 2, got 1"
+        );
+        assert!(
+            message("[palettes.bad]\ncolors = [\"red\", \"#000\"]\n")
+                .contains("invalid color `red`, expected #rrggbb")
+        );
+    }
+
+    #[test]
+    fn layers_merge_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let paths = ConfigPaths {
+            system: write(
+                "system.toml",
+                "[matrix]\ndrops_coeff = 2.0\nspeed_coeff = 2.0\n",
+            ),
+            user: dir.path().join("missing.toml"),
+            project: write(
+                "project.toml",
+                "version = 1\n[matrix]\nspeed_coeff = 3.0\nspeed = 4\n\n\
+                 [fire.presets.hot]\nuse_colors = true\n",
+            ),
+            explicit: false,
+        };
+        let (config, status) = Config::load_from(&paths).unwrap();
+        assert_eq!(config.matrix.drops_coeff, 2.0);
+        assert_eq!(config.matrix.speed_coeff, 3.0);
+        assert!(config.presets.get("fire", "hot").is_some());
+        assert_eq!(
+            status,
+            format!(
+                "Loaded config from {}, {}\n\
+                 Warning: Unknown key `matrix.speed` in {} is ignored",
+                paths.system.display(),
+                paths.project.display(),
+                paths.project.display()
+            )
+        );
+
+        // explicit file must exist, errors name the file
+        let explicit = ConfigPaths {
+            explicit: true,
+            ..paths.clone()
+        };
+        let message = Config::load_from(&explicit).unwrap_err().to_string();
+        assert!(message.contains("missing.toml"), "{}", message);
+
+        let broken = ConfigPaths {
+            user: write("broken.toml", "[matrix\n"),
+            ..paths
+        };
+        let message = Config::load_from(&broken).unwrap_err().to_string();
+        assert!(
+            message.contains("broken.toml: Failed to deserialize"),
+            "{}",
+            message
+        );
+    }
 }
@@ -1,5 +1,10 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::governor::scaled_count;
+use crate::gradient::{ColorSpace, Rgb};
+use crate::palette::PaletteChoice;
+use crate::random;
 use crossterm::style;
 use derive_builder::Builder;
 use rand::RngExt;
@@ -8,17 +13,50 @@ use serde::{Deserialize, Serialize};
 const STAR_GLYPHS: [char; 4] = ['○', '◦', '*', '✦'];
 const DT: f64 = 1.0 / 60.0;
 
-const PALETTE: [(u8, u8, u8); 4] = [
-    (110, 150, 240),
-    (170, 110, 230),
-    (90, 210, 230),
-    (190, 150, 255),
+const PALETTE: [Rgb; 4] = [
+    Rgb::new(110, 150, 240),
+    Rgb::new(170, 110, 230),
+    Rgb::new(90, 210, 230),
+    Rgb::new(190, 150, 255),
 ];
 
-const DIM_PALETTE: [(u8, u8, u8); 4] =
-    [(33, 43, 78), (48, 30, 68), (26, 58, 68), (53, 38, 78)];
+const DIM_PALETTE: [Rgb; 4] = [
+    Rgb::new(33, 43, 78),
+    Rgb::new(48, 30, 68),
+    Rgb::new(26, 58, 68),
+    Rgb::new(53, 38, 78),
+];
+
+const BRIGHT: Rgb = Rgb::new(238, 243, 255);
 
-const BRIGHT: (u8, u8, u8) = (238, 243, 255);
+/// Star hues with their dim versions and the twinkle highlight
+struct StarColors {
+    pal: [Rgb; 4],
+    dim: [Rgb; 4],
+    bright: Rgb,
+}
+
+impl StarColors {
+    /// Built-in blues and purples, or hues from the brighter part of the
+    /// palette dimmed to about a third for connections
+    fn new(choice: &PaletteChoice) -> Self {
+        let Some(palette) = choice.palette() else {
+            return Self {
+                pal: PALETTE,
+                dim: DIM_PALETTE,
+                bright: BRIGHT,
+            };
+        };
+        let pal: [Rgb; 4] =
+            std::array::from_fn(|i| palette.sample(0.55 + 0.15 * i as f32));
+        let dim = pal.map(|c| ColorSpace::Rgb.mix(Rgb::new(0, 0, 0), c, 0.3));
+        Self {
+            pal,
+            dim,
+            bright: palette.sample(1.0),
+        }
+    }
+}
 
 #[derive(Builder, Default, Debug, Clone, Serialize, Deserialize)]
 #[builder(public, setter(into))]
@@ -35,9 +73,23 @@ pub struct ConstellationOptions {
     pub min_speed: f64,
     #[builder(default = "1.5")]
     pub max_speed: f64,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
 }
 
-#[derive(Clone, Debug)]
+impl ConstellationOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.at_least("star_count", self.star_count, 1);
+        check.non_negative("connect_radius", self.connect_radius);
+        check.non_negative("min_speed", self.min_speed);
+        check.less_than("min_speed", self.min_speed, "max_speed", self.max_speed);
+        check.palette("palette", &self.palette);
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct Star {
     x: f64,
     y: f64,
@@ -49,12 +101,20 @@ struct Star {
     palette_idx: usize,
 }
 
+/// `snapshot` of the sky, stars with their drift and twinkle phase
+#[derive(Serialize, Deserialize)]
+struct ConstellationState {
+    stars: Vec<Star>,
+}
+
 pub struct Constellation {
     screen_size: (u16, u16),
     options: ConstellationOptions,
     buffer: Buffer,
     stars: Vec<Star>,
     connect_dist: f64,
+    quality: f32,
+    colors: StarColors,
 }
 
 impl TerminalEffect for Constellation {
@@ -96,8 +156,17 @@ impl TerminalEffect for Constellation {
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // stars keep drifting, positions are stretched to the new screen
+        let scale_x = width as f64 / self.screen_size.0.max(1) as f64;
+        let scale_y = height as f64 / self.screen_size.1.max(1) as f64;
+        for star in &mut self.stars {
+            star.x = (star.x * scale_x).clamp(0.0, (width as f64 - 0.01).max(0.0));
+            star.y = (star.y * scale_y).clamp(0.0, (height as f64 - 0.01).max(0.0));
+        }
         self.screen_size = (width, height);
-        self.reset();
+        self.buffer = Buffer::new(width as usize, height as usize);
+        self.connect_dist =
+            Self::calc_connect_dist(width, height, self.options.connect_radius);
     }
 
     fn reset(&mut self) {
@@ -109,22 +178,75 @@ impl TerminalEffect for Constellation {
             self.options.connect_radius,
         );
 
+        let star_count = scaled_count(self.options.star_count, self.quality);
         self.stars.clear();
-        self.stars.reserve(self.options.star_count);
-        for _ in 0..self.options.star_count {
+        self.stars.reserve(star_count);
+        for _ in 0..star_count {
             self.stars.push(self.random_star(true));
         }
     }
+
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        vec![("stars", self.stars.len())]
+    }
+
+    fn set_quality(&mut self, quality: f32) {
+        // new stars fly in from the edges instead of popping up
+        self.quality = quality;
+        let star_count = scaled_count(self.options.star_count, quality);
+        self.stars.truncate(star_count);
+        while self.stars.len() < star_count {
+            self.stars.push(self.random_star(false));
+        }
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (4, 3)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        // stars keep drifting, count and connections follow options
+        self.options = config.get_constellation_options();
+        self.colors = StarColors::new(&self.options.palette);
+        self.set_quality(self.quality);
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
+
+    fn snapshot(&self) -> Option<toml::Value> {
+        let state = ConstellationState {
+            stars: self.stars.clone(),
+        };
+        toml::Value::try_from(state).ok()
+    }
+
+    fn restore(
+        &mut self,
+        state: toml::Value,
+        screen_size: (u16, u16),
+    ) -> Result<(), String> {
+        let state: ConstellationState =
+            state.try_into().map_err(|e| e.to_string())?;
+        self.stars = state.stars;
+        for star in &mut self.stars {
+            star.glyph_idx %= STAR_GLYPHS.len();
+        }
+        self.screen_size = screen_size;
+        self.update_size(screen_size.0, screen_size.1);
+        Ok(())
+    }
 }
 
 impl Constellation {
     pub fn new(options: ConstellationOptions, screen_size: (u16, u16)) -> Self {
         let mut effect = Self {
             screen_size,
+            colors: StarColors::new(&options.palette),
             options,
             buffer: Buffer::new(screen_size.0 as usize, screen_size.1 as usize),
             stars: Vec::new(),
             connect_dist: 0.0,
+            quality: 1.0,
         };
 
         effect.reset();
@@ -136,7 +258,7 @@ impl Constellation {
     }
 
     fn random_star(&self, scattered: bool) -> Star {
-        let mut rng = rand::rng();
+        let mut rng = random::rng();
         let speed =
             rng.random_range(self.options.min_speed..self.options.max_speed);
         let angle = rng.random_range(0.0..(std::f64::consts::PI * 2.0));
@@ -235,20 +357,14 @@ impl Constellation {
 
             let mut color = self.star_color(star.palette_idx, brightness);
             if brightness > 0.9 {
-                let pal = PALETTE[star.palette_idx % PALETTE.len()];
-                color = lerp_color(
-                    style::Color::Rgb {
-                        r: pal.0,
-                        g: pal.1,
-                        b: pal.2,
-                    },
-                    style::Color::Rgb {
-                        r: BRIGHT.0,
-                        g: BRIGHT.1,
-                        b: BRIGHT.2,
-                    },
-                    (brightness - 0.9) * 10.0,
-                );
+                let pal = self.colors.pal[star.palette_idx % PALETTE.len()];
+                color = ColorSpace::Rgb
+                    .mix(
+                        pal,
+                        self.colors.bright,
+                        ((brightness - 0.9) * 10.0) as f32,
+                    )
+                    .into();
             }
 
             let x = star.x.round() as i32;
@@ -300,39 +416,15 @@ impl Constellation {
     }
 
     fn connection_color(&self, palette_idx: usize, alpha: f64) -> style::Color {
-        let dim = DIM_PALETTE[palette_idx % DIM_PALETTE.len()];
-        let pal = PALETTE[palette_idx % PALETTE.len()];
-        lerp_color(
-            style::Color::Rgb {
-                r: dim.0,
-                g: dim.1,
-                b: dim.2,
-            },
-            style::Color::Rgb {
-                r: pal.0,
-                g: pal.1,
-                b: pal.2,
-            },
-            alpha,
-        )
+        let dim = self.colors.dim[palette_idx % DIM_PALETTE.len()];
+        let pal = self.colors.pal[palette_idx % PALETTE.len()];
+        ColorSpace::Rgb.mix(dim, pal, alpha as f32).into()
     }
 
     fn star_color(&self, palette_idx: usize, brightness: f64) -> style::Color {
-        let dim = DIM_PALETTE[palette_idx % DIM_PALETTE.len()];
-        let pal = PALETTE[palette_idx % PALETTE.len()];
-        lerp_color(
-            style::Color::Rgb {
-                r: dim.0,
-                g: dim.1,
-                b: dim.2,
-            },
-            style::Color::Rgb {
-                r: pal.0,
-                g: pal.1,
-                b: pal.2,
-            },
-            brightness,
-        )
+        let dim = self.colors.dim[palette_idx % DIM_PALETTE.len()];
+        let pal = self.colors.pal[palette_idx % PALETTE.len()];
+        ColorSpace::Rgb.mix(dim, pal, brightness as f32).into()
     }
 
     fn in_bounds(&self, x: i32, y: i32) -> bool {
@@ -343,25 +435,6 @@ impl Constellation {
     }
 }
 
-fn lerp_color(a: style::Color, b: style::Color, t: f64) -> style::Color {
-    let (ar, ag, ab) = as_rgb(a);
-    let (br, bg, bb) = as_rgb(b);
-
-    let clamped_t = t.clamp(0.0, 1.0);
-    style::Color::Rgb {
-        r: (ar as f64 + (br as f64 - ar as f64) * clamped_t) as u8,
-        g: (ag as f64 + (bg as f64 - ag as f64) * clamped_t) as u8,
-        b: (ab as f64 + (bb as f64 - ab as f64) * clamped_t) as u8,
-    }
-}
-
-fn as_rgb(color: style::Color) -> (u8, u8, u8) {
-    match color {
-        style::Color::Rgb { r, g, b } => (r, g, b),
-        _ => (255, 255, 255),
-    }
-}
-
 impl DefaultOptions for Constellation {
     type Options = ConstellationOptions;
 
@@ -1,8 +1,11 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::{Palette, PaletteChoice};
+use crate::random;
 use crossterm::style;
 use derive_builder::Builder;
-use rand::RngExt;
+use rand::{Rng, RngExt, rngs::SmallRng};
 use serde::{Deserialize, Serialize};
 use std::sync::LazyLock;
 
@@ -65,13 +68,10 @@ struct CrabEntity {
     frame_height: usize,  // Cached frame height
 }
 
-#[derive(Builder, Default, Debug, Clone, Serialize, Deserialize)]
+/// User-facing settings, `[crab]` section of `tarts.toml`
+#[derive(Builder, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[builder(public, setter(into))]
-pub struct CrabOptions {
-    #[builder(default = "5")]
-    #[serde(skip)]
-    pub crab_count: u16,
-
+pub struct CrabConfig {
     #[builder(default = "0.2")]
     pub animation_speed: f32,
 
@@ -83,6 +83,46 @@ pub struct CrabOptions {
 
     #[builder(default = "1.0")]
     pub crab_coeff: f32,
+
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+/// Runtime options resolved from `CrabConfig` for a screen size
+#[derive(Builder, Debug, Clone, PartialEq)]
+#[builder(public, setter(into))]
+pub struct CrabOptions {
+    pub crab_count: u16,
+    #[builder(default)]
+    pub config: CrabConfig,
+}
+
+impl Default for CrabConfig {
+    fn default() -> Self {
+        CrabConfigBuilder::default().build().unwrap()
+    }
+}
+
+impl CrabConfig {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.positive("crab_coeff", self.crab_coeff);
+        check.non_negative("animation_speed", self.animation_speed);
+        check.in_range("clap_chance", self.clap_chance, 0.0..=1.0);
+        check.non_negative("movement_speed", self.movement_speed);
+        check.palette("palette", &self.palette);
+    }
+
+    /// One crab per 800 cells, 3 to 15 of them
+    pub fn to_runtime(&self, screen_size: (u16, u16)) -> CrabOptions {
+        let screen_area = screen_size.0 as f32 * screen_size.1 as f32;
+        CrabOptions {
+            crab_count: (screen_area / 800.0 * self.crab_coeff).clamp(3.0, 15.0)
+                as u16,
+            config: self.clone(),
+        }
+    }
 }
 
 pub struct Crab {
@@ -90,7 +130,7 @@ pub struct Crab {
     options: CrabOptions,
     buffer: Buffer,
     crabs: Vec<CrabEntity>,
-    rng: rand::prelude::ThreadRng,
+    rng: SmallRng,
     frame_timer: f32,
 }
 
@@ -98,7 +138,8 @@ impl CrabEntity {
     fn new(
         position: (f32, f32),
         velocity: (f32, f32),
-        rng: &mut rand::prelude::ThreadRng,
+        palette: Option<&Palette>,
+        rng: &mut impl Rng,
     ) -> Self {
         // Determine initial direction based on velocity
         let direction = if velocity.0 >= 0.0 {
@@ -107,11 +148,15 @@ impl CrabEntity {
             Direction::Left
         };
 
-        // Random color with predominantly red tint for crabs
-        let color = style::Color::Rgb {
-            r: rng.random_range(200..=255),
-            g: rng.random_range(50..=150),
-            b: rng.random_range(50..=100),
+        // Random color with predominantly red tint for crabs, or from the
+        // brighter half of the palette
+        let color = match palette {
+            Some(palette) => palette.color(rng.random_range(0.5..=1.0)),
+            None => style::Color::Rgb {
+                r: rng.random_range(200..=255),
+                g: rng.random_range(50..=150),
+                b: rng.random_range(50..=100),
+            },
         };
 
         // Calculate frame dimensions from the first frame
@@ -169,7 +214,7 @@ impl CrabEntity {
         animation_speed: f32,
         movement_speed: f32,
         clap_chance: f32,
-        rng: &mut rand::prelude::ThreadRng,
+        rng: &mut impl Rng,
     ) {
         // Update position based on velocity
         self.position.0 += self.velocity.0 * movement_speed * dt;
@@ -300,9 +345,9 @@ impl TerminalEffect for Crab {
             crab.update(
                 dt,
                 self.screen_size,
-                self.options.animation_speed,
-                self.options.movement_speed,
-                self.options.clap_chance,
+                self.options.config.animation_speed,
+                self.options.config.movement_speed,
+                self.options.config.clap_chance,
                 &mut self.rng,
             );
         }
@@ -312,17 +357,48 @@ impl TerminalEffect for Crab {
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // crabs stay where they were relative to the screen
+        let scale_x = width as f32 / self.screen_size.0.max(1) as f32;
+        let scale_y = height as f32 / self.screen_size.1.max(1) as f32;
+        for crab in &mut self.crabs {
+            crab.position.0 *= scale_x;
+            crab.position.1 *= scale_y;
+        }
         self.screen_size = (width, height);
+        self.buffer = Buffer::new(width as usize, height as usize);
     }
 
     fn reset(&mut self) {
         *self = Self::new(self.options.clone(), self.screen_size);
     }
+
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        vec![("crabs", self.crabs.len())]
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        // single crab frame is 15x4
+        (16, 5)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        let options = config.get_crab_options(self.screen_size);
+        // crabs keep their colors, new palette needs new crabs
+        if options.crab_count != self.options.crab_count
+            || options.config.palette != self.options.config.palette
+        {
+            *self = Self::new(options, self.screen_size);
+            return;
+        }
+        self.options = options;
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
 }
 
 impl Crab {
     pub fn new(options: CrabOptions, screen_size: (u16, u16)) -> Self {
-        let mut rng = rand::rng();
+        let mut rng: SmallRng = random::rng();
         let buffer = Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
 
         let width = screen_size.0 as f32;
@@ -343,14 +419,27 @@ impl Crab {
                 rng.random_range(-0.5..0.5),
             );
 
-            crabs.push(CrabEntity::new(position, velocity, &mut rng));
+            crabs.push(CrabEntity::new(
+                position,
+                velocity,
+                options.config.palette.palette(),
+                &mut rng,
+            ));
         }
 
         // ensure crabs won't start to close to each other
+        // on small screens there may be no room, so give up after some attempts
         let min_distance_squared = 100.0; // Adjust based on crab size
+        let mut attempts = 0;
         let mut i = 0;
         while i < crabs.len() {
             let mut repositioned = false;
+            attempts += 1;
+            if attempts > 100 {
+                attempts = 0;
+                i += 1;
+                continue;
+            }
 
             for j in 0..i {
                 let dx = crabs[i].position.0 - crabs[j].position.0;
@@ -367,6 +456,7 @@ impl Crab {
             }
 
             if !repositioned {
+                attempts = 0;
                 i += 1; // Only advance if no repositioning was needed
             }
         }
@@ -400,11 +490,11 @@ impl Crab {
                     // Trigger special animation for both crabs
                     self.crabs[i].is_special = true;
                     self.crabs[i].special_timer =
-                        self.options.animation_speed * 5.0;
+                        self.options.config.animation_speed * 5.0;
 
                     self.crabs[j].is_special = true;
                     self.crabs[j].special_timer =
-                        self.options.animation_speed * 5.0;
+                        self.options.config.animation_speed * 5.0;
 
                     // Reverse directions
                     self.crabs[i].velocity.0 = -self.crabs[i].velocity.0;
@@ -436,16 +526,6 @@ impl DefaultOptions for Crab {
     type Options = CrabOptions;
 
     fn default_options(width: u16, height: u16) -> Self::Options {
-        // Adjust crab count based on screen size
-        let screen_area = width as f32 * height as f32;
-        let crab_count = (screen_area / 800.0).clamp(3.0, 15.0) as u16;
-
-        CrabOptionsBuilder::default()
-            .crab_count(crab_count)
-            .animation_speed(0.2)
-            .movement_speed(5.0)
-            .clap_chance(0.05)
-            .build()
-            .unwrap()
+        CrabConfig::default().to_runtime((width, height))
     }
 }
@@ -1,3 +1,5 @@
 pub mod effect;
 #[allow(unused)]
-pub use effect::{Crab, CrabOptions, CrabOptionsBuilder};
+pub use effect::{
+    Crab, CrabConfig, CrabConfigBuilder, CrabOptions, CrabOptionsBuilder,
+};
@@ -0,0 +1,216 @@
+//! Terminal restoration for abnormal exits.
+//!
+//! Release builds use `panic = "abort"`, so destructors (and `TerminalGuard`)
+//! never run when something panics. The panic hook installed here restores
+//! the terminal first, then writes a crash report and prints the panic
+//! message. Termination signals only raise a flag, the main loop notices it
+//! and exits through the regular cleanup path.
+use crossterm::{cursor, execute, terminal};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What was running at the moment of crash
+#[derive(Debug, Clone, Default)]
+pub struct CrashContext {
+    pub effect: String,
+    pub options: String,
+    pub screen_size: (u16, u16),
+}
+
+static CONTEXT: Mutex<Option<CrashContext>> = Mutex::new(None);
+/// Raw mode and alternate screen are on, the panic hook leaves them
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+static TERMINATING: LazyLock<Arc<AtomicBool>> =
+    LazyLock::new(|| Arc::new(AtomicBool::new(false)));
+static SIGNAL: LazyLock<Arc<AtomicUsize>> =
+    LazyLock::new(|| Arc::new(AtomicUsize::new(0)));
+
+/// Remember running effect to include it into crash report
+pub fn set_context(effect: &str, options: String, screen_size: (u16, u16)) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        *context = Some(CrashContext {
+            effect: effect.to_string(),
+            options,
+            screen_size,
+        });
+    }
+}
+
+/// Mark the terminal as taken over by an effect or released again, the
+/// panic hook leaves stdout alone while it is not
+pub fn set_terminal_active(active: bool) {
+    TERMINAL_ACTIVE.store(active, Ordering::Relaxed);
+}
+
+/// Best effort to leave terminal in usable state, errors are ignored
+pub fn restore_terminal() {
+    restore_terminal_to(&mut io::stdout());
+}
+
+fn restore_terminal_to(out: &mut impl Write) {
+    let _ = execute!(
+        out,
+        cursor::Show,
+        terminal::Clear(terminal::ClearType::All),
+        terminal::LeaveAlternateScreen,
+    );
+    let _ = terminal::disable_raw_mode();
+    let _ = out.flush();
+}
+
+/// Restore the terminal only while an effect has it, so panics in exports
+/// and streams don't write escape sequences into files and pipes
+fn restore_active_terminal(out: &mut impl Write) {
+    if TERMINAL_ACTIVE.swap(false, Ordering::Relaxed) {
+        restore_terminal_to(out);
+    }
+}
+
+/// Restore terminal and write crash report before default panic message
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_active_terminal(&mut io::stdout());
+        default_hook(info);
+
+        let context = CONTEXT
+            .lock()
+            .ok()
+            .and_then(|context| context.clone())
+            .unwrap_or_default();
+        let report = format_report(&info.to_string(), &context);
+        match write_report(&report) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+    }));
+}
+
+/// Route SIGTERM, SIGHUP and SIGINT into the flag checked by the main loop,
+/// second signal terminates process immediately
+#[cfg(unix)]
+pub fn install_signal_handlers() -> io::Result<()> {
+    use signal_hook::consts::signal::{SIGHUP, SIGINT, SIGTERM};
+    use signal_hook::flag;
+
+    for signal in [SIGTERM, SIGHUP, SIGINT] {
+        flag::register_conditional_shutdown(
+            signal,
+            128 + signal,
+            Arc::clone(&TERMINATING),
+        )?;
+        flag::register(signal, Arc::clone(&TERMINATING))?;
+        flag::register_usize(signal, Arc::clone(&SIGNAL), signal as usize)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn install_signal_handlers() -> io::Result<()> {
+    Ok(())
+}
+
+/// True once termination signal arrived
+pub fn terminate_requested() -> bool {
+    TERMINATING.load(Ordering::Relaxed)
+}
+
+/// Number of the signal which requested termination, if any
+pub fn received_signal() -> Option<i32> {
+    match SIGNAL.load(Ordering::Relaxed) {
+        0 => None,
+        signal => Some(signal as i32),
+    }
+}
+
+fn format_report(message: &str, context: &CrashContext) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let effect = if context.effect.is_empty() {
+        "<none>"
+    } else {
+        context.effect.as_str()
+    };
+
+    format!(
+        "tarts {} crash report\n\
+         timestamp: {}\n\
+         effect: {}\n\
+         terminal size: {}x{}\n\
+         panic: {}\n\
+         \n\
+         [options]\n\
+         {}\n\
+         \n\
+         [backtrace]\n\
+         {}\n",
+        env!("CARGO_PKG_VERSION"),
+        timestamp,
+        effect,
+        context.screen_size.0,
+        context.screen_size.1,
+        message,
+        context.options.trim_end(),
+        std::backtrace::Backtrace::force_capture(),
+    )
+}
+
+fn report_dir() -> PathBuf {
+    directories::BaseDirs::new()
+        .map(|dirs| dirs.cache_dir().join("tarts"))
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+fn write_report(report: &str) -> io::Result<PathBuf> {
+    let dir = report_dir();
+    std::fs::create_dir_all(&dir)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = dir.join(format!("crash-{}.log", timestamp));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_contains_context() {
+        let context = CrashContext {
+            effect: "life".to_string(),
+            options: "cells_coeff = 1.0\n".to_string(),
+            screen_size: (80, 24),
+        };
+        let report = format_report("boom at src/life/effect.rs", &context);
+        assert!(report.contains("effect: life"));
+        assert!(report.contains("terminal size: 80x24"));
+        assert!(report.contains("panic: boom at src/life/effect.rs"));
+        assert!(report.contains("cells_coeff = 1.0"));
+    }
+
+    #[test]
+    fn hook_leaves_inactive_terminal_alone() {
+        let mut out = Vec::new();
+        restore_active_terminal(&mut out);
+        assert!(out.is_empty());
+
+        set_terminal_active(true);
+        restore_active_terminal(&mut out);
+        assert!(!out.is_empty());
+        assert!(!TERMINAL_ACTIVE.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn report_without_context() {
+        let report = format_report("boom", &CrashContext::default());
+        assert!(report.contains("effect: <none>"));
+    }
+}
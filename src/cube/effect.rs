@@ -1,9 +1,11 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::PaletteChoice;
 use crossterm::style;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Represents a 3D point in space
 #[derive(Clone, Copy, Debug)]
@@ -41,6 +43,18 @@ pub struct CubeOptions {
     pub distance: f32,
     #[builder(default = "true")]
     pub use_braille: bool,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+impl CubeOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.positive("cube_size", self.cube_size);
+        check.positive("distance", self.distance);
+        check.palette("palette", &self.palette);
+    }
 }
 
 pub struct Cube {
@@ -50,7 +64,11 @@ pub struct Cube {
     vertices: Vec<Point3D>,
     edges: Vec<Edge>,
     rotation: (f32, f32, f32),
-    start_time: Instant,
+    /// Seconds of animation since `started_at`
+    elapsed: f32,
+    started_at: Instant,
+    /// Seconds per frame without a terminal, so recordings match
+    time_step: Option<f32>,
 }
 
 impl TerminalEffect for Cube {
@@ -78,19 +96,51 @@ impl TerminalEffect for Cube {
     }
 
     fn update(&mut self) {
-        // Update rotation based on elapsed time
-        let elapsed = self.start_time.elapsed().as_secs_f32();
-        self.rotation.0 = elapsed * self.options.rotation_speed_x;
-        self.rotation.1 = elapsed * self.options.rotation_speed_y;
-        self.rotation.2 = elapsed * self.options.rotation_speed_z;
+        self.elapsed = match self.time_step {
+            Some(step) => self.elapsed + step,
+            None => self.started_at.elapsed().as_secs_f32(),
+        };
+        self.rotation.0 = self.elapsed * self.options.rotation_speed_x;
+        self.rotation.1 = self.elapsed * self.options.rotation_speed_y;
+        self.rotation.2 = self.elapsed * self.options.rotation_speed_z;
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // rotation goes on, projection uses screen size on every frame
         self.screen_size = (width, height);
+        self.buffer = Buffer::new(width as usize, height as usize);
     }
 
     fn reset(&mut self) {
-        *self = Self::new(self.options.clone(), self.screen_size);
+        *self = Self {
+            time_step: self.time_step,
+            ..Self::new(self.options.clone(), self.screen_size)
+        };
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (10, 6)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        let options = config.get_cube_options();
+        if options.cube_size != self.options.cube_size {
+            // vertices are built from the size, rotation goes on
+            *self = Self {
+                elapsed: self.elapsed,
+                started_at: self.started_at,
+                time_step: self.time_step,
+                ..Self::new(options, self.screen_size)
+            };
+            return;
+        }
+        self.options = options;
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
+
+    fn set_time_step(&mut self, step: Duration) {
+        self.time_step = Some(step.as_secs_f32());
     }
 }
 
@@ -169,7 +219,9 @@ impl Cube {
             vertices,
             edges,
             rotation: (0.0, 0.0, 0.0),
-            start_time: Instant::now(),
+            elapsed: 0.0,
+            started_at: Instant::now(),
+            time_step: None,
         }
     }
 
@@ -210,6 +262,14 @@ impl Cube {
             .collect()
     }
 
+    /// Edges are green or the brightest color of the palette
+    fn line_color(&self) -> style::Color {
+        match self.options.palette.palette() {
+            Some(palette) => palette.color(1.0),
+            None => style::Color::Green,
+        }
+    }
+
     // Project a 3D point to 2D screen coordinates
     fn project(&self, p: Point3D) -> Point2D {
         let distance = self.options.distance;
@@ -285,7 +345,7 @@ impl Cube {
                 buffer.set(
                     x0 as usize,
                     y0 as usize,
-                    Cell::new('█', style::Color::Green, style::Attribute::Bold),
+                    Cell::new('█', self.line_color(), style::Attribute::Bold),
                 );
             }
 
@@ -400,7 +460,7 @@ impl Cube {
                     y as usize,
                     Cell::new(
                         braille_char,
-                        style::Color::Green,
+                        self.line_color(),
                         style::Attribute::Bold,
                     ),
                 );
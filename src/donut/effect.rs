@@ -1,12 +1,15 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::PaletteChoice;
 use crossterm::style;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
-#[derive(Builder, Default, Debug, Clone, Serialize, Deserialize)]
+/// User-facing settings, `[donut]` section of `tarts.toml`
+#[derive(Builder, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[builder(public, setter(into))]
-pub struct DonutOptions {
+pub struct DonutConfig {
     #[builder(default = "1.0")]
     pub inner_radius: f32,
     #[builder(default = "2.0")]
@@ -17,15 +20,51 @@ pub struct DonutOptions {
     pub rotation_speed_b: f32,
     #[builder(default = "5.5")]
     pub distance: f32,
-    #[builder(default = "25.0")]
-    #[serde(skip)]
-    pub k1: f32,
     #[builder(default = "1.0")]
     pub k1_coeff: f32,
     #[builder(
         default = "vec!['.', ',', '-', '~', ':', ';', '=', '!', '*', '#', '$', '@']"
     )]
     pub luminance_chars: Vec<char>,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+/// Runtime options resolved from `DonutConfig` for a screen size
+#[derive(Builder, Debug, Clone, PartialEq)]
+#[builder(public, setter(into))]
+pub struct DonutOptions {
+    /// Projection scale, donut takes most of the smaller screen side
+    pub k1: f32,
+    #[builder(default)]
+    pub config: DonutConfig,
+}
+
+impl Default for DonutConfig {
+    fn default() -> Self {
+        DonutConfigBuilder::default().build().unwrap()
+    }
+}
+
+impl DonutConfig {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.positive("inner_radius", self.inner_radius);
+        check.positive("outer_radius", self.outer_radius);
+        check.positive("distance", self.distance);
+        check.positive("k1_coeff", self.k1_coeff);
+        check.not_empty("luminance_chars", &self.luminance_chars);
+        check.palette("palette", &self.palette);
+    }
+
+    pub fn to_runtime(&self, screen_size: (u16, u16)) -> DonutOptions {
+        let min_dim = screen_size.0.min(screen_size.1) as f32;
+        DonutOptions {
+            k1: min_dim * 0.8 * self.k1_coeff,
+            config: self.clone(),
+        }
+    }
 }
 
 pub struct Donut {
@@ -49,17 +88,32 @@ impl TerminalEffect for Donut {
     }
 
     fn update(&mut self) {
-        self.rotation_a += self.options.rotation_speed_a;
-        self.rotation_b += self.options.rotation_speed_b;
+        self.rotation_a += self.options.config.rotation_speed_a;
+        self.rotation_b += self.options.config.rotation_speed_b;
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // k1 is derived from the smaller screen side, keep donut proportional
+        let old_dim = self.screen_size.0.min(self.screen_size.1).max(1) as f32;
+        let new_dim = width.min(height).max(1) as f32;
+        self.options.k1 *= new_dim / old_dim;
         self.screen_size = (width, height);
+        self.buffer = Buffer::new(width as usize, height as usize);
     }
 
     fn reset(&mut self) {
         *self = Self::new(self.options.clone(), self.screen_size);
     }
+
+    fn min_size(&self) -> (u16, u16) {
+        (10, 6)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        self.options = config.get_donut_options(self.screen_size);
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
 }
 
 impl Donut {
@@ -167,16 +221,16 @@ impl Donut {
                 let cos_phi = phi.cos();
 
                 // Compute the x,y coordinate of the circle before revolving
-                let circle_x = self.options.outer_radius
-                    + self.options.inner_radius * cos_theta;
-                let circle_y = self.options.inner_radius * sin_theta;
+                let circle_x = self.options.config.outer_radius
+                    + self.options.config.inner_radius * cos_theta;
+                let circle_y = self.options.config.inner_radius * sin_theta;
 
                 // Final 3D (x,y,z) coordinate after rotations
                 let x = circle_x * (cos_b * cos_phi + sin_a * sin_b * sin_phi)
                     - circle_y * cos_a * sin_b;
                 let y = circle_x * (sin_b * cos_phi - sin_a * cos_b * sin_phi)
                     + circle_y * cos_a * cos_b;
-                let z = self.options.distance
+                let z = self.options.config.distance
                     + cos_a * circle_x * sin_phi
                     + circle_y * sin_a;
                 let z_inv = 1.0 / z;
@@ -196,8 +250,8 @@ impl Donut {
 
                 if l > 0.0 {
                     let luminance_index = ((l * 8.0) as usize)
-                        .min(self.options.luminance_chars.len() - 1);
-                    let c = self.options.luminance_chars[luminance_index];
+                        .min(self.options.config.luminance_chars.len() - 1);
+                    let c = self.options.config.luminance_chars[luminance_index];
 
                     // Check bounds
                     if x_proj < width && y_proj < height {
@@ -211,17 +265,26 @@ impl Donut {
             }
         }
 
+        let luminance_chars_len = self.options.config.luminance_chars.len();
         for y in 0..height {
             for x in 0..width {
                 let idx = y * width + x;
                 if output[idx] != ' ' {
                     let luminance_index = self
                         .options
+                        .config
                         .luminance_chars
                         .iter()
                         .position(|&r| r == output[idx])
                         .unwrap_or(0);
-                    let color = colors[luminance_index % colors.len()];
+                    let color = match self.options.config.palette.palette() {
+                        Some(palette) => palette.color(
+                            luminance_index as f32
+                                / luminance_chars_len.saturating_sub(1).max(1)
+                                    as f32,
+                        ),
+                        None => colors[luminance_index % colors.len()],
+                    };
                     buffer.set(
                         x,
                         y,
@@ -237,17 +300,6 @@ impl DefaultOptions for Donut {
     type Options = DonutOptions;
 
     fn default_options(width: u16, height: u16) -> Self::Options {
-        DonutOptionsBuilder::default()
-            .inner_radius(1.0)
-            .outer_radius(2.0)
-            .rotation_speed_a(0.07)
-            .rotation_speed_b(0.03)
-            .distance(5.5)
-            .k1((width.min(height) as f32) * 0.8)
-            .luminance_chars(vec![
-                '.', ',', '-', '~', ':', ';', '=', '!', '*', '#', '$', '@',
-            ])
-            .build()
-            .unwrap()
+        DonutConfig::default().to_runtime((width, height))
     }
 }
@@ -1,3 +1,5 @@
 pub mod effect;
 #[allow(unused)]
-pub use effect::{Donut, DonutOptions, DonutOptionsBuilder};
+pub use effect::{
+    Donut, DonutConfig, DonutConfigBuilder, DonutOptions, DonutOptionsBuilder,
+};
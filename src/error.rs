@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 pub type Result<T> = std::result::Result<T, TartsError>;
 
 #[derive(Debug, thiserror::Error)]
@@ -19,6 +21,43 @@ pub enum ConfigError {
     #[error("Failed to deserialize config: {0}")]
     DeserializeFormat(#[from] toml::de::Error),
 
+    /// Error of one config layer, `path` is the file it came from
+    #[error("{}: {source}", .path.display())]
+    InFile {
+        path: PathBuf,
+        source: Box<ConfigError>,
+    },
+
     #[error("Failed to serialize config: {0}")]
     SerializeFormat(#[from] toml::ser::Error),
+
+    /// Value the effect can't work with, `path` is `section.field`
+    #[error("{path}: {reason}")]
+    InvalidValue { path: String, reason: String },
+
+    #[error("Invalid override `{0}`, expected section.key=value")]
+    InvalidOverride(String),
+
+    #[error(
+        "Config version {0} is newer than supported {supported}, update tarts",
+        supported = crate::migrate::CURRENT_VERSION
+    )]
+    UnsupportedVersion(i64),
+
+    #[error("Unknown config key `{0}`")]
+    UnknownKey(String),
+
+    #[error("Unknown preset `{0}`, see `tarts --list-presets <EFFECT>`")]
+    UnknownPreset(String),
+
+    #[error("Invalid config values:\n{}", list_errors(.0))]
+    Validation(Vec<ConfigError>),
+}
+
+fn list_errors(errors: &[ConfigError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("  {}", error))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
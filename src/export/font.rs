@@ -0,0 +1,495 @@
+//! Embedded bitmap font.
+//!
+//! Printable ASCII comes from a classic 5x7 font scaled to the cell.
+//! Braille, block elements, box drawing, arrows and a few dots and circles
+//! are drawn from their geometry at the cell size, so they stay sharp and
+//! join neighbours like in a terminal. Anything else (katakana of Matrix,
+//! for one) gets a pattern picked by its code point: not the real glyph,
+//! but different symbols stay different.
+
+/// Glyphs 0x20..=0x7e, five columns each, bit 0 is the top row
+const ASCII: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // )
+    [0x08, 0x2a, 0x1c, 0x2a, 0x08], // *
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // 0
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // D
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7f, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3e, 0x41, 0x49, 0x49, 0x7a], // G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // H
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // J
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f], // M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // N
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // T
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // V
+    [0x3f, 0x40, 0x38, 0x40, 0x3f], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7f, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7f, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // f
+    [0x0c, 0x52, 0x52, 0x52, 0x3e], // g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // j
+    [0x7f, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // l
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // t
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
+
+/// Glyphs sit in a 6x9 grid: one column of spacing on the right, one row
+/// above and below
+const GRID: (usize, usize) = (6, 9);
+
+/// Line weights of box drawing arms
+const LIGHT: u8 = 1;
+const HEAVY: u8 = 2;
+const DOUBLE: u8 = 3;
+
+/// Pixels of `symbol` in a `width` x `height` cell, row by row
+pub fn glyph(symbol: char, width: usize, height: usize) -> Vec<bool> {
+    let shape = Shape::of(symbol);
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            pixels.push(shape.covers(x, y, width, height));
+        }
+    }
+    pixels
+}
+
+enum Shape {
+    /// Columns of a glyph in the 5x7 grid
+    Bitmap([u8; 5]),
+    /// Dots 1..8 as bits
+    Braille(u8),
+    /// Covered part of the cell, in cell fractions
+    Block(fn(f32, f32) -> bool),
+    /// Arms up, right, down, left
+    Lines([u8; 4]),
+    /// Segments between points in cell fractions
+    Strokes(&'static [Stroke]),
+    /// Ring or disc at the center, radius in cell widths
+    Circle { radius: f32, filled: bool },
+}
+
+impl Shape {
+    fn of(symbol: char) -> Self {
+        let code = symbol as u32;
+        match symbol {
+            ' '..='~' => Self::Bitmap(ASCII[(code - 0x20) as usize]),
+            '\u{2800}'..='\u{28ff}' => Self::Braille((code - 0x2800) as u8),
+            '\u{2580}'..='\u{259f}' => Self::Block(block(symbol)),
+            '\u{2500}'..='\u{257f}' => match box_arms(symbol) {
+                Some(arms) => Self::Lines(arms),
+                None => Self::Bitmap(pattern(code)),
+            },
+            '—' | '–' => Self::Lines([0, LIGHT, 0, LIGHT]),
+            '¦' => {
+                Self::Strokes(&[((0.5, 0.1), (0.5, 0.4)), ((0.5, 0.6), (0.5, 0.9))])
+            }
+            '¬' => Self::Strokes(&[
+                ((0.15, 0.45), (0.8, 0.45)),
+                ((0.8, 0.45), (0.8, 0.6)),
+            ]),
+            '←' => Self::Strokes(&ARROW_LEFT),
+            '→' => Self::Strokes(&ARROW_RIGHT),
+            '↑' => Self::Strokes(&ARROW_UP),
+            '↓' => Self::Strokes(&ARROW_DOWN),
+            '↖' => Self::Strokes(&ARROW_UP_LEFT),
+            '↗' => Self::Strokes(&ARROW_UP_RIGHT),
+            '↘' => Self::Strokes(&ARROW_DOWN_RIGHT),
+            '↙' => Self::Strokes(&ARROW_DOWN_LEFT),
+            '✦' => Self::Strokes(&STAR),
+            '○' => Self::Circle {
+                radius: 0.4,
+                filled: false,
+            },
+            '◦' => Self::Circle {
+                radius: 0.25,
+                filled: false,
+            },
+            '•' | '●' => Self::Circle {
+                radius: 0.3,
+                filled: true,
+            },
+            '·' | '・' => Self::Circle {
+                radius: 0.12,
+                filled: true,
+            },
+            _ => Self::Bitmap(pattern(code)),
+        }
+    }
+
+    fn covers(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        // pixel center in cell fractions
+        let fx = (x as f32 + 0.5) / width as f32;
+        let fy = (y as f32 + 0.5) / height as f32;
+        match self {
+            Self::Bitmap(columns) => {
+                let column = x * GRID.0 / width;
+                let row = y * GRID.1 / height;
+                column < 5
+                    && (1..8).contains(&row)
+                    && columns[column] >> (row - 1) & 1 == 1
+            }
+            Self::Braille(dots) => {
+                let (column, row) = (x * 2 / width, y * 4 / height);
+                // dots 1-3 and 4-6 go down the columns, 7 and 8 are the last row
+                let bit = match (column, row) {
+                    (0, 3) => 6,
+                    (1, 3) => 7,
+                    (column, row) => column * 3 + row,
+                };
+                let (cx, cy) =
+                    ((column as f32 + 0.5) / 2.0, (row as f32 + 0.5) / 4.0);
+                let radius = (width as f32 / 2.0).min(height as f32 / 4.0) * 0.35;
+                let (dx, dy) =
+                    ((fx - cx) * width as f32, (fy - cy) * height as f32);
+                dots >> bit & 1 == 1
+                    && dx.abs() <= radius.max(0.5)
+                    && dy.abs() <= radius.max(0.5)
+            }
+            Self::Block(covered) => covered(fx, fy),
+            Self::Lines(arms) => lines(*arms, x, y, width, height),
+            Self::Strokes(segments) => {
+                let thickness = (width as f32 / 8.0).max(1.0);
+                let point = (fx * width as f32, fy * height as f32);
+                segments.iter().any(|(from, to)| {
+                    let from = (from.0 * width as f32, from.1 * height as f32);
+                    let to = (to.0 * width as f32, to.1 * height as f32);
+                    distance_to_segment(point, from, to) <= thickness / 2.0 + 0.25
+                })
+            }
+            Self::Circle { radius, filled } => {
+                let radius = radius * width as f32;
+                let (dx, dy) =
+                    ((fx - 0.5) * width as f32, (fy - 0.5) * height as f32);
+                let distance = (dx * dx + dy * dy).sqrt();
+                let thickness = (width as f32 / 8.0).max(1.0);
+                if *filled {
+                    distance <= radius.max(0.5)
+                } else {
+                    (distance - radius).abs() <= thickness / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// Segment from one point to another, in cell fractions
+type Stroke = ((f32, f32), (f32, f32));
+
+const ARROW_LEFT: [Stroke; 3] = [
+    ((0.1, 0.5), (0.9, 0.5)),
+    ((0.1, 0.5), (0.4, 0.35)),
+    ((0.1, 0.5), (0.4, 0.65)),
+];
+const ARROW_RIGHT: [Stroke; 3] = [
+    ((0.1, 0.5), (0.9, 0.5)),
+    ((0.9, 0.5), (0.6, 0.35)),
+    ((0.9, 0.5), (0.6, 0.65)),
+];
+const ARROW_UP: [Stroke; 3] = [
+    ((0.5, 0.2), (0.5, 0.8)),
+    ((0.5, 0.2), (0.2, 0.4)),
+    ((0.5, 0.2), (0.8, 0.4)),
+];
+const ARROW_DOWN: [Stroke; 3] = [
+    ((0.5, 0.2), (0.5, 0.8)),
+    ((0.5, 0.8), (0.2, 0.6)),
+    ((0.5, 0.8), (0.8, 0.6)),
+];
+const ARROW_UP_LEFT: [Stroke; 3] = [
+    ((0.15, 0.3), (0.85, 0.7)),
+    ((0.15, 0.3), (0.55, 0.3)),
+    ((0.15, 0.3), (0.15, 0.55)),
+];
+const ARROW_UP_RIGHT: [Stroke; 3] = [
+    ((0.85, 0.3), (0.15, 0.7)),
+    ((0.85, 0.3), (0.45, 0.3)),
+    ((0.85, 0.3), (0.85, 0.55)),
+];
+const ARROW_DOWN_RIGHT: [Stroke; 3] = [
+    ((0.15, 0.3), (0.85, 0.7)),
+    ((0.85, 0.7), (0.45, 0.7)),
+    ((0.85, 0.7), (0.85, 0.45)),
+];
+const ARROW_DOWN_LEFT: [Stroke; 3] = [
+    ((0.85, 0.3), (0.15, 0.7)),
+    ((0.15, 0.7), (0.55, 0.7)),
+    ((0.15, 0.7), (0.15, 0.45)),
+];
+const STAR: [Stroke; 4] = [
+    ((0.5, 0.25), (0.5, 0.75)),
+    ((0.15, 0.5), (0.85, 0.5)),
+    ((0.35, 0.4), (0.65, 0.6)),
+    ((0.65, 0.4), (0.35, 0.6)),
+];
+
+/// Part of the cell covered by a block element
+fn block(symbol: char) -> fn(f32, f32) -> bool {
+    match symbol {
+        '▀' => |_, y| y < 0.5,
+        '▁' => |_, y| y > 7.0 / 8.0,
+        '▂' => |_, y| y > 6.0 / 8.0,
+        '▃' => |_, y| y > 5.0 / 8.0,
+        '▄' => |_, y| y > 0.5,
+        '▅' => |_, y| y > 3.0 / 8.0,
+        '▆' => |_, y| y > 2.0 / 8.0,
+        '▇' => |_, y| y > 1.0 / 8.0,
+        '█' => |_, _| true,
+        '▉' => |x, _| x < 7.0 / 8.0,
+        '▊' => |x, _| x < 6.0 / 8.0,
+        '▋' => |x, _| x < 5.0 / 8.0,
+        '▌' => |x, _| x < 0.5,
+        '▍' => |x, _| x < 3.0 / 8.0,
+        '▎' => |x, _| x < 2.0 / 8.0,
+        '▏' => |x, _| x < 1.0 / 8.0,
+        '▐' => |x, _| x > 0.5,
+        // shades as checker patterns of quarter cells
+        '░' => |x, y| ((x * 4.0) as u32 + (y * 8.0) as u32 * 2) % 4 == 0,
+        '▒' => |x, y| ((x * 4.0) as u32 + (y * 8.0) as u32) % 2 == 0,
+        '▓' => |x, y| ((x * 4.0) as u32 + (y * 8.0) as u32 * 2) % 4 != 0,
+        '▔' => |_, y| y < 1.0 / 8.0,
+        '▕' => |x, _| x > 7.0 / 8.0,
+        '▖' => |x, y| x < 0.5 && y > 0.5,
+        '▗' => |x, y| x > 0.5 && y > 0.5,
+        '▘' => |x, y| x < 0.5 && y < 0.5,
+        '▙' => |x, y| x < 0.5 || y > 0.5,
+        '▚' => |x, y| (x < 0.5) == (y < 0.5),
+        '▛' => |x, y| x < 0.5 || y < 0.5,
+        '▜' => |x, y| x > 0.5 || y < 0.5,
+        '▝' => |x, y| x > 0.5 && y < 0.5,
+        '▞' => |x, y| (x < 0.5) != (y < 0.5),
+        _ => |x, y| x > 0.5 || y > 0.5, // ▟
+    }
+}
+
+/// Arms up, right, down, left of box drawing characters, `None` for
+/// diagonals and mixed weights which aren't drawn
+fn box_arms(symbol: char) -> Option<[u8; 4]> {
+    let arms = match symbol {
+        '─' | '┄' | '┈' | '╌' => [0, LIGHT, 0, LIGHT],
+        '━' | '┅' | '┉' | '╍' => [0, HEAVY, 0, HEAVY],
+        '│' | '┆' | '┊' | '╎' => [LIGHT, 0, LIGHT, 0],
+        '┃' | '┇' | '┋' | '╏' => [HEAVY, 0, HEAVY, 0],
+        '┌' | '╭' => [0, LIGHT, LIGHT, 0],
+        '┐' | '╮' => [0, 0, LIGHT, LIGHT],
+        '└' | '╰' => [LIGHT, LIGHT, 0, 0],
+        '┘' | '╯' => [LIGHT, 0, 0, LIGHT],
+        '┏' => [0, HEAVY, HEAVY, 0],
+        '┓' => [0, 0, HEAVY, HEAVY],
+        '┗' => [HEAVY, HEAVY, 0, 0],
+        '┛' => [HEAVY, 0, 0, HEAVY],
+        '├' => [LIGHT, LIGHT, LIGHT, 0],
+        '┤' => [LIGHT, 0, LIGHT, LIGHT],
+        '┬' => [0, LIGHT, LIGHT, LIGHT],
+        '┴' => [LIGHT, LIGHT, 0, LIGHT],
+        '┼' => [LIGHT, LIGHT, LIGHT, LIGHT],
+        '┣' => [HEAVY, HEAVY, HEAVY, 0],
+        '┫' => [HEAVY, 0, HEAVY, HEAVY],
+        '┳' => [0, HEAVY, HEAVY, HEAVY],
+        '┻' => [HEAVY, HEAVY, 0, HEAVY],
+        '╋' => [HEAVY, HEAVY, HEAVY, HEAVY],
+        '═' => [0, DOUBLE, 0, DOUBLE],
+        '║' => [DOUBLE, 0, DOUBLE, 0],
+        '╔' => [0, DOUBLE, DOUBLE, 0],
+        '╗' => [0, 0, DOUBLE, DOUBLE],
+        '╚' => [DOUBLE, DOUBLE, 0, 0],
+        '╝' => [DOUBLE, 0, 0, DOUBLE],
+        '╠' => [DOUBLE, DOUBLE, DOUBLE, 0],
+        '╣' => [DOUBLE, 0, DOUBLE, DOUBLE],
+        '╦' => [0, DOUBLE, DOUBLE, DOUBLE],
+        '╩' => [DOUBLE, DOUBLE, 0, DOUBLE],
+        '╬' => [DOUBLE, DOUBLE, DOUBLE, DOUBLE],
+        '╴' => [0, 0, 0, LIGHT],
+        '╵' => [LIGHT, 0, 0, 0],
+        '╶' => [0, LIGHT, 0, 0],
+        '╷' => [0, 0, LIGHT, 0],
+        '╸' => [0, 0, 0, HEAVY],
+        '╹' => [HEAVY, 0, 0, 0],
+        '╺' => [0, HEAVY, 0, 0],
+        '╻' => [0, 0, HEAVY, 0],
+        _ => return None,
+    };
+    Some(arms)
+}
+
+/// Box drawing arms from the cell center to its edges, lines of
+/// neighbouring cells meet at the same offsets
+fn lines(arms: [u8; 4], x: usize, y: usize, width: usize, height: usize) -> bool {
+    let light = (width / 8).max(1) as i32;
+    let (cx, cy) = ((width / 2) as i32, (height / 2) as i32);
+    let (x, y) = (x as i32, y as i32);
+    // distance from the arm axis for vertical and horizontal arms
+    let (across_v, across_h) = (x - cx, y - cy);
+    let on_arm = |weight: u8, across: i32| match weight {
+        LIGHT => across >= -light / 2 && across < light - light / 2,
+        HEAVY => across >= -light && across < light,
+        DOUBLE => {
+            let offset = across.abs() - light;
+            (0..light.max(1)).contains(&offset)
+                || (0..light.max(1)).contains(&(-across - light))
+        }
+        _ => false,
+    };
+    // arms reach over the widest crossing line so corners close
+    let reach = arms
+        .iter()
+        .map(|&weight| match weight {
+            HEAVY => light,
+            DOUBLE => 2 * light,
+            _ => light / 2,
+        })
+        .max()
+        .unwrap_or(0);
+    let [up, right, down, left] = arms;
+    (y <= cy + reach && on_arm(up, across_v))
+        || (y >= cy - reach && on_arm(down, across_v))
+        || (x <= cx + reach && on_arm(left, across_h))
+        || (x >= cx - reach && on_arm(right, across_h))
+}
+
+fn distance_to_segment(point: (f32, f32), from: (f32, f32), to: (f32, f32)) -> f32 {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((point.0 - from.0) * dx + (point.1 - from.1) * dy) / length)
+            .clamp(0.0, 1.0)
+    };
+    let (px, py) = (from.0 + t * dx - point.0, from.1 + t * dy - point.1);
+    (px * px + py * py).sqrt()
+}
+
+/// Made up 5x7 glyph for symbols the font doesn't have
+fn pattern(code: u32) -> [u8; 5] {
+    let mut state = code.wrapping_mul(0x9e37_79b9) ^ 0x5bd1_e995;
+    let mut columns = [0u8; 5];
+    for column in columns.iter_mut() {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        *column = (state & 0x7f) as u8;
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(symbol: char, width: usize, height: usize) -> Vec<String> {
+        glyph(symbol, width, height)
+            .chunks(width)
+            .map(|row| row.iter().map(|&on| if on { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn ascii_glyphs_fill_the_grid() {
+        assert_eq!(
+            rows('T', 6, 9),
+            vec![
+                "......", "#####.", "..#...", "..#...", "..#...", "..#...",
+                "..#...", "..#...", "......",
+            ]
+        );
+        assert!(glyph(' ', 8, 16).iter().all(|on| !on));
+    }
+
+    #[test]
+    fn block_and_braille_glyphs_follow_geometry() {
+        let half = rows('▄', 2, 4);
+        assert_eq!(half, vec!["..", "..", "##", "##"]);
+        // dots 1 and 8: top left and bottom right
+        assert_eq!(rows('\u{2881}', 2, 4), vec!["#.", "..", "..", ".#"]);
+    }
+
+    #[test]
+    fn box_lines_reach_cell_edges() {
+        let corner = rows('┌', 8, 8);
+        assert_eq!(corner[4], "....####");
+        assert_eq!(corner[7], "....#...");
+        assert_eq!(corner[0], "........");
+        let cross = rows('┼', 8, 8);
+        assert!(cross[0].contains('#') && cross[4] == "########");
+    }
+
+    #[test]
+    fn unknown_symbols_get_distinct_patterns() {
+        assert_ne!(glyph('ｱ', 6, 9), glyph('ｲ', 6, 9));
+        assert!(glyph('ｱ', 6, 9).iter().any(|&on| on));
+    }
+}
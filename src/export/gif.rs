@@ -0,0 +1,319 @@
+//! Animated GIF writer.
+//!
+//! Frames are palette indices of the whole image. Only the rectangle which
+//! changed since the previous frame is stored, unchanged frames and frames
+//! shorter than `MIN_DELAY` are folded into their neighbours because
+//! browsers slow such frames down.
+use crate::gradient::Rgb;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Shortest frame browsers show at its own delay, in 1/100 s
+const MIN_DELAY: u16 = 2;
+/// LZW codes are at most 12 bits long
+const MAX_CODES: u16 = 4096;
+
+pub struct GifEncoder<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+    /// Last frame written to `out`
+    written: Option<Vec<u8>>,
+    /// Frame waiting for its final delay
+    pending: Option<(Vec<u8>, u16)>,
+}
+
+impl<W: Write> GifEncoder<W> {
+    /// Write header and global palette, at most 256 colors, the animation
+    /// loops forever
+    pub fn new(
+        mut out: W,
+        width: u16,
+        height: u16,
+        palette: &[Rgb],
+    ) -> io::Result<Self> {
+        let bits = table_bits(palette.len());
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // global color table of 2^bits entries, 8 bits per channel
+        out.write_all(&[0xf0 | (bits - 1), 0, 0])?;
+        for index in 0..1usize << bits {
+            let color = palette.get(index).copied().unwrap_or(Rgb::new(0, 0, 0));
+            out.write_all(&[color.r, color.g, color.b])?;
+        }
+        // NETSCAPE2.0 extension, 0 repeats is forever
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+        Ok(Self {
+            out,
+            width,
+            height,
+            written: None,
+            pending: None,
+        })
+    }
+
+    /// Add a frame shown for `delay` hundredths of a second
+    pub fn frame(&mut self, pixels: Vec<u8>, delay: u16) -> io::Result<()> {
+        debug_assert_eq!(pixels.len(), self.width as usize * self.height as usize);
+        match self.pending.take() {
+            Some((previous, previous_delay)) if previous == pixels => {
+                self.pending =
+                    Some((previous, previous_delay.saturating_add(delay)));
+            }
+            Some((_, previous_delay)) if previous_delay < MIN_DELAY => {
+                self.pending = Some((pixels, previous_delay + delay));
+            }
+            Some((previous, previous_delay)) => {
+                self.write_frame(previous, previous_delay)?;
+                self.pending = Some((pixels, delay));
+            }
+            None => self.pending = Some((pixels, delay)),
+        }
+        Ok(())
+    }
+
+    /// Write the last frame and the trailer
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some((pixels, delay)) = self.pending.take() {
+            self.write_frame(pixels, delay.max(MIN_DELAY))?;
+        }
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_frame(&mut self, pixels: Vec<u8>, delay: u16) -> io::Result<()> {
+        let width = self.width as usize;
+        let (left, top, right, bottom) = match &self.written {
+            Some(previous) => changed_rect(previous, &pixels, width),
+            None => (0, 0, width, self.height as usize),
+        };
+        // graphic control: keep the previous frame under this one
+        self.out.write_all(&[0x21, 0xf9, 0x04, 0x04])?;
+        self.out.write_all(&delay.to_le_bytes())?;
+        self.out.write_all(&[0, 0])?;
+        // image descriptor without a local color table
+        self.out.write_all(&[0x2c])?;
+        for value in [left, top, right - left, bottom - top] {
+            self.out.write_all(&(value as u16).to_le_bytes())?;
+        }
+        self.out.write_all(&[0])?;
+
+        let rect = (top..bottom).flat_map(|y| {
+            pixels[y * width + left..y * width + right].iter().copied()
+        });
+        let data = lzw(rect, 8);
+        self.out.write_all(&[8])?;
+        for block in data.chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0])?;
+        self.written = Some(pixels);
+        Ok(())
+    }
+}
+
+/// Bits per index of the color table, GIF needs at least 1
+fn table_bits(colors: usize) -> u8 {
+    let mut bits = 1;
+    while (1usize << bits) < colors {
+        bits += 1;
+    }
+    bits
+}
+
+/// Smallest rectangle holding every changed pixel as left, top, right and
+/// bottom, a single pixel when nothing changed
+fn changed_rect(
+    previous: &[u8],
+    pixels: &[u8],
+    width: usize,
+) -> (usize, usize, usize, usize) {
+    let height = pixels.len() / width;
+    let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+    for (index, (old, new)) in previous.iter().zip(pixels).enumerate() {
+        if old != new {
+            let (x, y) = (index % width, index / width);
+            left = left.min(x);
+            right = right.max(x + 1);
+            top = top.min(y);
+            bottom = bottom.max(y + 1);
+        }
+    }
+    if right == 0 {
+        (0, 0, 1, 1)
+    } else {
+        (left, top, right, bottom)
+    }
+}
+
+/// Variable length LZW of GIF, codes packed from the lowest bit
+fn lzw(indices: impl Iterator<Item = u8>, min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut code_size = min_code_size + 1;
+    writer.write(clear, code_size);
+
+    let mut current: Option<u16> = None;
+    for index in indices {
+        let Some(prefix) = current else {
+            current = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = table.get(&(prefix, index)) {
+            current = Some(code);
+            continue;
+        }
+        writer.write(prefix, code_size);
+        if next == MAX_CODES {
+            writer.write(clear, code_size);
+            table.clear();
+            next = end + 1;
+            code_size = min_code_size + 1;
+        } else {
+            table.insert((prefix, index), next);
+            // decoder grows the code size one code later than we add it
+            if next == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            next += 1;
+        }
+        current = Some(index as u16);
+    }
+    if let Some(prefix) = current {
+        writer.write(prefix, code_size);
+    }
+    writer.write(end, code_size);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference LZW decoder, enough to check the encoder
+    fn unlzw(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1u16 << min_code_size;
+        let end = clear + 1;
+        let mut reader = (0usize, 0u32, 0u8); // position, buffer, bits
+        let mut read = |size: u8| {
+            while reader.2 < size {
+                reader.1 |= (data[reader.0] as u32) << reader.2;
+                reader.0 += 1;
+                reader.2 += 8;
+            }
+            let code = (reader.1 & ((1 << size) - 1)) as u16;
+            reader.1 >>= size;
+            reader.2 -= size;
+            code
+        };
+        let mut out = Vec::new();
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut code_size = min_code_size + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        loop {
+            let code = read(code_size);
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).collect();
+                table.push(Vec::new());
+                table.push(Vec::new());
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code as usize), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                (None, None) => panic!("bad code {}", code),
+            };
+            out.extend(&entry);
+            if let Some(mut previous) = previous {
+                previous.push(entry[0]);
+                table.push(previous);
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips() {
+        let runs: Vec<u8> = (0..20_000).map(|i| ((i / 7) % 5) as u8).collect();
+        assert_eq!(unlzw(&lzw(runs.iter().copied(), 8), 8), runs);
+
+        // enough distinct strings to fill the table and clear it
+        let mut state = 7u32;
+        let noise: Vec<u8> = (0..50_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect();
+        assert_eq!(unlzw(&lzw(noise.iter().copied(), 8), 8), noise);
+    }
+
+    #[test]
+    fn frames_store_changed_rect_and_fold_delays() {
+        let palette = [Rgb::new(0, 0, 0), Rgb::new(255, 255, 255)];
+        let mut gif = GifEncoder::new(Vec::new(), 4, 3, &palette).unwrap();
+        let blank = vec![0; 12];
+        let mut dot = blank.clone();
+        dot[6] = 1;
+        gif.frame(blank.clone(), 5).unwrap();
+        gif.frame(blank.clone(), 5).unwrap(); // same picture, 10 in total
+        gif.frame(dot.clone(), 1).unwrap(); // too short, dropped for the next
+        gif.frame(blank, 4).unwrap();
+        let bytes = gif.finish().unwrap();
+
+        assert!(bytes.starts_with(b"GIF89a\x04\x00\x03\x00\xf0"));
+        assert_eq!(bytes.last(), Some(&0x3b));
+        let controls: Vec<u16> = bytes
+            .windows(4)
+            .enumerate()
+            .filter(|(_, window)| window == &[0x21, 0xf9, 0x04, 0x04])
+            .map(|(i, _)| u16::from_le_bytes([bytes[i + 4], bytes[i + 5]]))
+            .collect();
+        assert_eq!(controls, vec![10, 5]);
+        assert_eq!(changed_rect(&[0; 12], &dot, 4), (2, 1, 3, 2));
+    }
+}
@@ -0,0 +1,173 @@
+//! Offline export of effects to GIF and PNG.
+//!
+//! Frames come from the headless renderer, so no terminal is needed, and
+//! are rasterized with the embedded font. A `.gif` gets every frame with
+//! one palette quantized from all of them, a `.png` the last frame, and
+//! `stills` adds a PNG of every n-th frame next to the output.
+pub mod font;
+pub mod gif;
+pub mod png;
+pub mod quantize;
+pub mod raster;
+
+use crate::buffer::Buffer;
+use crate::common::{TerminalEffect, run_headless};
+use crate::error::Result;
+use crate::gradient::Rgb;
+use crate::theme::Theme;
+use quantize::Quantizer;
+use raster::Rasterizer;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Seed of exports without `--seed`, the same command gives the same file
+pub const DEFAULT_SEED: u64 = 0;
+
+/// Kind of file written to `out`, from its extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Gif,
+    Png,
+}
+
+pub fn format_of(path: &Path) -> Option<Format> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "gif" => Some(Format::Gif),
+        "png" => Some(Format::Png),
+        _ => None,
+    }
+}
+
+/// What and where to export, `tarts export`
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub out: PathBuf,
+    pub frames: usize,
+    /// Columns and rows of the virtual terminal
+    pub screen_size: (u16, u16),
+    /// Pixels of one cell, width and height
+    pub cell_size: (u16, u16),
+    /// Also save every n-th frame as `<stem>-NNNN.png`
+    pub stills: Option<usize>,
+}
+
+/// Render `options.frames` frames of `effect` and write them, returns the
+/// written files
+pub fn export<TE>(
+    effect: &mut TE,
+    theme: &Theme,
+    options: &ExportOptions,
+) -> Result<Vec<PathBuf>>
+where
+    TE: TerminalEffect + ?Sized,
+{
+    let (cols, rows) = options.screen_size;
+    let mut screen = Buffer::new(cols as usize, rows as usize);
+    let mut frames: Vec<(Duration, Buffer)> = Vec::with_capacity(options.frames);
+    run_headless(effect, options.frames, |at, diff| {
+        screen.apply_diff(diff);
+        frames.push((at, screen.clone()));
+        Ok(())
+    })?;
+
+    let cell_size = (options.cell_size.0 as usize, options.cell_size.1 as usize);
+    let mut rasterizer = Rasterizer::new(theme, cell_size);
+    let mut paths = Vec::new();
+    match format_of(&options.out) {
+        Some(Format::Gif) => write_gif(&frames, &mut rasterizer, &options.out)?,
+        Some(Format::Png) => {
+            if let Some((_, last)) = frames.last() {
+                write_png(last, &mut rasterizer, &options.out)?;
+            }
+        }
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: expected a .gif or .png file", options.out.display()),
+            )
+            .into());
+        }
+    }
+    paths.push(options.out.clone());
+
+    if let Some(every) = options.stills {
+        let stem = options.out.with_extension("");
+        for (index, (_, frame)) in frames.iter().enumerate().step_by(every) {
+            let path =
+                PathBuf::from(format!("{}-{:04}.png", stem.display(), index + 1));
+            write_png(frame, &mut rasterizer, &path)?;
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+fn write_png(
+    buffer: &Buffer,
+    rasterizer: &mut Rasterizer,
+    path: &Path,
+) -> io::Result<()> {
+    let (width, height) = rasterizer.image_size(buffer);
+    let pixels = rasterizer.render(buffer, |color| color);
+    let mut out = BufWriter::new(File::create(path)?);
+    png::write(&mut out, width as u32, height as u32, &pixels)
+}
+
+fn write_gif(
+    frames: &[(Duration, Buffer)],
+    rasterizer: &mut Rasterizer,
+    path: &Path,
+) -> io::Result<()> {
+    let Some((_, first)) = frames.first() else {
+        return Ok(());
+    };
+    let (width, height) = rasterizer.image_size(first);
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}x{} pixels is too large for a GIF", width, height),
+        ));
+    }
+
+    // cells weigh as much as their pixels roughly do
+    let mut histogram: HashMap<Rgb, usize> = HashMap::new();
+    let cell_pixels = rasterizer.cell_size.0 * rasterizer.cell_size.1;
+    for (_, frame) in frames {
+        for cell in frame.iter() {
+            *histogram.entry(rasterizer.background()).or_default() += cell_pixels;
+            if !matches!(cell.symbol, ' ' | '\0') {
+                *histogram.entry(rasterizer.ink(cell)).or_default() +=
+                    cell_pixels / 4;
+            }
+        }
+    }
+    let mut quantizer = Quantizer::new(&histogram, 256);
+
+    let mut gif = gif::GifEncoder::new(
+        BufWriter::new(File::create(path)?),
+        width as u16,
+        height as u16,
+        &quantizer.palette,
+    )?;
+    // delays from rounded times, so they don't drift over the animation
+    let centiseconds = |at: Duration| (at.as_secs_f64() * 100.0).round() as u64;
+    let interval = match frames {
+        [.., (previous, _), (last, _)] => *last - *previous,
+        _ => Duration::from_secs(1),
+    };
+    for (index, (at, frame)) in frames.iter().enumerate() {
+        let next = frames
+            .get(index + 1)
+            .map(|(next, _)| *next)
+            .unwrap_or(*at + interval);
+        let delay = (centiseconds(next) - centiseconds(*at)).min(u16::MAX as u64);
+        let pixels = rasterizer.render(frame, |color| quantizer.index(color));
+        gif.frame(pixels, delay as u16)?;
+    }
+    gif.finish()?;
+    Ok(())
+}
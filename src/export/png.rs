@@ -0,0 +1,314 @@
+//! PNG stills.
+//!
+//! Truecolor images without filters, compressed with a small deflate:
+//! LZ77 over hash chains and the fixed Huffman codes. Frames are mostly
+//! flat runs of a few colors, which this handles well enough.
+use crate::gradient::Rgb;
+use std::io::{self, Write};
+
+/// Deflate looks back at most this far
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// Candidates tried for each position
+const MAX_CHAIN: usize = 64;
+
+/// Base lengths of the codes 257..=285 and their extra bits
+const LENGTHS: [(u16, u8); 29] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 1),
+    (13, 1),
+    (15, 1),
+    (17, 1),
+    (19, 2),
+    (23, 2),
+    (27, 2),
+    (31, 2),
+    (35, 3),
+    (43, 3),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 4),
+    (115, 4),
+    (131, 5),
+    (163, 5),
+    (195, 5),
+    (227, 5),
+    (258, 0),
+];
+
+/// Base distances of the codes 0..=29 and their extra bits
+const DISTANCES: [(u16, u8); 30] = [
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 1),
+    (7, 1),
+    (9, 2),
+    (13, 2),
+    (17, 3),
+    (25, 3),
+    (33, 4),
+    (49, 4),
+    (65, 5),
+    (97, 5),
+    (129, 6),
+    (193, 6),
+    (257, 7),
+    (385, 7),
+    (513, 8),
+    (769, 8),
+    (1025, 9),
+    (1537, 9),
+    (2049, 10),
+    (3073, 10),
+    (4097, 11),
+    (6145, 11),
+    (8193, 12),
+    (12289, 12),
+    (16385, 13),
+    (24577, 13),
+];
+
+/// Write `pixels`, row by row, as an RGB PNG
+pub fn write<W: Write>(
+    out: &mut W,
+    width: u32,
+    height: u32,
+    pixels: &[Rgb],
+) -> io::Result<()> {
+    debug_assert_eq!(pixels.len(), width as usize * height as usize);
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, truecolor, deflate, no filters, not interlaced
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    chunk(out, b"IHDR", &header)?;
+
+    let mut raw = Vec::with_capacity(pixels.len() * 3 + height as usize);
+    for row in pixels.chunks(width.max(1) as usize) {
+        raw.push(0);
+        for pixel in row {
+            raw.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+        }
+    }
+    chunk(out, b"IDAT", &zlib(&raw))?;
+    chunk(out, b"IEND", &[])
+}
+
+fn chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let table: Vec<u32> = (0..256u32)
+        .map(|n| {
+            (0..8).fold(n, |c, _| {
+                if c & 1 == 1 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                }
+            })
+        })
+        .collect();
+    !bytes.fold(!0u32, |crc, &byte| {
+        table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65_521;
+        (a, (b + a) % 65_521)
+    });
+    (b << 16) | a
+}
+
+/// zlib stream of a single fixed Huffman block
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    // last block, fixed codes
+    writer.write(1, 1);
+    writer.write(1, 2);
+
+    let mut chains = Chains::new(data.len());
+    let mut at = 0;
+    while at < data.len() {
+        let (length, distance) = chains.longest_match(data, at);
+        if length >= MIN_MATCH {
+            writer.length(length);
+            writer.distance(distance);
+            for position in at..at + length {
+                chains.insert(data, position);
+            }
+            at += length;
+        } else {
+            writer.literal(data[at] as u16);
+            chains.insert(data, at);
+            at += 1;
+        }
+    }
+    writer.literal(256);
+
+    let mut out = vec![0x78, 0x01];
+    out.extend(writer.finish());
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Earlier positions of every three byte prefix, newest first
+struct Chains {
+    head: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl Chains {
+    fn new(len: usize) -> Self {
+        Self {
+            head: vec![usize::MAX; 1 << 15],
+            previous: vec![usize::MAX; len],
+        }
+    }
+
+    fn hash(data: &[u8], at: usize) -> usize {
+        let key = u32::from_be_bytes([0, data[at], data[at + 1], data[at + 2]]);
+        (key.wrapping_mul(2_654_435_761) >> 17) as usize
+    }
+
+    fn insert(&mut self, data: &[u8], at: usize) {
+        if at + MIN_MATCH <= data.len() {
+            let key = Self::hash(data, at);
+            self.previous[at] = self.head[key];
+            self.head[key] = at;
+        }
+    }
+
+    /// Longest earlier copy of the bytes at `at` as length and distance
+    fn longest_match(&self, data: &[u8], at: usize) -> (usize, usize) {
+        if at + MIN_MATCH > data.len() {
+            return (0, 0);
+        }
+        let limit = MAX_MATCH.min(data.len() - at);
+        let mut candidate = self.head[Self::hash(data, at)];
+        let mut best = (0, 0);
+        for _ in 0..MAX_CHAIN {
+            if candidate == usize::MAX || at - candidate > WINDOW {
+                break;
+            }
+            let length = (0..limit)
+                .take_while(|&i| data[candidate + i] == data[at + i])
+                .count();
+            if length > best.0 {
+                best = (length, at - candidate);
+                if length == limit {
+                    break;
+                }
+            }
+            candidate = self.previous[candidate];
+        }
+        best
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    /// Low `size` bits of `value`, lowest first
+    fn write(&mut self, value: u32, size: u8) {
+        self.buffer |= value << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    /// Huffman codes go out from their highest bit
+    fn code(&mut self, code: u32, size: u8) {
+        self.write(code.reverse_bits() >> (32 - size), size);
+    }
+
+    /// Fixed code of a literal, the end of block or a length symbol
+    fn literal(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn length(&mut self, length: usize) {
+        let index = LENGTHS
+            .iter()
+            .rposition(|&(base, _)| base as usize <= length)
+            .expect("length of at least 3");
+        let (base, extra) = LENGTHS[index];
+        self.literal(257 + index as u16);
+        self.write((length - base as usize) as u32, extra);
+    }
+
+    fn distance(&mut self, distance: usize) {
+        let index = DISTANCES
+            .iter()
+            .rposition(|&(base, _)| base as usize <= distance)
+            .expect("distance of at least 1");
+        let (base, extra) = DISTANCES[index];
+        self.code(index as u32, 5);
+        self.write((distance - base as usize) as u32, extra);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"IEND".iter()), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn image_has_chunks_and_compresses_runs() {
+        let pixels = vec![Rgb::new(10, 20, 30); 64 * 64];
+        let mut out = Vec::new();
+        write(&mut out, 64, 64, &pixels).unwrap();
+
+        assert!(out.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        assert!(out.ends_with(b"IEND\xae\x42\x60\x82"));
+        // 12 KiB of pixels
+        assert!(out.len() < 1024, "{} bytes", out.len());
+    }
+}
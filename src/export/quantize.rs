@@ -0,0 +1,142 @@
+//! Palette of at most 256 colors for GIF frames.
+//!
+//! Median cut over a histogram of the colors frames use: the box with the
+//! widest channel is split at its weighted median until there are enough
+//! boxes, each box becomes its weighted average color.
+use crate::gradient::Rgb;
+use std::collections::HashMap;
+
+pub struct Quantizer {
+    pub palette: Vec<Rgb>,
+    nearest: HashMap<Rgb, u8>,
+}
+
+impl Quantizer {
+    /// Palette of at most `size` colors for `histogram`, colors and how
+    /// many pixels have them
+    pub fn new(histogram: &HashMap<Rgb, usize>, size: usize) -> Self {
+        let mut colors: Vec<(Rgb, usize)> = histogram
+            .iter()
+            .map(|(&color, &count)| (color, count))
+            .collect();
+        // same palette for the same frames
+        colors.sort_by_key(|&(color, _)| (color.r, color.g, color.b));
+        let palette = if colors.len() <= size {
+            colors.iter().map(|&(color, _)| color).collect()
+        } else {
+            median_cut(colors, size)
+        };
+        Self {
+            palette,
+            nearest: HashMap::new(),
+        }
+    }
+
+    /// Index of the closest palette color
+    pub fn index(&mut self, color: Rgb) -> u8 {
+        let palette = &self.palette;
+        *self.nearest.entry(color).or_insert_with(|| {
+            let distance = |other: &Rgb| {
+                let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+                d(color.r, other.r) + d(color.g, other.g) + d(color.b, other.b)
+            };
+            (0..palette.len())
+                .min_by_key(|&i| distance(&palette[i]))
+                .unwrap_or(0) as u8
+        })
+    }
+}
+
+fn median_cut(colors: Vec<(Rgb, usize)>, size: usize) -> Vec<Rgb> {
+    let mut boxes = vec![colors];
+    while boxes.len() < size {
+        // widest box which still has two colors to split
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(index, colors)| {
+                let (channel, range) = (0..3)
+                    .map(|channel| {
+                        let values =
+                            colors.iter().map(|&(c, _)| component(c, channel));
+                        let range = values.clone().max().unwrap_or(0)
+                            - values.min().unwrap_or(0);
+                        (channel, range)
+                    })
+                    .max_by_key(|&(_, range)| range)
+                    .unwrap_or((0, 0));
+                (index, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range)
+            .map(|(index, channel, _)| (index, channel))
+        else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|&(color, _)| component(color, channel));
+        let total: usize = colors.iter().map(|&(_, count)| count).sum();
+        let mut seen = 0;
+        let split = colors
+            .iter()
+            .position(|&(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap_or(0)
+            .clamp(0, colors.len() - 2)
+            + 1;
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+    boxes.iter().map(|colors| average(colors)).collect()
+}
+
+fn component(color: Rgb, channel: usize) -> u8 {
+    match channel {
+        0 => color.r,
+        1 => color.g,
+        _ => color.b,
+    }
+}
+
+fn average(colors: &[(Rgb, usize)]) -> Rgb {
+    let total = colors.iter().map(|&(_, count)| count).sum::<usize>().max(1);
+    let channel = |channel| {
+        let sum: usize = colors
+            .iter()
+            .map(|&(color, count)| component(color, channel) as usize * count)
+            .sum();
+        ((sum + total / 2) / total) as u8
+    };
+    Rgb::new(channel(0), channel(1), channel(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn few_colors_are_kept_as_they_are() {
+        let histogram =
+            HashMap::from([(Rgb::new(0, 0, 0), 10), (Rgb::new(0, 255, 0), 1)]);
+        let mut quantizer = Quantizer::new(&histogram, 256);
+        assert_eq!(quantizer.palette.len(), 2);
+        assert_eq!(quantizer.index(Rgb::new(0, 250, 0)), 1);
+        assert_eq!(quantizer.index(Rgb::new(0, 0, 0)), 0);
+    }
+
+    #[test]
+    fn many_colors_are_cut_to_size() {
+        let histogram: HashMap<Rgb, usize> = (0..=255u8)
+            .flat_map(|r| (0..4u8).map(move |g| (Rgb::new(r, g * 80, 0), 1)))
+            .collect();
+        let mut quantizer = Quantizer::new(&histogram, 16);
+        assert_eq!(quantizer.palette.len(), 16);
+        let index = quantizer.index(Rgb::new(200, 240, 0));
+        let nearest = quantizer.palette[index as usize];
+        assert!(nearest.r.abs_diff(200) < 40 && nearest.g.abs_diff(240) < 60);
+    }
+}
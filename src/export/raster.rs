@@ -0,0 +1,128 @@
+//! Buffer frames as pixels.
+//!
+//! Every cell is a `cell_size` block painted in the page background with
+//! its glyph on top in the cell color, after the theme. Glyph masks are
+//! made once per symbol.
+use super::font;
+use crate::buffer::{Buffer, Cell};
+use crate::gradient::{ColorSpace, Rgb};
+use crate::screenshot;
+use crate::theme::{self, Theme};
+use crossterm::style;
+use std::collections::HashMap;
+
+pub struct Rasterizer {
+    theme: Theme,
+    pub cell_size: (usize, usize),
+    background: Rgb,
+    foreground: Rgb,
+    glyphs: HashMap<char, Vec<bool>>,
+}
+
+impl Rasterizer {
+    pub fn new(theme: &Theme, cell_size: (usize, usize)) -> Self {
+        let (background, foreground) = screenshot::page_colors(theme);
+        Self {
+            theme: theme.clone(),
+            cell_size,
+            background,
+            foreground,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    pub fn background(&self) -> Rgb {
+        self.background
+    }
+
+    /// Pixel size of `buffer`
+    pub fn image_size(&self, buffer: &Buffer) -> (usize, usize) {
+        (
+            buffer.width * self.cell_size.0,
+            buffer.height * self.cell_size.1,
+        )
+    }
+
+    /// Color the glyph of `cell` is drawn in
+    pub fn ink(&self, cell: &Cell) -> Rgb {
+        let color = match cell.color {
+            style::Color::Reset => self.foreground,
+            color => {
+                theme::to_rgb(self.theme.color(color)).unwrap_or(self.foreground)
+            }
+        };
+        match cell.attr {
+            style::Attribute::Dim => {
+                ColorSpace::Rgb.mix(self.background, color, 0.5)
+            }
+            _ => color,
+        }
+    }
+
+    /// Pixels of `buffer` row by row, `paint` turns a color into a pixel
+    /// and is called once per cell and color
+    pub fn render<T: Copy>(
+        &mut self,
+        buffer: &Buffer,
+        mut paint: impl FnMut(Rgb) -> T,
+    ) -> Vec<T> {
+        let (cell_width, cell_height) = self.cell_size;
+        let (width, height) = self.image_size(buffer);
+        let background = paint(self.background);
+        let mut pixels = vec![background; width * height];
+        for y in 0..buffer.height {
+            for x in 0..buffer.width {
+                let cell = buffer.get(x, y);
+                if matches!(cell.symbol, ' ' | '\0') {
+                    continue;
+                }
+                let ink = paint(self.ink(&cell));
+                let mask = self.glyphs.entry(cell.symbol).or_insert_with(|| {
+                    font::glyph(cell.symbol, cell_width, cell_height)
+                });
+                for row in 0..cell_height {
+                    let start = (y * cell_height + row) * width + x * cell_width;
+                    let line = &mut pixels[start..start + cell_width];
+                    let bits = &mask[row * cell_width..(row + 1) * cell_width];
+                    for (pixel, &on) in line.iter_mut().zip(bits) {
+                        if on {
+                            *pixel = ink;
+                        }
+                    }
+                }
+            }
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::ThemeBuilder;
+
+    #[test]
+    fn cells_become_blocks_of_glyph_pixels() {
+        let mut buffer = Buffer::new(2, 1);
+        let green = style::Color::Rgb { r: 0, g: 255, b: 0 };
+        buffer.set(1, 0, Cell::new('█', green, style::Attribute::Reset));
+        let mut rasterizer = Rasterizer::new(&Theme::default(), (4, 8));
+        let pixels = rasterizer.render(&buffer, |color| color);
+
+        assert_eq!(pixels.len(), 8 * 8);
+        assert_eq!(pixels[0], Rgb::new(0, 0, 0));
+        assert!(pixels[4..8].iter().all(|&p| p == Rgb::new(0, 255, 0)));
+        assert!(pixels[60..64].iter().all(|&p| p == Rgb::new(0, 255, 0)));
+    }
+
+    #[test]
+    fn ink_follows_theme_and_dim() {
+        let light = ThemeBuilder::default().light(true).build().unwrap();
+        let rasterizer = Rasterizer::new(&light, (8, 16));
+        assert_eq!(rasterizer.background(), Rgb::new(255, 255, 255));
+        let reset = Cell::new('x', style::Color::Reset, style::Attribute::Reset);
+        assert_eq!(rasterizer.ink(&reset), Rgb::new(0, 0, 0));
+        let dim = Cell::new('x', style::Color::Reset, style::Attribute::Dim);
+        assert_eq!(rasterizer.ink(&dim), Rgb::new(128, 128, 128));
+    }
+}
@@ -1,55 +1,13 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::PaletteChoice;
+use crate::random;
 use crossterm::style;
 use derive_builder::Builder;
 use rand::RngExt;
 use serde::{Deserialize, Serialize};
-use std::sync::LazyLock;
-
-static PALETTE: LazyLock<Vec<u8>> = LazyLock::new(|| {
-    let mut vec = Vec::with_capacity(768);
-    vec.extend_from_slice(&[
-        0, 0, 0, 0, 0, 6, 0, 0, 6, 0, 0, 7, 0, 0, 8, 0, 0, 8, 0, 0, 9, 0, 0, 10, 2,
-        0, 10, 4, 0, 9, 6, 0, 9, 8, 0, 8, 10, 0, 7, 12, 0, 7, 14, 0, 6, 16, 0, 5,
-        18, 0, 5, 20, 0, 4, 22, 0, 4, 24, 0, 3, 26, 0, 2, 28, 0, 2, 30, 0, 1, 32,
-        0, 0, 32, 0, 0, 33, 0, 0, 34, 0, 0, 35, 0, 0, 36, 0, 0, 36, 0, 0, 37, 0, 0,
-        38, 0, 0, 39, 0, 0, 40, 0, 0, 40, 0, 0, 41, 0, 0, 42, 0, 0, 43, 0, 0, 44,
-        0, 0, 45, 0, 0, 46, 1, 0, 47, 1, 0, 48, 2, 0, 49, 2, 0, 50, 3, 0, 51, 3, 0,
-        52, 4, 0, 53, 4, 0, 54, 5, 0, 55, 5, 0, 56, 6, 0, 57, 6, 0, 58, 7, 0, 59,
-        7, 0, 60, 8, 0, 61, 8, 0, 63, 9, 0, 63, 9, 0, 63, 10, 0, 63, 10, 0, 63, 11,
-        0, 63, 11, 0, 63, 12, 0, 63, 12, 0, 63, 13, 0, 63, 13, 0, 63, 14, 0, 63,
-        14, 0, 63, 15, 0, 63, 15, 0, 63, 16, 0, 63, 16, 0, 63, 17, 0, 63, 17, 0,
-        63, 18, 0, 63, 18, 0, 63, 19, 0, 63, 19, 0, 63, 20, 0, 63, 20, 0, 63, 21,
-        0, 63, 21, 0, 63, 22, 0, 63, 22, 0, 63, 23, 0, 63, 24, 0, 63, 24, 0, 63,
-        25, 0, 63, 25, 0, 63, 26, 0, 63, 26, 0, 63, 27, 0, 63, 27, 0, 63, 28, 0,
-        63, 28, 0, 63, 29, 0, 63, 29, 0, 63, 30, 0, 63, 30, 0, 63, 31, 0, 63, 31,
-        0, 63, 32, 0, 63, 32, 0, 63, 33, 0, 63, 33, 0, 63, 34, 0, 63, 34, 0, 63,
-        35, 0, 63, 35, 0, 63, 36, 0, 63, 36, 0, 63, 37, 0, 63, 38, 0, 63, 38, 0,
-        63, 39, 0, 63, 39, 0, 63, 40, 0, 63, 40, 0, 63, 41, 0, 63, 41, 0, 63, 42,
-        0, 63, 42, 0, 63, 43, 0, 63, 43, 0, 63, 44, 0, 63, 44, 0, 63, 45, 0, 63,
-        45, 0, 63, 46, 0, 63, 46, 0, 63, 47, 0, 63, 47, 0, 63, 48, 0, 63, 48, 0,
-        63, 49, 0, 63, 49, 0, 63, 50, 0, 63, 50, 0, 63, 51, 0, 63, 52, 0, 63, 52,
-        0, 63, 52, 0, 63, 52, 0, 63, 52, 0, 63, 53, 0, 63, 53, 0, 63, 53, 0, 63,
-        53, 0, 63, 54, 0, 63, 54, 0, 63, 54, 0, 63, 54, 0, 63, 54, 0, 63, 55, 0,
-        63, 55, 0, 63, 55, 0, 63, 55, 0, 63, 56, 0, 63, 56, 0, 63, 56, 0, 63, 56,
-        0, 63, 57, 0, 63, 57, 0, 63, 57, 0, 63, 57, 0, 63, 57, 0, 63, 58, 0, 63,
-        58, 0, 63, 58, 0, 63, 58, 0, 63, 59, 0, 63, 59, 0, 63, 59, 0, 63, 59, 0,
-        63, 60, 0, 63, 60, 0, 63, 60, 0, 63, 60, 0, 63, 60, 0, 63, 61, 0, 63, 61,
-        0, 63, 61, 0, 63, 61, 0, 63, 62, 0, 63, 62, 0, 63, 62, 0, 63, 62, 0, 63,
-        63, 0, 63, 63, 1, 63, 63, 2, 63, 63, 3, 63, 63, 4, 63, 63, 5, 63, 63, 6,
-        63, 63, 7, 63, 63, 8, 63, 63, 9, 63, 63, 10, 63, 63, 10, 63, 63, 11, 63,
-        63, 12, 63, 63, 13, 63, 63, 14, 63, 63, 15, 63, 63, 16, 63, 63, 17, 63, 63,
-        18, 63, 63, 19, 63, 63, 20, 63, 63, 21, 63, 63, 21, 63, 63, 22, 63, 63, 23,
-        63, 63, 24, 63, 63, 25, 63, 63, 26, 63, 63, 27, 63, 63, 28, 63, 63, 29, 63,
-        63, 30, 63, 63, 31, 63, 63, 31, 63, 63, 32, 63, 63, 33, 63, 63, 34, 63, 63,
-        35, 63, 63, 36, 63, 63, 37, 63, 63, 38, 63, 63, 39, 63, 63, 40, 63, 63, 41,
-        63, 63, 42, 63, 63, 42, 63, 63, 43, 63, 63, 44, 63, 63, 45, 63, 63, 46, 63,
-        63, 47, 63, 63, 48, 63, 63, 49, 63, 63, 50, 63, 63, 51, 63, 63, 52, 63, 63,
-        52, 63, 63, 53, 63, 63, 54, 63, 63, 55, 63, 63, 56, 63, 63, 57, 63, 63, 58,
-        63, 63, 59, 63, 63, 60, 63, 63, 61, 63, 63, 62, 63, 63, 63,
-    ]);
-    vec
-});
+
 const MAXTABLE: usize = 256 * 5;
 
 #[derive(Builder, Default, Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +15,16 @@ const MAXTABLE: usize = 256 * 5;
 pub struct FireOptions {
     #[builder(default = "true")]
     pub use_colors: bool,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+impl FireOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.palette("palette", &self.palette);
+    }
 }
 
 pub struct Fire {
@@ -84,7 +52,7 @@ impl TerminalEffect for Fire {
         self.loop_counter -= 1;
 
         if self.loop_counter < 0 {
-            self.loop_counter = rand::rng().random_range(0..3);
+            self.loop_counter = random::rng().random_range(0..3);
             self.sloop_counter += 1;
         }
 
@@ -94,6 +62,7 @@ impl TerminalEffect for Fire {
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // fire bitmap is tied to screen size, fire starts again
         self.screen_size = (width, height);
         self.reset();
     }
@@ -118,6 +87,18 @@ impl TerminalEffect for Fire {
         self.sloop_counter = 0;
         self.height_counter = 0;
     }
+
+    fn min_size(&self) -> (u16, u16) {
+        (4, 3)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        // options only change colors, flames keep burning
+        self.options = config.get_fire_options();
+        self.color_palette = self.options.palette.or_builtin("fire").ramp(256);
+        self.buffer =
+            Buffer::new(self.screen_size.0 as usize, self.screen_size.1 as usize);
+    }
 }
 
 impl Fire {
@@ -126,14 +107,7 @@ impl Fire {
         let height = screen_size.1 as usize;
         let buffer = Buffer::new(width, height);
 
-        // Create color palette from RGB values
-        let mut color_palette = Vec::with_capacity(256);
-        for i in 0..256 {
-            let r = (PALETTE[i * 3] as f32 * 4.047619) as u8;
-            let g = (PALETTE[i * 3 + 1] as f32 * 4.047619) as u8;
-            let b = (PALETTE[i * 3 + 2] as f32 * 4.047619) as u8;
-            color_palette.push(style::Color::Rgb { r, g, b });
-        }
+        let color_palette = options.palette.or_builtin("fire").ramp(256);
 
         // Initialize fire bitmap (double the width and height)
         let bitmap_width = width * 2;
@@ -213,10 +187,15 @@ impl Fire {
     }
 
     fn generate_fire_base(&mut self) {
-        let mut rng = rand::rng();
+        let mut rng = random::rng();
         let width = self.screen_size.0 as usize * 2;
         let height = self.screen_size.1 as usize * 2;
 
+        // base is three rows high
+        if height < 3 {
+            return;
+        }
+
         // Start with base row
         let base_row = height - 1;
         let mut x = 0;
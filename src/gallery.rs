@@ -0,0 +1,460 @@
+//! Gallery menu with live thumbnails of every registered effect.
+//!
+//! Each thumbnail is an effect constructed at reduced size and rendered
+//! through a `Viewport`, which keeps its own copy of the effect frame and
+//! blits it into the gallery screen buffer.
+use crate::buffer::{Buffer, Cell};
+use crate::common::{LoopOptions, TerminalEffect, draw_diff, run_loop};
+use crate::config::Config;
+use crate::crash;
+use crate::error::Result;
+use crate::metrics::FrameMetrics;
+use crate::registry::{self, EffectInfo};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, style, terminal,
+};
+use std::io::{BufWriter, Write};
+use std::time::{Duration, Instant};
+
+const MIN_TILE_WIDTH: usize = 20;
+const MIN_TILE_HEIGHT: usize = 9; // label row + thumbnail
+const MAX_COLUMNS: usize = 4;
+const TILE_GAP: usize = 1;
+const FOOTER_HEIGHT: usize = 2;
+const MIN_THUMBNAIL_SIZE: (usize, usize) = (16, 8);
+
+/// Effect rendered into rectangular part of the bigger screen
+pub struct Viewport {
+    effect: Box<dyn TerminalEffect>,
+    frame: Buffer,
+    pub position: (usize, usize),
+}
+
+impl Viewport {
+    pub fn new(
+        effect: Box<dyn TerminalEffect>,
+        size: (usize, usize),
+        position: (usize, usize),
+    ) -> Self {
+        Self {
+            effect,
+            frame: Buffer::new(size.0, size.1),
+            position,
+        }
+    }
+
+    /// Render current effect frame and advance effect to the next one
+    pub fn step(&mut self) {
+        let diff = self.effect.get_diff();
+        self.frame.apply_diff(&diff);
+        self.effect.update();
+    }
+
+    /// Copy last rendered frame into target buffer
+    pub fn draw(&self, target: &mut Buffer) {
+        target.blit(&self.frame, self.position.0, self.position.1);
+    }
+}
+
+/// Grid geometry, tile height includes the label row
+#[derive(Debug, PartialEq)]
+struct Layout {
+    columns: usize,
+    tile_width: usize,
+    tile_height: usize,
+    visible_rows: usize,
+}
+
+impl Layout {
+    fn new(screen_size: (usize, usize), count: usize) -> Self {
+        let (width, height) = screen_size;
+        let columns = ((width + TILE_GAP) / (MIN_TILE_WIDTH + TILE_GAP))
+            .clamp(1, MAX_COLUMNS)
+            .min(count.max(1));
+        let tile_width = ((width + TILE_GAP) / columns).saturating_sub(TILE_GAP);
+        let rows = count.div_ceil(columns).max(1);
+        let available = height.saturating_sub(FOOTER_HEIGHT);
+        // short screens get tiles without thumbnails, none below the footer
+        let tile_height = (available / rows)
+            .max(MIN_TILE_HEIGHT)
+            .min(available)
+            .max(1);
+        let visible_rows = available / tile_height;
+
+        Self {
+            columns,
+            tile_width,
+            tile_height,
+            visible_rows,
+        }
+    }
+
+    fn thumbnail_size(&self) -> (usize, usize) {
+        (self.tile_width, self.tile_height.saturating_sub(1))
+    }
+
+    /// Top left corner of the tile relative to the first visible row
+    fn tile_position(&self, index: usize, scroll: usize) -> Option<(usize, usize)> {
+        let row = index / self.columns;
+        if row < scroll || row >= scroll + self.visible_rows {
+            return None;
+        }
+        let column = index % self.columns;
+        Some((
+            column * (self.tile_width + TILE_GAP),
+            (row - scroll) * self.tile_height,
+        ))
+    }
+}
+
+enum GalleryAction {
+    Continue,
+    Quit,
+    Launch,
+    Navigate(KeyCode),
+    Resize(u16, u16),
+}
+
+pub struct Gallery<'a> {
+    config: &'a Config,
+    effects: &'static [EffectInfo],
+    screen_size: (u16, u16),
+    layout: Layout,
+    selected: usize,
+    scroll: usize,
+    thumbnails: Vec<Option<Viewport>>,
+    frame: Buffer,
+}
+
+impl<'a> Gallery<'a> {
+    pub fn new(config: &'a Config, screen_size: (u16, u16)) -> Self {
+        let mut gallery = Self {
+            config,
+            effects: registry::EFFECTS,
+            screen_size,
+            layout: Layout::new((1, 1), 1),
+            selected: 0,
+            scroll: 0,
+            thumbnails: Vec::new(),
+            frame: Buffer::new(1, 1),
+        };
+        gallery.rebuild(screen_size);
+        gallery
+    }
+
+    /// Recreate thumbnails for the new terminal size
+    fn rebuild(&mut self, screen_size: (u16, u16)) {
+        let size = (screen_size.0 as usize, screen_size.1 as usize);
+        self.screen_size = screen_size;
+        self.layout = Layout::new(size, self.effects.len());
+        self.frame = Buffer::new(size.0.max(1), size.1.max(1));
+
+        let (thumb_width, thumb_height) = self.layout.thumbnail_size();
+        let fits = thumb_width >= MIN_THUMBNAIL_SIZE.0
+            && thumb_height >= MIN_THUMBNAIL_SIZE.1;
+        self.thumbnails = self
+            .effects
+            .iter()
+            .map(|info| {
+                if !fits {
+                    return None;
+                }
+                registry::create(
+                    info.name,
+                    self.config,
+                    (thumb_width as u16, thumb_height as u16),
+                )
+                .map(|effect| {
+                    Viewport::new(effect, (thumb_width, thumb_height), (0, 0))
+                })
+            })
+            .collect();
+        self.scroll_to_selected();
+    }
+
+    pub fn selected(&self) -> &'static EffectInfo {
+        &self.effects[self.selected]
+    }
+
+    fn select(&mut self, index: isize) {
+        let last = self.effects.len() as isize - 1;
+        self.selected = index.clamp(0, last) as usize;
+        self.scroll_to_selected();
+    }
+
+    fn scroll_to_selected(&mut self) {
+        let row = self.selected / self.layout.columns;
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + self.layout.visible_rows {
+            self.scroll = row + 1 - self.layout.visible_rows;
+        }
+    }
+
+    fn move_selection(&mut self, key: KeyCode) {
+        let current = self.selected as isize;
+        let columns = self.layout.columns as isize;
+        match key {
+            KeyCode::Left | KeyCode::Char('h') => self.select(current - 1),
+            KeyCode::Right | KeyCode::Char('l') => self.select(current + 1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(current - columns),
+            KeyCode::Down | KeyCode::Char('j') => self.select(current + columns),
+            _ => {}
+        }
+    }
+
+    /// Advance visible thumbnails and compose the next gallery frame
+    fn draw(&mut self) -> Buffer {
+        let mut next = Buffer::new(self.frame.width, self.frame.height);
+
+        for (index, info) in self.effects.iter().enumerate() {
+            let Some((x, y)) = self.layout.tile_position(index, self.scroll) else {
+                continue;
+            };
+
+            let (color, attr) = if index == self.selected {
+                (style::Color::White, style::Attribute::Reverse)
+            } else {
+                (style::Color::Grey, style::Attribute::Bold)
+            };
+            let label =
+                format!("{:^width$}", info.name, width = self.layout.tile_width);
+            next.put_str(x, y, &label, color, attr);
+
+            if let Some(viewport) = self.thumbnails[index].as_mut() {
+                viewport.position = (x, y + 1);
+                viewport.step();
+                viewport.draw(&mut next);
+            }
+        }
+
+        let info = self.selected();
+        let footer_y = next.height.saturating_sub(FOOTER_HEIGHT);
+        next.put_str(
+            0,
+            footer_y,
+            &format!("{} - {}", info.name, info.description),
+            style::Color::White,
+            style::Attribute::Bold,
+        );
+        next.put_str(
+            0,
+            footer_y + 1,
+            "arrows/hjkl: select  enter: launch  esc: back to gallery  q: quit",
+            style::Color::DarkGrey,
+            style::Attribute::NormalIntensity,
+        );
+
+        next
+    }
+
+    /// Forget what is on screen, so the next frame is drawn completely
+    fn invalidate(&mut self) {
+        let blank = Cell::new('\0', style::Color::Reset, style::Attribute::Reset);
+        self.frame.fill_with(&blank);
+    }
+}
+
+fn process_gallery_input(timeout: Duration) -> Result<GalleryAction> {
+    if !event::poll(timeout)? {
+        return Ok(GalleryAction::Continue);
+    }
+    Ok(gallery_action(event::read()?))
+}
+
+/// Only key presses act, Windows reports key releases as events too
+fn gallery_action(event: Event) -> GalleryAction {
+    match event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            ..
+        }) => GalleryAction::Quit,
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('q') | KeyCode::Esc,
+            kind: KeyEventKind::Press,
+            ..
+        }) => GalleryAction::Quit,
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            kind: KeyEventKind::Press,
+            ..
+        }) => GalleryAction::Launch,
+        Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) => GalleryAction::Navigate(code),
+        Event::Resize(width, height) => GalleryAction::Resize(width, height),
+        _ => GalleryAction::Continue,
+    }
+}
+
+/// Show gallery until user quits, returns frame timings of the last
+/// launched effect, `loop_options` are used for every launched effect
+pub fn run_gallery<W: Write>(
+    stdout: &mut W,
+    config: &Config,
+    loop_options: &LoopOptions,
+) -> Result<Option<FrameMetrics>> {
+    let target_frame_duration = Duration::from_secs_f64(1.0 / 30.0);
+    let mut gallery = Gallery::new(config, terminal::size()?);
+    let mut last_metrics = None;
+
+    while !crash::terminate_requested() {
+        let started_at = Instant::now();
+
+        let next = gallery.draw();
+        {
+            let mut buffered_stdout = BufWriter::new(&mut *stdout);
+            draw_diff(
+                &mut buffered_stdout,
+                &gallery.frame.diff(&next),
+                &loop_options.theme,
+            )?;
+            buffered_stdout.flush()?;
+        }
+        gallery.frame = next;
+
+        let timeout = target_frame_duration.saturating_sub(started_at.elapsed());
+        match process_gallery_input(timeout)? {
+            GalleryAction::Continue => {}
+            GalleryAction::Quit => break,
+            GalleryAction::Navigate(code) => gallery.move_selection(code),
+            GalleryAction::Resize(width, height) => {
+                gallery.rebuild((width, height));
+                execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                gallery.invalidate();
+            }
+            GalleryAction::Launch => {
+                let screen_size = terminal::size()?;
+                let name = gallery.selected().name;
+                execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                crash::set_context(
+                    name,
+                    config.effect_options_toml(name),
+                    screen_size,
+                );
+                if let Some(mut effect) =
+                    registry::create(name, config, screen_size)
+                {
+                    let options = LoopOptions {
+                        name: name.to_string(),
+                        ..loop_options.clone()
+                    };
+                    last_metrics =
+                        Some(run_loop(stdout, effect.as_mut(), &options)?);
+                }
+                execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                gallery.rebuild(terminal::size()?);
+                gallery.invalidate();
+            }
+        }
+    }
+
+    Ok(last_metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_fits_screen() {
+        let layout = Layout::new((80, 24), 13);
+        assert_eq!(layout.columns, 3);
+        assert_eq!(layout.tile_width, 26);
+        assert_eq!(layout.tile_height, MIN_TILE_HEIGHT);
+        assert_eq!(layout.visible_rows, 2);
+
+        // hidden rows don't have position
+        assert_eq!(layout.tile_position(0, 0), Some((0, 0)));
+        assert_eq!(layout.tile_position(4, 0), Some((27, 9)));
+        assert_eq!(layout.tile_position(6, 0), None);
+        assert_eq!(layout.tile_position(6, 1), Some((0, 9)));
+    }
+
+    #[test]
+    fn layout_on_tiny_screen() {
+        let layout = Layout::new((5, 3), 13);
+        assert_eq!(layout.columns, 1);
+        assert_eq!(layout.visible_rows, 1);
+
+        for height in 0..30 {
+            for width in [1, 20, 80] {
+                let layout = Layout::new((width, height), 13);
+                let footer_y = height.saturating_sub(FOOTER_HEIGHT);
+                for index in 0..13 {
+                    if let Some((_, y)) = layout.tile_position(index, 0) {
+                        assert!(
+                            y + layout.tile_height <= footer_y,
+                            "tile {} overlaps footer on {}x{}",
+                            index,
+                            width,
+                            height
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn only_key_presses_act() {
+        let key = |code, kind| {
+            Event::Key(KeyEvent::new_with_kind(code, KeyModifiers::NONE, kind))
+        };
+        assert!(matches!(
+            gallery_action(key(KeyCode::Esc, KeyEventKind::Press)),
+            GalleryAction::Quit
+        ));
+        assert!(matches!(
+            gallery_action(key(KeyCode::Down, KeyEventKind::Press)),
+            GalleryAction::Navigate(KeyCode::Down)
+        ));
+        for code in [KeyCode::Esc, KeyCode::Down, KeyCode::Enter] {
+            assert!(matches!(
+                gallery_action(key(code, KeyEventKind::Release)),
+                GalleryAction::Continue
+            ));
+        }
+    }
+
+    #[test]
+    fn viewport_follows_effect_frame() {
+        let config = Config::default();
+        let effect = registry::create("plasma", &config, (20, 8)).unwrap();
+        let mut viewport = Viewport::new(effect, (20, 8), (2, 1));
+        viewport.step();
+
+        let mut screen = Buffer::new(30, 10);
+        viewport.draw(&mut screen);
+        assert_eq!(screen.get(0, 0), Cell::default());
+        assert_eq!(screen.get(2, 1).symbol, '*');
+        assert_eq!(screen.get(21, 8).symbol, '*');
+    }
+
+    #[test]
+    fn selection_scrolls_grid() {
+        let config = Config::default();
+        let mut gallery = Gallery::new(&config, (80, 24));
+        assert_eq!(gallery.selected().name, "matrix");
+
+        gallery.move_selection(KeyCode::Down);
+        gallery.move_selection(KeyCode::Down);
+        assert_eq!(gallery.selected, 6);
+        assert_eq!(gallery.scroll, 1);
+
+        gallery.move_selection(KeyCode::Left);
+        gallery.move_selection(KeyCode::Up);
+        gallery.move_selection(KeyCode::Up);
+        gallery.move_selection(KeyCode::Up);
+        assert_eq!(gallery.selected, 0);
+        assert_eq!(gallery.scroll, 0);
+
+        let frame = gallery.draw();
+        assert_eq!(frame.get(0, 0).attr, style::Attribute::Reverse);
+    }
+}
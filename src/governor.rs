@@ -0,0 +1,138 @@
+//! Adaptive quality governor.
+//!
+//! Watches how long frames take to compute and draw (sleep and input wait
+//! are not counted) and moves effect quality between `MIN_QUALITY` and 1.0
+//! to keep work inside the frame budget. Quality drops fast when frames are
+//! over budget and recovers slowly when there is headroom.
+use std::time::Duration;
+
+pub const MIN_QUALITY: f32 = 0.1;
+
+/// Time between two adjustments, lets the effect settle after a change
+const COOLDOWN: Duration = Duration::from_millis(500);
+/// Weight of the latest frame in the moving average
+const SMOOTHING: f64 = 0.1;
+/// Share of the budget above which quality goes down
+const HIGH_WATERMARK: f64 = 0.9;
+/// Share of the budget below which quality goes up
+const LOW_WATERMARK: f64 = 0.5;
+/// Quality is cut at least by this factor, more when far over budget
+const DECREASE_FACTOR: f32 = 0.8;
+const MAX_DECREASE_FACTOR: f32 = 0.5;
+const INCREASE_STEP: f32 = 0.05;
+
+#[derive(Debug, Clone)]
+pub struct QualityGovernor {
+    quality: f32,
+    budget: Duration,
+    average_secs: Option<f64>,
+    cooldown: Duration,
+}
+
+impl QualityGovernor {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            quality: 1.0,
+            budget,
+            average_secs: None,
+            cooldown: COOLDOWN,
+        }
+    }
+
+    pub fn quality(&self) -> f32 {
+        self.quality
+    }
+
+    /// Feed work time and whole duration of the last frame, returns new
+    /// quality when it changed
+    pub fn observe(&mut self, work: Duration, frame: Duration) -> Option<f32> {
+        let work = work.as_secs_f64();
+        let average = match self.average_secs {
+            Some(average) => average + (work - average) * SMOOTHING,
+            None => work,
+        };
+        self.average_secs = Some(average);
+
+        if !self.cooldown.is_zero() {
+            self.cooldown = self.cooldown.saturating_sub(frame);
+            return None;
+        }
+
+        let budget = self.budget.as_secs_f64();
+        let quality = if average > budget * HIGH_WATERMARK {
+            let factor = ((budget * HIGH_WATERMARK / average) as f32)
+                .clamp(MAX_DECREASE_FACTOR, DECREASE_FACTOR);
+            (self.quality * factor).max(MIN_QUALITY)
+        } else if average < budget * LOW_WATERMARK {
+            (self.quality + INCREASE_STEP).min(1.0)
+        } else {
+            self.quality
+        };
+
+        if quality == self.quality {
+            return None;
+        }
+        self.quality = quality;
+        self.cooldown = COOLDOWN;
+        // old frames were measured with previous quality
+        self.average_secs = None;
+        Some(quality)
+    }
+}
+
+/// Scale entity count by quality, never below one
+pub fn scaled_count(count: usize, quality: f32) -> usize {
+    ((count as f32 * quality).round() as usize).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(16);
+
+    fn run(governor: &mut QualityGovernor, work: Duration, frames: usize) {
+        for _ in 0..frames {
+            governor.observe(work, work.max(BUDGET));
+        }
+    }
+
+    #[test]
+    fn slow_frames_lower_quality() {
+        let mut governor = QualityGovernor::new(BUDGET);
+        run(&mut governor, Duration::from_millis(16), 40);
+        assert_eq!(governor.quality(), 0.8);
+
+        // far over budget, quality is halved at once
+        let mut governor = QualityGovernor::new(BUDGET);
+        run(&mut governor, Duration::from_millis(100), 6);
+        assert_eq!(governor.quality(), 0.5);
+
+        run(&mut governor, Duration::from_millis(40), 1000);
+        assert_eq!(governor.quality(), MIN_QUALITY);
+    }
+
+    #[test]
+    fn headroom_restores_quality() {
+        let mut governor = QualityGovernor::new(BUDGET);
+        run(&mut governor, Duration::from_millis(40), 100);
+        assert!(governor.quality() < 1.0);
+
+        run(&mut governor, Duration::from_millis(1), 1000);
+        assert_eq!(governor.quality(), 1.0);
+    }
+
+    #[test]
+    fn stable_inside_budget() {
+        let mut governor = QualityGovernor::new(BUDGET);
+        run(&mut governor, Duration::from_millis(12), 1000);
+        assert_eq!(governor.quality(), 1.0);
+    }
+
+    #[test]
+    fn scaled_count_keeps_one() {
+        assert_eq!(scaled_count(100, 1.0), 100);
+        assert_eq!(scaled_count(100, 0.25), 25);
+        assert_eq!(scaled_count(3, 0.1), 1);
+    }
+}
@@ -0,0 +1,375 @@
+//! Color gradients: stops at float positions, mixed in RGB, HSV or OKLab.
+//!
+//! `Gradient` is the general engine, `palette::Palette` builds on the same
+//! color spaces and easing curves for evenly spaced stops.
+use crossterm::style;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parse `#rrggbb` or `#rgb`
+    pub fn parse(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#')?;
+        if !digits.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize, len: usize| {
+            u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).ok()
+        };
+        match digits.len() {
+            6 => Some(Self::new(channel(0, 2)?, channel(1, 2)?, channel(2, 2)?)),
+            3 => {
+                let (r, g, b) = (channel(0, 1)?, channel(1, 1)?, channel(2, 1)?);
+                Some(Self::new(r * 17, g * 17, b * 17))
+            }
+            _ => None,
+        }
+    }
+
+    /// Hue in degrees `0..360`, saturation and value in `0..=1`
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (r, g, b) = (
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+        );
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        (hue, saturation, max)
+    }
+
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let hue = hue.rem_euclid(360.0);
+        let (saturation, value) =
+            (saturation.clamp(0.0, 1.0), value.clamp(0.0, 1.0));
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match (hue / 60.0) as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        let channel = |v: f32| ((v + m) * 255.0).round() as u8;
+        Self::new(channel(r), channel(g), channel(b))
+    }
+
+    /// Perceptual lightness, a and b of OKLab
+    pub fn to_oklab(self) -> (f32, f32, f32) {
+        let linear = |v: u8| {
+            let v = v as f32 / 255.0;
+            if v <= 0.04045 {
+                v / 12.92
+            } else {
+                ((v + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(self.r), linear(self.g), linear(self.b));
+        let l = (0.41222146 * r + 0.53633255 * g + 0.051445995 * b).cbrt();
+        let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+        let s = (0.08830246 * r + 0.28171885 * g + 0.6299787 * b).cbrt();
+        (
+            0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+            1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+            0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+        )
+    }
+
+    pub fn from_oklab(lightness: f32, a: f32, b: f32) -> Self {
+        let l = (lightness + 0.39633778 * a + 0.21580376 * b).powi(3);
+        let m = (lightness - 0.105561346 * a - 0.06385417 * b).powi(3);
+        let s = (lightness - 0.08948418 * a - 1.2914855 * b).powi(3);
+        let srgb = |v: f32| {
+            let v = v.clamp(0.0, 1.0);
+            let v = if v <= 0.0031308 {
+                v * 12.92
+            } else {
+                1.055 * v.powf(1.0 / 2.4) - 0.055
+            };
+            (v * 255.0).round() as u8
+        };
+        Self::new(
+            srgb(4.0767417 * l - 3.3077116 * m + 0.23096994 * s),
+            srgb(-1.268438 * l + 2.6097574 * m - 0.34131938 * s),
+            srgb(-0.0041960863 * l - 0.7034186 * m + 1.7076147 * s),
+        )
+    }
+}
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(hex: String) -> Result<Self, Self::Error> {
+        Self::parse(&hex)
+            .ok_or_else(|| format!("invalid color `{}`, expected #rrggbb", hex))
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(color: Rgb) -> Self {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    }
+}
+
+impl From<Rgb> for style::Color {
+    fn from(color: Rgb) -> Self {
+        style::Color::Rgb {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+        }
+    }
+}
+
+/// Space two colors are mixed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    /// Channel by channel, fast and what the effects always did
+    #[default]
+    Rgb,
+    /// Along the shorter way around the hue circle, keeps colors saturated
+    Hsv,
+    /// Perceptually even steps of lightness
+    Oklab,
+}
+
+impl ColorSpace {
+    /// Color between `a` and `b`, `t` is clamped to `0.0..=1.0`
+    pub fn mix(self, a: Rgb, b: Rgb, t: f32) -> Rgb {
+        if t <= 0.0 {
+            return a;
+        }
+        if t >= 1.0 {
+            return b;
+        }
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        match self {
+            ColorSpace::Rgb => {
+                let channel = |a: u8, b: u8| lerp(a as f32, b as f32).round() as u8;
+                Rgb::new(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b))
+            }
+            ColorSpace::Hsv => {
+                let (mut ha, sa, va) = a.to_hsv();
+                let (mut hb, sb, vb) = b.to_hsv();
+                // grays have no hue of their own
+                if sa == 0.0 {
+                    ha = hb;
+                }
+                if sb == 0.0 {
+                    hb = ha;
+                }
+                let turn = (hb - ha + 540.0).rem_euclid(360.0) - 180.0;
+                Rgb::from_hsv(ha + turn * t, lerp(sa, sb), lerp(va, vb))
+            }
+            ColorSpace::Oklab => {
+                let (la, aa, ba) = a.to_oklab();
+                let (lb, ab, bb) = b.to_oklab();
+                Rgb::from_oklab(lerp(la, lb), lerp(aa, ab), lerp(ba, bb))
+            }
+        }
+    }
+}
+
+/// Curve applied to the position before sampling
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Longer dark end
+    EaseIn,
+    /// Longer bright end
+    EaseOut,
+    /// Both ends stretched
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Color stops at positions in `0.0..=1.0`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f32, Rgb)>,
+    pub space: ColorSpace,
+    pub easing: Easing,
+}
+
+impl Gradient {
+    /// Stops are sorted by position, positions are clamped to `0.0..=1.0`
+    pub fn new(stops: impl IntoIterator<Item = (f32, Rgb)>) -> Self {
+        let mut stops: Vec<(f32, Rgb)> = stops
+            .into_iter()
+            .map(|(position, color)| (position.clamp(0.0, 1.0), color))
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            stops,
+            space: ColorSpace::default(),
+            easing: Easing::default(),
+        }
+    }
+
+    /// Stops spread evenly from 0 to 1
+    pub fn evenly(colors: &[Rgb]) -> Self {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        Self::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, color)| (i as f32 / last, *color)),
+        )
+    }
+
+    pub fn with_space(mut self, space: ColorSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Color at position `t`, ends repeat the outer stops
+    pub fn sample(&self, t: f32) -> Rgb {
+        let t = self.easing.apply(t);
+        let next = self.stops.partition_point(|(position, _)| *position < t);
+        let before = next.checked_sub(1).and_then(|i| self.stops.get(i));
+        match (before, self.stops.get(next)) {
+            (Some(&(from, a)), Some(&(to, b))) => {
+                let span = to - from;
+                let local = if span > 0.0 { (t - from) / span } else { 1.0 };
+                self.space.mix(a, b, local)
+            }
+            (Some(&(_, color)), None) | (None, Some(&(_, color))) => color,
+            (None, None) => Rgb::new(255, 255, 255),
+        }
+    }
+
+    pub fn color(&self, t: f32) -> style::Color {
+        self.sample(t).into()
+    }
+
+    /// `len` colors from position 0 to 1, for lookup tables
+    pub fn ramp(&self, len: usize) -> Vec<style::Color> {
+        let last = len.saturating_sub(1).max(1) as f32;
+        (0..len).map(|i| self.color(i as f32 / last)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_parsed_from_hex() {
+        assert_eq!(Rgb::parse("#ff8000"), Some(Rgb::new(255, 128, 0)));
+        assert_eq!(Rgb::parse("#f80"), Some(Rgb::new(255, 136, 0)));
+        assert_eq!(Rgb::parse("ff8000"), None);
+        assert_eq!(Rgb::parse("#ff80zz"), None);
+        assert_eq!(String::from(Rgb::new(10, 0, 255)), "#0a00ff");
+    }
+
+    #[test]
+    fn stops_split_the_range_at_their_positions() {
+        let white = Rgb::new(255, 255, 255);
+        let green = Rgb::new(0, 255, 0);
+        let dark = Rgb::new(10, 10, 10);
+        // rain body: white head, green at 4 of 20 cells, dark tail
+        let gradient = Gradient::new([(1.0, dark), (0.0, white), (0.2, green)]);
+        assert_eq!(gradient.sample(0.0), white);
+        assert_eq!(gradient.sample(0.1), Rgb::new(128, 255, 128));
+        assert_eq!(gradient.sample(0.2), green);
+        // second segment runs over the remaining 16 cells, not 4 of them
+        assert_eq!(gradient.sample(0.52), Rgb::new(4, 157, 4));
+        assert_eq!(gradient.sample(1.0), dark);
+        assert_eq!(gradient.sample(-3.0), white);
+
+        assert_eq!(Gradient::new([]).sample(0.5), white);
+        assert_eq!(Gradient::evenly(&[green]).sample(0.7), green);
+        assert_eq!(Gradient::evenly(&[dark, green]).ramp(3)[1], {
+            style::Color::Rgb { r: 5, g: 133, b: 5 }
+        });
+    }
+
+    #[test]
+    fn color_spaces_mix_differently() {
+        let red = Rgb::new(255, 0, 0);
+        let blue = Rgb::new(0, 0, 255);
+        assert_eq!(ColorSpace::Rgb.mix(red, blue, 0.5), Rgb::new(128, 0, 128));
+        // red to blue through magenta, the short way round
+        assert_eq!(ColorSpace::Hsv.mix(red, blue, 0.5), Rgb::new(255, 0, 255));
+        // black takes the hue of red instead of fading through yellow
+        let dark_red = ColorSpace::Hsv.mix(Rgb::new(0, 0, 0), red, 0.5);
+        assert_eq!(dark_red, Rgb::new(128, 64, 64));
+
+        // perceptual middle of black and white is darker than RGB middle
+        let black = Rgb::new(0, 0, 0);
+        let white = Rgb::new(255, 255, 255);
+        let middle = ColorSpace::Oklab.mix(black, white, 0.5);
+        assert_eq!(middle, Rgb::new(99, 99, 99));
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        for color in [
+            Rgb::new(0, 0, 0),
+            Rgb::new(255, 255, 255),
+            Rgb::new(247, 37, 133),
+            Rgb::new(10, 77, 140),
+        ] {
+            let (h, s, v) = color.to_hsv();
+            assert_eq!(Rgb::from_hsv(h, s, v), color);
+            let (l, a, b) = color.to_oklab();
+            assert_eq!(Rgb::from_oklab(l, a, b), color);
+        }
+    }
+
+    #[test]
+    fn easing_bends_positions() {
+        assert_eq!(Easing::Linear.apply(0.5), 0.5);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.25), 0.15625);
+        assert_eq!(Easing::EaseInOut.apply(2.0), 1.0);
+
+        let gradient = Gradient::evenly(&[Rgb::new(0, 0, 0), Rgb::new(200, 0, 0)])
+            .with_easing(Easing::EaseIn);
+        assert_eq!(gradient.sample(0.5), Rgb::new(50, 0, 0));
+    }
+}
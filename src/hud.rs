@@ -0,0 +1,335 @@
+//! Debug overlay drawn on top of the running effect (F1 or `--hud`).
+//!
+//! Short notices (config reloaded, config errors) use the same overlay for
+//! a few seconds, with or without the HUD.
+//!
+//! The loop keeps a copy of what the effect drew on screen, `Hud::compose`
+//! takes the effect diff, drops cells hidden under the overlay, adds changed
+//! overlay cells and restores effect cells once the overlay shrinks or hides.
+use crate::buffer::{Buffer, Cell};
+use crate::metrics::FrameSample;
+use crossterm::style;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Number of recent frames live fps is averaged over
+const FPS_WINDOW: usize = 30;
+
+pub struct Hud {
+    pub visible: bool,
+    name: String,
+    seed: Option<u64>,
+    target_fps: f64,
+    recent_frames_us: VecDeque<u64>,
+    dropped_frames: usize,
+    last: FrameSample,
+    /// Message shown below the HUD until the given time
+    notice: Option<(String, Instant)>,
+    /// Overlay currently on screen, anchored at top left corner
+    shown: Option<Buffer>,
+}
+
+impl Hud {
+    pub fn new(
+        name: &str,
+        seed: Option<u64>,
+        target_fps: f64,
+        visible: bool,
+    ) -> Self {
+        Self {
+            visible,
+            name: name.to_string(),
+            seed,
+            target_fps,
+            recent_frames_us: VecDeque::with_capacity(FPS_WINDOW),
+            dropped_frames: 0,
+            last: FrameSample::default(),
+            notice: None,
+            shown: None,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Show message on top of the effect until `until`
+    pub fn notify(&mut self, text: impl Into<String>, until: Instant) {
+        self.notice = Some((text.into(), until));
+    }
+
+    /// Drop notice which is due at `now`, true when it was removed
+    pub fn expire_notice(&mut self, now: Instant) -> bool {
+        let expired = self.notice.as_ref().is_some_and(|(_, until)| *until <= now);
+        if expired {
+            self.notice = None;
+        }
+        expired
+    }
+
+    /// HUD or notice is on top of the effect
+    pub fn overlay_visible(&self) -> bool {
+        self.visible || self.notice.is_some()
+    }
+
+    /// Screen was cleared, nothing of the overlay is left on it
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+    }
+
+    /// Remember timings of the finished frame
+    pub fn record(&mut self, sample: &FrameSample) {
+        if self.recent_frames_us.len() == FPS_WINDOW {
+            self.recent_frames_us.pop_front();
+        }
+        self.recent_frames_us.push_back(sample.frame_us);
+        self.dropped_frames += sample.dropped;
+        self.last = *sample;
+    }
+
+    /// Frames per second over the last few frames
+    pub fn fps(&self) -> f64 {
+        let total_us: u64 = self.recent_frames_us.iter().sum();
+        if total_us == 0 {
+            return 0.0;
+        }
+        self.recent_frames_us.len() as f64 * 1_000_000.0 / total_us as f64
+    }
+
+    pub fn lines(
+        &self,
+        screen_size: (usize, usize),
+        stats: &[(&'static str, usize)],
+    ) -> Vec<String> {
+        let mut lines = if self.visible {
+            self.hud_lines(screen_size, stats)
+        } else {
+            Vec::new()
+        };
+        if let Some((text, _)) = &self.notice {
+            lines.extend(text.lines().map(str::to_string));
+        }
+        lines
+    }
+
+    fn hud_lines(
+        &self,
+        screen_size: (usize, usize),
+        stats: &[(&'static str, usize)],
+    ) -> Vec<String> {
+        let last = &self.last;
+        // waiting for input is not work, it only fills the frame budget;
+        // worker computes the next frame while this one is written
+        let work_us = (last.update_us + last.diff_us).max(last.write_us);
+        let seed = self.seed.map_or("-".to_string(), |seed| seed.to_string());
+        let work_ms = work_us as f64 / 1000.0;
+        // static effects have no frame rate to keep up with
+        let (target, frame) = if self.target_fps > 0.0 {
+            (
+                format!("{:.0}", self.target_fps),
+                format!("{:.2} ms of {:.2} ms", work_ms, 1000.0 / self.target_fps),
+            )
+        } else {
+            ("static".to_string(), format!("{:.2} ms", work_ms))
+        };
+        let mut rows = vec![
+            ("fps", format!("{:.1} / {}", self.fps(), target)),
+            ("frame", frame),
+            (
+                "diff",
+                format!("{} cells, {} bytes", last.changed_cells, last.bytes),
+            ),
+            ("dropped", self.dropped_frames.to_string()),
+            ("size", format!("{}x{}", screen_size.0, screen_size.1)),
+            ("quality", format!("{:.2}", last.quality)),
+            ("seed", seed),
+        ];
+        for (label, count) in stats {
+            rows.push((label, count.to_string()));
+        }
+
+        let mut lines = vec![self.name.clone()];
+        for (label, value) in rows {
+            lines.push(format!("{:<8}{}", label, value));
+        }
+        lines
+    }
+
+    /// Render lines into a box clipped by the screen size
+    fn render(
+        &self,
+        screen_size: (usize, usize),
+        stats: &[(&'static str, usize)],
+    ) -> Buffer {
+        let lines = self.lines(screen_size, stats);
+        let text_width = lines.iter().map(|line| line.chars().count()).max();
+        let width = (text_width.unwrap_or(0) + 2).min(screen_size.0);
+        let height = lines.len().min(screen_size.1);
+
+        let mut overlay = Buffer::new(width, height);
+        overlay.fill_with(&Cell::new(
+            ' ',
+            style::Color::White,
+            style::Attribute::Reset,
+        ));
+        for (y, line) in lines.iter().enumerate().take(height) {
+            let (color, attr) = if y == 0 {
+                (style::Color::Yellow, style::Attribute::Bold)
+            } else {
+                (style::Color::White, style::Attribute::Reset)
+            };
+            overlay.put_str(1, y, line, color, attr);
+        }
+        overlay
+    }
+
+    /// Merge effect diff with the overlay, `screen` holds effect output
+    /// with this diff already applied
+    pub fn compose(
+        &mut self,
+        screen: &Buffer,
+        diff: Vec<(usize, usize, Cell)>,
+        stats: &[(&'static str, usize)],
+    ) -> Vec<(usize, usize, Cell)> {
+        if !self.overlay_visible() && self.shown.is_none() {
+            return diff;
+        }
+
+        let overlay = self
+            .overlay_visible()
+            .then(|| self.render((screen.width, screen.height), stats));
+        let covers = |buffer: &Option<Buffer>, x: usize, y: usize| {
+            buffer
+                .as_ref()
+                .is_some_and(|buffer| x < buffer.width && y < buffer.height)
+        };
+
+        let mut composed: Vec<(usize, usize, Cell)> = diff
+            .into_iter()
+            .filter(|(x, y, _)| !covers(&overlay, *x, *y))
+            .collect();
+
+        // bring back effect cells which are not covered anymore
+        if let Some(shown) = &self.shown {
+            for y in 0..shown.height.min(screen.height) {
+                for x in 0..shown.width.min(screen.width) {
+                    if !covers(&overlay, x, y) {
+                        composed.push((x, y, screen.get(x, y)));
+                    }
+                }
+            }
+        }
+
+        if let Some(overlay) = &overlay {
+            for y in 0..overlay.height {
+                for x in 0..overlay.width {
+                    let cell = overlay.get(x, y);
+                    let unchanged = covers(&self.shown, x, y)
+                        && self.shown.as_ref().map(|shown| shown.get(x, y))
+                            == Some(cell);
+                    if !unchanged {
+                        composed.push((x, y, cell));
+                    }
+                }
+            }
+        }
+
+        self.shown = overlay;
+        composed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(symbol: char) -> Cell {
+        Cell::new(symbol, style::Color::Green, style::Attribute::Bold)
+    }
+
+    #[test]
+    fn hidden_hud_passes_diff_through() {
+        let mut hud = Hud::new("matrix", None, 60.0, false);
+        let screen = Buffer::new(80, 24);
+        let diff = vec![(0, 0, cell('a')), (79, 23, cell('b'))];
+        assert_eq!(hud.compose(&screen, diff.clone(), &[]), diff);
+    }
+
+    #[test]
+    fn overlay_hides_and_restores_effect_cells() {
+        let mut hud = Hud::new("matrix", Some(7), 60.0, true);
+        let mut screen = Buffer::new(80, 24);
+        let diff = vec![(0, 0, cell('a')), (79, 23, cell('b'))];
+        screen.apply_diff(&diff);
+
+        let composed = hud.compose(&screen, diff, &[("drops", 12)]);
+        assert!(!composed.contains(&(0, 0, cell('a'))));
+        assert!(composed.contains(&(79, 23, cell('b'))));
+
+        // nothing changed, nothing to redraw
+        assert!(
+            hud.compose(&screen, Vec::new(), &[("drops", 12)])
+                .is_empty()
+        );
+
+        hud.toggle();
+        let composed = hud.compose(&screen, Vec::new(), &[]);
+        assert!(composed.contains(&(0, 0, cell('a'))));
+        assert!(hud.compose(&screen, Vec::new(), &[]).is_empty());
+    }
+
+    #[test]
+    fn lines_show_stats_and_seed() {
+        let mut hud = Hud::new("boids", Some(42), 60.0, true);
+        hud.record(&FrameSample {
+            frame_us: 20_000,
+            changed_cells: 15,
+            dropped: 2,
+            ..Default::default()
+        });
+        let lines = hud.lines((80, 24), &[("boids", 120)]);
+        assert_eq!(lines[0], "boids");
+        assert!(lines.contains(&"fps     50.0 / 60".to_string()));
+        assert!(lines.contains(&"dropped 2".to_string()));
+        assert!(lines.contains(&"size    80x24".to_string()));
+        assert!(lines.contains(&"seed    42".to_string()));
+        assert!(lines.contains(&"boids   120".to_string()));
+    }
+
+    #[test]
+    fn static_effects_have_no_target() {
+        let mut hud = Hud::new("terrain", None, 0.0, true);
+        hud.record(&FrameSample {
+            update_us: 1500,
+            ..Default::default()
+        });
+        let lines = hud.lines((80, 24), &[]);
+        assert!(lines.iter().any(|line| line.ends_with("/ static")));
+        assert!(lines.contains(&"frame   1.50 ms".to_string()));
+    }
+
+    #[test]
+    fn overlay_is_clipped_by_tiny_screen() {
+        let mut hud = Hud::new("constellation", None, 60.0, true);
+        let screen = Buffer::new(5, 2);
+        let composed = hud.compose(&screen, Vec::new(), &[]);
+        assert!(composed.iter().all(|(x, y, _)| *x < 5 && *y < 2));
+    }
+
+    #[test]
+    fn notice_shows_without_hud_until_expired() {
+        let mut hud = Hud::new("matrix", None, 60.0, false);
+        let now = Instant::now();
+        hud.notify("Config error:\n  line 1", now);
+        assert!(hud.overlay_visible());
+        assert_eq!(hud.lines((80, 24), &[]), vec!["Config error:", "  line 1"]);
+
+        let screen = Buffer::new(80, 24);
+        assert!(!hud.compose(&screen, Vec::new(), &[]).is_empty());
+        assert!(hud.expire_notice(now));
+        assert!(!hud.overlay_visible());
+        // overlay cells are restored once
+        assert!(!hud.compose(&screen, Vec::new(), &[]).is_empty());
+        assert!(hud.compose(&screen, Vec::new(), &[]).is_empty());
+    }
+}
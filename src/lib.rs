@@ -12,16 +12,35 @@
 //! | `config` | CLI configuration and argument parsing   |
 //! | `constellation` | Drifting stars and dotted connections |
 //! | `crab`   | ASCII crab walking animation             |
+//! | `crash`  | Panic hook, signals and crash reports    |
 //! | `cube`   | 3D rotating cube in ASCII                |
 //! | `donut`  | 3D rotating donut in ASCII               |
 //! | `error`  | Error types for the crate                |
+//! | `export` | GIF and PNG export with a bitmap font    |
 //! | `fire`   | Fire simulation effect                   |
+//! | `gallery`| Effect picker with live thumbnails       |
+//! | `governor`| Adaptive quality to keep frame rate      |
+//! | `gradient`| Multi-stop color gradients and easing   |
+//! | `hud`    | Debug overlay with fps and entity counts |
 //! | `life`   | Conway's Game of Life                    |
 //! | `maze`   | Maze generation and animation            |
+//! | `metrics`| Frame timings, percentiles and JSON dump |
+//! | `migrate`| Config schema versions and migrations    |
+//! | `palette`| Named color palettes shared by effects   |
+//! | `particles` | Particle system described in the config |
 //! | `pipes`  | Pipe maze animation                      |
 //! | `plasma` | Plasma color wave effect                 |
+//! | `presets`| Built-in and user named effect presets   |
 //! | `rain`   | Matrix-style digital rain                |
+//! | `random` | Effect random generators, seedable       |
+//! | `registry` | Effect names, descriptions, constructors |
+//! | `record` | asciicast v2 recording of the terminal   |
+//! | `reload` | Config hot reload by polling file mtimes |
+//! | `screenshot` | Frame dumps as ANSI, plain text and HTML |
+//! | `snapshot` | Effect state saved for `--resume`        |
+//! | `stream` | Frame streams as JSON lines and replay    |
 //! | `terrain`| Terrain generation — scrolling landscape |
+//! | `theme`  | Global colors, light terminal background |
 
 pub mod blank;
 pub mod boids;
@@ -31,13 +50,32 @@ pub mod common;
 pub mod config;
 pub mod constellation;
 pub mod crab;
+pub mod crash;
 pub mod cube;
 pub mod donut;
 pub mod error;
+pub mod export;
 pub mod fire;
+pub mod gallery;
+pub mod governor;
+pub mod gradient;
+pub mod hud;
 pub mod life;
 pub mod maze;
+pub mod metrics;
+pub mod migrate;
+pub mod palette;
+pub mod particles;
 pub mod pipes;
 pub mod plasma;
+pub mod presets;
 pub mod rain;
+pub mod random;
+pub mod record;
+pub mod registry;
+pub mod reload;
+pub mod screenshot;
+pub mod snapshot;
+pub mod stream;
 pub mod terrain;
+pub mod theme;
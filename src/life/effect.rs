@@ -12,9 +12,13 @@
 //!     it becomes a living cell.
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::{Palette, PaletteChoice};
+use crate::random;
+use crate::snapshot::SavedColor;
 use crossterm::style;
 use derive_builder::Builder;
-use rand::RngExt;
+use rand::{Rng, RngExt, rngs::SmallRng};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::LazyLock;
@@ -25,14 +29,54 @@ static DEAD_CELLS_CHARS: LazyLock<Vec<char>> = LazyLock::new(|| {
     char_vec
 });
 
-#[derive(Builder, Default, Debug, Serialize, Deserialize, Clone)]
+/// User-facing settings, `[life]` section of `tarts.toml`
+#[derive(Builder, Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[builder(public, setter(into))]
-pub struct ConwayLifeOptions {
-    #[builder(default = "200")]
-    #[serde(skip)]
-    pub initial_cells: u32,
+pub struct ConwayLifeConfig {
     #[builder(default = "1.0")]
     pub cells_coeff: f32,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+/// Runtime options resolved from `ConwayLifeConfig` for a screen size
+#[derive(Builder, Debug, Clone, PartialEq)]
+#[builder(public, setter(into))]
+pub struct ConwayLifeOptions {
+    pub initial_cells: u32,
+    #[builder(default)]
+    pub palette: PaletteChoice,
+}
+
+impl Default for ConwayLifeConfig {
+    fn default() -> Self {
+        ConwayLifeConfigBuilder::default().build().unwrap()
+    }
+}
+
+impl ConwayLifeConfig {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.positive("cells_coeff", self.cells_coeff);
+        check.palette("palette", &self.palette);
+    }
+
+    /// Seed about 15% of the screen with live cells
+    pub fn to_runtime(&self, screen_size: (u16, u16)) -> ConwayLifeOptions {
+        let (w, h) = screen_size;
+        ConwayLifeOptions {
+            initial_cells: (w as f32 * h as f32 * 0.15 * self.cells_coeff) as u32,
+            palette: self.palette.clone(),
+        }
+    }
+}
+
+/// `snapshot` of the game, cells as `[x, y, character, color]`
+#[derive(Serialize, Deserialize)]
+struct LifeState {
+    generation: u8,
+    cells: Vec<(usize, usize, char, SavedColor)>,
 }
 
 #[derive(Clone)]
@@ -43,12 +87,13 @@ pub struct LifeCell {
 
 pub struct ConwayLife {
     pub screen_size: (u16, u16),
-    #[allow(dead_code)]
     options: ConwayLifeOptions,
     buffer: Buffer,
     cells: HashMap<(usize, usize), LifeCell>,
-    pub rng: rand::prelude::ThreadRng,
+    pub rng: SmallRng,
     pub current_gen: u8,
+    /// Cells fade from the bright end as generations go by
+    palette: Palette,
 }
 
 impl LifeCell {
@@ -61,30 +106,12 @@ impl LifeCell {
 
     pub fn update_color_and_char(
         &mut self,
-        rng: &mut rand::prelude::ThreadRng,
-        current_gen: u8,
+        rng: &mut impl Rng,
+        color: style::Color,
     ) {
-        let green_color = 255_u8.wrapping_sub(current_gen);
-        match current_gen {
-            0..=230 => {
-                self.color = style::Color::Rgb {
-                    r: 0,
-                    g: green_color,
-                    b: 0,
-                }; // Green
-                let random_index = rng.random_range(0..DEAD_CELLS_CHARS.len());
-                self.character = *DEAD_CELLS_CHARS.get(random_index).unwrap();
-            }
-            _ => {
-                self.color = style::Color::Rgb {
-                    r: 0,
-                    g: green_color,
-                    b: 0,
-                };
-                let random_index = rng.random_range(0..DEAD_CELLS_CHARS.len());
-                self.character = *DEAD_CELLS_CHARS.get(random_index).unwrap();
-            }
-        }
+        self.color = color;
+        let random_index = rng.random_range(0..DEAD_CELLS_CHARS.len());
+        self.character = *DEAD_CELLS_CHARS.get(random_index).unwrap();
     }
 }
 
@@ -107,6 +134,7 @@ impl TerminalEffect for ConwayLife {
         // update current generation counter
         self.current_gen = (self.current_gen + 1) % 255;
 
+        let color = self.generation_color();
         for (index, _) in self.buffer.iter().enumerate() {
             let neighbors = get_neighbors_by_index(&self.buffer, index);
             if neighbors.is_empty() {
@@ -116,7 +144,7 @@ impl TerminalEffect for ConwayLife {
             let alive_neighbors = neighbors.len();
 
             if let Some(cell) = self.cells.get_mut(&(nx, ny)) {
-                cell.update_color_and_char(&mut self.rng, self.current_gen);
+                cell.update_color_and_char(&mut self.rng, color);
 
                 // Survival: an alive cell with 2 or 3 alive neighbors stays alive
                 if alive_neighbors == 2 || alive_neighbors == 3 {
@@ -126,7 +154,7 @@ impl TerminalEffect for ConwayLife {
                 // Birth: a dead cell with exactly 3 alive neighbors becomes alive
                 if alive_neighbors == 3 {
                     let mut new_cell = LifeCell::new('*');
-                    new_cell.update_color_and_char(&mut self.rng, self.current_gen); // Initialize generation and update color/char
+                    new_cell.update_color_and_char(&mut self.rng, color); // Initialize generation and update color/char
                     next_cells.insert((nx, ny), new_cell);
                     // Replace 'X' with the desired initial state
                 }
@@ -135,7 +163,12 @@ impl TerminalEffect for ConwayLife {
         }
 
         // generate new cells, if cell already present, skip
+        // (there is no room for the glider on tiny screens)
+        let glider_fits = self.buffer.width >= 5 && self.buffer.height >= 5;
         for _ in 0..9 {
+            if !glider_fits {
+                break;
+            }
             // Inserting glider at a random position with random rotation
             let glider_size = 3;
             let x = self
@@ -145,28 +178,104 @@ impl TerminalEffect for ConwayLife {
                 .rng
                 .random_range(2..self.buffer.height - glider_size + 1);
             let rotation = [0, 90, 180, 270][self.rng.random_range(0..4)];
-            insert_glider(&mut next_cells, x, y, rotation, self.current_gen);
+            insert_glider(&mut next_cells, x, y, rotation, color);
         }
         self.cells = next_cells;
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // population survives, cells outside of new screen are lost
+        self.cells
+            .retain(|(x, y), _| *x < width as usize && *y < height as usize);
         self.screen_size = (width, height);
+        self.buffer = Buffer::new(width as usize, height as usize);
     }
 
     fn reset(&mut self) {
-        todo!();
+        *self = Self::new(self.options.clone(), self.screen_size);
+    }
+
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        vec![("cells", self.cells.len())]
+    }
+
+    fn frame_rate(&self) -> f64 {
+        // generations are easier to follow at slower pace
+        10.0
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        // room for the glider
+        (5, 5)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        // seed again when the initial population changes, colors apply from
+        // the next generation
+        let options = config.get_life_options(self.screen_size);
+        if options.initial_cells != self.options.initial_cells {
+            *self = Self::new(options, self.screen_size);
+        } else {
+            self.palette = options.palette.or_builtin("green");
+            self.options = options;
+            let (width, height) = self.screen_size;
+            self.update_size(width, height);
+        }
+    }
+
+    fn snapshot(&self) -> Option<toml::Value> {
+        let mut cells: Vec<_> = self
+            .cells
+            .iter()
+            .map(|((x, y), cell)| (*x, *y, cell.character, SavedColor(cell.color)))
+            .collect();
+        cells.sort_by_key(|(x, y, _, _)| (*y, *x));
+        let state = LifeState {
+            generation: self.current_gen,
+            cells,
+        };
+        toml::Value::try_from(state).ok()
+    }
+
+    fn restore(
+        &mut self,
+        state: toml::Value,
+        screen_size: (u16, u16),
+    ) -> Result<(), String> {
+        let state: LifeState = state.try_into().map_err(|e| e.to_string())?;
+        let (width, height) = screen_size;
+        self.cells = state
+            .cells
+            .into_iter()
+            .filter(|(x, y, _, _)| *x < width as usize && *y < height as usize)
+            .map(|(x, y, character, color)| {
+                (
+                    (x, y),
+                    LifeCell {
+                        character,
+                        color: color.0,
+                    },
+                )
+            })
+            .collect();
+        // generations count modulo 255, hand edited files may say 255
+        self.current_gen = state.generation % 255;
+        self.screen_size = screen_size;
+        self.buffer = Buffer::new(width as usize, height as usize);
+        Ok(())
     }
 }
 
 impl ConwayLife {
     pub fn new(options: ConwayLifeOptions, screen_size: (u16, u16)) -> Self {
-        let mut rng = rand::rng();
+        let mut rng: SmallRng = random::rng();
         let buffer = Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
 
+        let palette = options.palette.or_builtin("green");
         let mut cells = HashMap::new();
         for _ in 0..options.initial_cells {
-            let lc = LifeCell::new('*');
+            let mut lc = LifeCell::new('*');
+            lc.color = palette.color(1.0);
             let x = rng.random_range(0..screen_size.0) as usize;
             let y = rng.random_range(0..screen_size.1) as usize;
 
@@ -180,9 +289,14 @@ impl ConwayLife {
             cells,
             rng,
             current_gen: 0,
+            palette,
         }
     }
 
+    fn generation_color(&self) -> style::Color {
+        self.palette.color(1.0 - self.current_gen as f32 / 255.0)
+    }
+
     pub fn fill_buffer(&mut self, buffer: &mut Buffer) {
         for ((x, y), cell) in self.cells.iter() {
             buffer.set(
@@ -199,7 +313,7 @@ fn insert_glider(
     x: usize,
     y: usize,
     rotation: i32,
-    current_gen: u8,
+    color: style::Color,
 ) {
     let base_glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
 
@@ -213,18 +327,12 @@ fn insert_glider(
         }
     });
 
-    let green_color = 255_u8.wrapping_sub(current_gen);
-
     for coords in rotated_glider {
         cells.insert(
             coords,
             LifeCell {
                 character: '0',
-                color: style::Color::Rgb {
-                    r: 0,
-                    g: green_color,
-                    b: 0,
-                },
+                color,
             },
         );
     }
@@ -286,12 +394,7 @@ impl DefaultOptions for ConwayLife {
     type Options = ConwayLifeOptions;
 
     fn default_options(width: u16, height: u16) -> Self::Options {
-        let initial_cells = ((width as u32 * height as u32) as f32 * 0.3) as u32; // 30% of screen space
-
-        ConwayLifeOptionsBuilder::default()
-            .initial_cells(initial_cells)
-            .build()
-            .unwrap()
+        ConwayLifeConfig::default().to_runtime((width, height))
     }
 }
 
@@ -299,6 +402,56 @@ impl DefaultOptions for ConwayLife {
 mod tests {
     use super::*;
 
+    #[test]
+    fn resize_keeps_cells_inside() {
+        let options = ConwayLifeOptionsBuilder::default()
+            .initial_cells(500_u32)
+            .build()
+            .unwrap();
+        let mut life = ConwayLife::new(options, (40, 20));
+        life.get_diff();
+
+        life.update_size(20, 10);
+        assert!(!life.cells.is_empty());
+        assert!(life.cells.keys().all(|(x, y)| *x < 20 && *y < 10));
+
+        // whole population is drawn again after resize
+        let diff = life.get_diff();
+        assert_eq!(diff.len(), life.cells.len());
+
+        life.reset();
+        assert_eq!(life.screen_size, (20, 10));
+    }
+
+    #[test]
+    fn restored_game_continues_from_snapshot() {
+        let options = ConwayLifeOptionsBuilder::default()
+            .initial_cells(300_u32)
+            .build()
+            .unwrap();
+        let mut life = ConwayLife::new(options.clone(), (40, 20));
+        for _ in 0..5 {
+            life.get_diff();
+            life.update();
+        }
+        let state = life.snapshot().unwrap();
+
+        let mut resumed = ConwayLife::new(options, (10, 10));
+        resumed.restore(state.clone(), (40, 20)).unwrap();
+        assert_eq!(resumed.snapshot(), Some(state.clone()));
+        assert_eq!(resumed.current_gen, 5);
+        assert_eq!(resumed.get_diff().len(), life.cells.len());
+
+        let broken = toml::Value::try_from(vec![1]).unwrap();
+        assert!(resumed.restore(broken, (40, 20)).is_err());
+
+        let mut edited = state.as_table().unwrap().clone();
+        edited.insert("generation".into(), 255.into());
+        resumed.restore(edited.into(), (40, 20)).unwrap();
+        resumed.update();
+        assert_eq!(resumed.current_gen, 1);
+    }
+
     #[test]
     fn empty_neighbors_by_index() {
         let buf = Buffer::new(3, 3);
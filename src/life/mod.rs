@@ -1,3 +1,6 @@
 pub mod effect;
 #[allow(unused)]
-pub use effect::{ConwayLife, ConwayLifeOptions, ConwayLifeOptionsBuilder};
+pub use effect::{
+    ConwayLife, ConwayLifeConfig, ConwayLifeConfigBuilder, ConwayLifeOptions,
+    ConwayLifeOptionsBuilder,
+};
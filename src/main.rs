@@ -1,6 +1,14 @@
 #![cfg(not(test))]
-use crossterm::{self, cursor, execute, terminal};
-use std::{io, process};
+use crossterm::{
+    self, cursor, execute,
+    style::{self, Stylize},
+    terminal,
+};
+use std::{
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+};
 
 mod blank;
 mod boids;
@@ -10,40 +18,65 @@ mod common;
 mod config;
 mod constellation;
 mod crab;
+mod crash;
 mod cube;
 mod donut;
 mod error;
+mod export;
 mod fire;
+mod gallery;
+mod governor;
+mod gradient;
+mod hud;
 mod life;
 mod maze;
+mod metrics;
+mod migrate;
+mod palette;
+mod particles;
 mod pipes;
 mod plasma;
+mod presets;
 mod rain;
+mod random;
+mod record;
+mod registry;
+mod reload;
+mod screenshot;
+mod snapshot;
+mod stream;
 mod terrain;
+mod theme;
 
-use crate::config::Config;
-
-const VALID_SAVERS: &[&str] = &[
-    "matrix",
-    "life",
-    "maze",
-    "boids",
-    "blank",
-    "cube",
-    "crab",
-    "donut",
-    "pipes",
-    "plasma",
-    "fire",
-    "constellation",
-];
+use crate::config::{Config, ConfigOverride, ConfigPaths};
+use crate::reload::ConfigSource;
 
 #[derive(Debug)]
 struct AppArgs {
-    screen_saver: String,
+    screen_saver: Option<String>,
     check: bool,
     effect: Option<String>,
     frames: Option<usize>,
+    metrics: Option<PathBuf>,
+    hud: bool,
+    fixed_quality: bool,
+    preset: Option<String>,
+    overrides: Vec<ConfigOverride>,
+    config: Option<PathBuf>,
+    screenshot_at: Option<usize>,
+    record: Option<record::RecordOptions>,
+    /// Record this many frames without a terminal
+    record_frames: Option<usize>,
+    export: Option<export::ExportOptions>,
+    seed: Option<u64>,
+    /// Stream frames as JSON lines to stdout instead of the terminal
+    output: Option<stream::StreamOptions>,
+    /// Virtual terminal size of `--output`
+    size: Option<(u16, u16)>,
+    /// Stream file to replay
+    play: Option<PathBuf>,
+    /// Snapshot file the effect continues from and is saved to at exit
+    resume: Option<PathBuf>,
 }
 
 /// Guard to drop out alternate screen in case of errors
@@ -61,6 +94,7 @@ impl TerminalGuard {
             cursor::Hide,
             terminal::Clear(terminal::ClearType::All)
         )?;
+        crash::set_terminal_active(true);
 
         Ok(Self { stdout })
     }
@@ -74,18 +108,16 @@ impl TerminalGuard {
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
         // Ignore errors during drop - we're doing best effort cleanup
-        let _ = execute!(
-            self.stdout,
-            cursor::Show,
-            terminal::Clear(terminal::ClearType::All),
-            terminal::LeaveAlternateScreen,
-        );
-        let _ = terminal::disable_raw_mode();
+        let _ = self.stdout.flush();
+        crash::restore_terminal();
+        crash::set_terminal_active(false);
     }
 }
 
 fn main() -> Result<(), error::TartsError> {
     env_logger::init();
+    crash::install_panic_hook();
+    crash::install_signal_handlers()?;
 
     let args = match parse_args() {
         Ok(v) => v,
@@ -98,111 +130,256 @@ fn main() -> Result<(), error::TartsError> {
     if args.check {
         let effect = args.effect.unwrap_or_else(|| "matrix".to_string());
         let frames = args.frames.unwrap_or(1);
+        crash::set_context(&effect, String::new(), terminal::size()?);
         return check::run_test_for_effect(&effect, frames);
     }
 
+    if let Some(path) = args.play.as_deref() {
+        let played = stream::FrameReader::open(path)
+            .map_err(Into::into)
+            .and_then(|reader| {
+                let mut guard = TerminalGuard::new()?;
+                stream::play(guard.get_stdout(), reader)
+            });
+        match played {
+            Ok(frames) => {
+                println!("Played {} frames of {}", frames, path.display())
+            }
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+        if let Some(signal) = crash::received_signal() {
+            process::exit(128 + signal);
+        }
+        return Ok(());
+    }
+
     // Check if valid before entering alternate screen
-    if !VALID_SAVERS.contains(&args.screen_saver.as_str()) {
-        println!("Unknown screen saver: {}", args.screen_saver);
+    if let Some(name) = args.screen_saver.as_deref()
+        && registry::find(name).is_none()
+    {
+        println!("Unknown screen saver: {}", name);
         print_help();
         return Ok(());
     }
 
-    let (config, config_status) = Config::load()?;
+    // before any effect is made, they take their generators on creation
+    if let Some(seed) = args.seed {
+        random::set_seed(seed);
+    }
+
+    let mut config_source = ConfigSource::new(
+        ConfigPaths::discover(args.config.as_deref()),
+        args.screen_saver.clone().zip(args.preset),
+        args.overrides,
+    );
+    let (config, config_status) = match config_source.load() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    let loop_options = common::LoopOptions {
+        hud: args.hud,
+        adaptive_quality: !args.fixed_quality,
+        config: Some(config_source),
+        theme: config.theme.clone(),
+        screenshot_at: args.screenshot_at,
+        record: args.record.clone(),
+        seed: args.seed,
+        snapshot: args.resume.clone(),
+        ..Default::default()
+    };
+
+    if let (Some(name), Some(record), Some(frames)) = (
+        args.screen_saver.as_deref(),
+        args.record.as_ref(),
+        args.record_frames,
+    ) {
+        let screen_size = terminal::size().unwrap_or((80, 24));
+        crash::set_context(name, config.effect_options_toml(name), screen_size);
+        let mut effect = registry::create(name, &config, screen_size)
+            .expect("effect is registered");
+        record::record_headless(
+            effect.as_mut(),
+            screen_size,
+            frames,
+            &config.theme,
+            record,
+        )?;
+        println!("{}", config_status);
+        println!(
+            "Recorded {} frames at {}x{} to {}",
+            frames,
+            screen_size.0,
+            screen_size.1,
+            record.path.display()
+        );
+        return Ok(());
+    }
+
+    if let (Some(name), Some(output)) =
+        (args.screen_saver.as_deref(), args.output.as_ref())
+    {
+        let screen_size = args
+            .size
+            .unwrap_or_else(|| terminal::size().unwrap_or((80, 24)));
+        crash::set_context(name, config.effect_options_toml(name), screen_size);
+        let mut effect = registry::create(name, &config, screen_size)
+            .expect("effect is registered");
+        // stdout carries the stream, the rest goes to stderr
+        eprintln!("{}", config_status);
+        let frames = stream::stream(
+            effect.as_mut(),
+            name,
+            screen_size,
+            &config.theme,
+            output,
+            io::stdout().lock(),
+        )?;
+        eprintln!(
+            "Streamed {} frames at {}x{}",
+            frames, screen_size.0, screen_size.1
+        );
+        if let Some(signal) = crash::received_signal() {
+            process::exit(128 + signal);
+        }
+        return Ok(());
+    }
+
+    if let (Some(name), Some(export)) =
+        (args.screen_saver.as_deref(), args.export.as_ref())
+    {
+        crash::set_context(
+            name,
+            config.effect_options_toml(name),
+            export.screen_size,
+        );
+        let mut effect = registry::create(name, &config, export.screen_size)
+            .expect("effect is registered");
+        let paths = export::export(effect.as_mut(), &config.theme, export)?;
+        println!("{}", config_status);
+        println!(
+            "Exported {} frames at {}x{} to {}",
+            export.frames,
+            export.screen_size.0,
+            export.screen_size.1,
+            paths[0].display()
+        );
+        if paths.len() > 1 {
+            println!("Saved {} stills next to it", paths.len() - 1);
+        }
+        return Ok(());
+    }
 
-    let fps = {
+    // outcome of saving the effect state at exit, with `--resume`
+    let mut saved = None;
+    let metrics = {
         let mut guard = TerminalGuard::new()?;
-        let (width, height) = terminal::size()?;
-
-        match args.screen_saver.as_str() {
-            "matrix" => {
-                let options = config.get_matrix_options((width, height));
-                let mut digital_rain =
-                    rain::digital_rain::DigitalRain::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut digital_rain, None)?
-            }
-            "life" => {
-                let options = config.get_life_options((width, height));
-                let mut conway_life =
-                    life::ConwayLife::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut conway_life, None)?
-            }
-            "maze" => {
-                let options = config.get_maze_options((width, height));
-                let mut maze = maze::Maze::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut maze, None)?
-            }
-            "boids" => {
-                let options = config.get_boids_options((width, height));
-                let mut boids = boids::Boids::new(options);
-                common::run_loop(guard.get_stdout(), &mut boids, None)?
-            }
-            "blank" => {
-                let options = config.get_blank_options();
-                let mut blank = blank::Blank::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut blank, None)?
-            }
-            "cube" => {
-                let options = config.get_cube_options();
-                let mut cube = cube::Cube::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut cube, None)?
-            }
-            "crab" => {
-                let options = config.get_crab_options((width, height));
-                let mut crab = crab::Crab::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut crab, None)?
-            }
-            "donut" => {
-                let options = config.get_donut_options((width, height));
-                let mut donut = donut::Donut::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut donut, None)?
-            }
-            "pipes" => {
-                let options = config.get_pipes_options();
-                let mut pipes = pipes::Pipes::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut pipes, None)?
-            }
-            "plasma" => {
-                let options = config.get_plasma_options();
-                let mut plasma = plasma::Plasma::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut plasma, None)?
-            }
-            "fire" => {
-                let options = config.get_fire_options();
-                let mut fire = fire::Fire::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut fire, None)?
-            }
-            "constellation" => {
-                let options = config.get_constellation_options();
-                let mut constellation =
-                    constellation::Constellation::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut constellation, None)?
-            }
-            "terrain" => {
-                let options = config.get_terrain_options();
-                let mut terrain = terrain::Terrain::new(options, (width, height));
-                common::run_loop(guard.get_stdout(), &mut terrain, None)?
-            }
-            _ => {
-                println!(
-                    "Pick screensaver: [matrix, life, maze, boids, cube, crab, donut]"
+        let screen_size = terminal::size()?;
+
+        match args.screen_saver.as_deref() {
+            Some(name) => {
+                crash::set_context(
+                    name,
+                    config.effect_options_toml(name),
+                    screen_size,
                 );
-                0.0
+                // name is validated above, so effect is always there
+                let mut effect = registry::create(name, &config, screen_size)
+                    .expect("effect is registered");
+                if let Some(path) = args.resume.as_deref()
+                    && let Err(e) =
+                        snapshot::resume(effect.as_mut(), name, path, screen_size)
+                {
+                    drop(guard);
+                    eprintln!("{}: {}", path.display(), e);
+                    process::exit(1);
+                }
+                let options = common::LoopOptions {
+                    name: name.to_string(),
+                    ..loop_options
+                };
+                let metrics = common::run_loop(
+                    guard.get_stdout(),
+                    effect.as_mut(),
+                    &options,
+                )?;
+                if let Some(path) = args.resume.as_deref() {
+                    let state = effect.snapshot().ok_or_else(|| {
+                        io::Error::other(format!("{} can't save its state", name))
+                    });
+                    saved = Some(state.and_then(|state| {
+                        snapshot::Snapshot::new(name, terminal::size()?, state)
+                            .save(path)
+                    }));
+                }
+                Some(metrics)
+            }
+            None => {
+                gallery::run_gallery(guard.get_stdout(), &config, &loop_options)?
             }
         }
     };
 
     println!("{}", config_status);
-    println!("Frames per second: {:.1}", fps);
+    if let (Some(saved), Some(path)) = (saved, args.resume.as_deref()) {
+        match saved {
+            Ok(()) => println!("State saved to {}", path.display()),
+            Err(e) => eprintln!("State not saved to {}: {}", path.display(), e),
+        }
+    }
+    if let Some(metrics) = metrics {
+        println!("{}", metrics.report());
+        if let Some(path) = args.metrics.as_deref() {
+            metrics.write_json(path)?;
+            println!("Frame metrics written to {}", path.display());
+        }
+    }
+    if let Some(signal) = crash::received_signal() {
+        process::exit(128 + signal);
+    }
     Ok(())
 }
 
 fn parse_args() -> Result<AppArgs, String> {
     let mut args = std::env::args().skip(1);
-    let mut screen_saver = "matrix".to_string();
+    let mut screen_saver = None;
     let mut check = false;
     let mut effect = None;
     let mut frames = None;
+    let mut metrics = None;
+    let mut hud = false;
+    let mut fixed_quality = false;
+    let mut preset = None;
+    let mut config = None;
+    let mut screenshot_at = None;
+    let mut record = None;
+    let mut record_frames = None;
+    let mut idle_time_limit = None;
+    let mut export = false;
+    let mut out = None;
+    let mut size = None;
+    let mut cell = None;
+    let mut stills = None;
+    let mut seed = None;
+    let mut output = false;
+    let mut keyframes = None;
+    let mut play = false;
+    let mut play_file = None;
+    let mut resume = None;
+    // handled once `--config` is known, it may come later
+    let mut validate_config = false;
+    let mut migrate_config = false;
+    let mut list_presets_of = None;
+    let mut list_palettes = false;
+    let mut overrides = Vec::new();
+    // `-o key=value` needs the effect name, which may come later
+    let mut effect_overrides = Vec::new();
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -224,6 +401,27 @@ fn parse_args() -> Result<AppArgs, String> {
                 }
                 std::process::exit(0);
             }
+            "--validate-config" => {
+                validate_config = true;
+            }
+            "--migrate-config" => {
+                migrate_config = true;
+            }
+            "--config" => {
+                let path = args.next().ok_or("--config requires a file path")?;
+                config = Some(PathBuf::from(path));
+            }
+            "--list-presets" => {
+                let name =
+                    args.next().ok_or("--list-presets requires an effect")?;
+                list_presets_of = Some(name);
+            }
+            "--list-palettes" => {
+                list_palettes = true;
+            }
+            "--preset" => {
+                preset = Some(args.next().ok_or("--preset requires a name")?);
+            }
             "--effect" => {
                 effect = args.next();
             }
@@ -232,11 +430,132 @@ fn parse_args() -> Result<AppArgs, String> {
                     frames = frame_str.parse().ok();
                 }
             }
+            "--hud" => {
+                hud = true;
+            }
+            "--fixed-quality" => {
+                fixed_quality = true;
+            }
+            "--set" => {
+                let arg = args.next().ok_or("--set requires section.key=value")?;
+                overrides
+                    .push(ConfigOverride::parse(&arg).map_err(|e| e.to_string())?);
+            }
+            "-o" | "--option" => {
+                let arg = args.next().ok_or("-o requires key=value")?;
+                effect_overrides.push(arg);
+            }
+            "--metrics" => {
+                let path = args.next().ok_or("--metrics requires a file path")?;
+                metrics = Some(PathBuf::from(path));
+            }
+            "--screenshot-at" => {
+                let frame =
+                    args.next().ok_or("--screenshot-at requires a frame")?;
+                screenshot_at = Some(
+                    frame
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|frame| *frame > 0)
+                        .ok_or("--screenshot-at requires a frame number from 1")?,
+                );
+            }
+            "--record" => {
+                let path = args.next().ok_or("--record requires a file path")?;
+                record = Some(PathBuf::from(path));
+            }
+            "--record-frames" => {
+                let frames =
+                    args.next().ok_or("--record-frames requires a number")?;
+                record_frames = Some(
+                    frames
+                        .parse::<usize>()
+                        .map_err(|_| "--record-frames requires a number")?,
+                );
+            }
+            "--idle-time-limit" => {
+                let secs =
+                    args.next().ok_or("--idle-time-limit requires seconds")?;
+                idle_time_limit = Some(
+                    secs.parse::<f64>()
+                        .ok()
+                        .filter(|secs| *secs > 0.0)
+                        .ok_or("--idle-time-limit requires positive seconds")?,
+                );
+            }
+            "--out" => {
+                let path = args.next().ok_or("--out requires a file path")?;
+                out = Some(PathBuf::from(path));
+            }
+            "--size" => {
+                let value = args.next().ok_or("--size requires COLSxROWS")?;
+                size = Some(
+                    parse_size(&value)
+                        .ok_or("--size requires COLSxROWS, e.g. 80x24")?,
+                );
+            }
+            "--cell" => {
+                let value = args.next().ok_or("--cell requires WxH")?;
+                cell = Some(
+                    parse_size(&value)
+                        .ok_or("--cell requires WxH pixels, e.g. 8x16")?,
+                );
+            }
+            "--stills" => {
+                let every = args.next().ok_or("--stills requires a number")?;
+                stills = Some(
+                    every
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|every| *every > 0)
+                        .ok_or("--stills requires a number from 1")?,
+                );
+            }
+            "--seed" => {
+                let value = args.next().ok_or("--seed requires a number")?;
+                seed = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| "--seed requires a number")?,
+                );
+            }
+            "--output" => {
+                let format = args.next().ok_or("--output requires a format")?;
+                if format != "jsonl" {
+                    return Err(format!(
+                        "Unknown output format `{}`, only jsonl is supported",
+                        format
+                    ));
+                }
+                output = true;
+            }
+            "--keyframes" => {
+                let every = args.next().ok_or("--keyframes requires a number")?;
+                keyframes = Some(
+                    every
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|every| *every > 0)
+                        .ok_or("--keyframes requires a number from 1")?,
+                );
+            }
+            "--resume" => {
+                let path = args.next().ok_or("--resume requires a file path")?;
+                resume = Some(PathBuf::from(path));
+            }
+            "export" if !check && !export && !play && screen_saver.is_none() => {
+                export = true;
+            }
+            "play" if !check && !export && !play && screen_saver.is_none() => {
+                play = true;
+            }
             arg if !arg.starts_with('-') => {
-                if check {
+                if play && play_file.is_none() {
+                    play_file = Some(PathBuf::from(arg));
+                } else if check {
                     effect = Some(arg.to_string());
                 } else {
-                    screen_saver = arg.to_string();
+                    screen_saver = Some(arg.to_string());
                 }
             }
             _ => {
@@ -245,51 +564,374 @@ fn parse_args() -> Result<AppArgs, String> {
         }
     }
 
+    if migrate_config {
+        let path = ConfigPaths::discover(config.as_deref()).user;
+        match migrate::migrate_file(&path) {
+            Ok(report) => {
+                print_migration(&path, &report);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(effect) = list_presets_of {
+        list_presets(&effect, config.as_deref());
+        std::process::exit(0);
+    }
+    if list_palettes {
+        print_palettes(config.as_deref());
+        std::process::exit(0);
+    }
+    if preset.is_some() && screen_saver.is_none() {
+        return Err(
+            "--preset needs an effect, e.g. tarts matrix --preset storm".into()
+        );
+    }
+    if screenshot_at.is_some() && screen_saver.is_none() {
+        return Err(
+            "--screenshot-at needs an effect, e.g. tarts fire --screenshot-at 100"
+                .into(),
+        );
+    }
+    if record.is_none() && (record_frames.is_some() || idle_time_limit.is_some()) {
+        return Err("--record-frames and --idle-time-limit need --record".into());
+    }
+    if record.is_some() && screen_saver.is_none() {
+        return Err(
+            "--record needs an effect, e.g. tarts matrix --record matrix.cast"
+                .into(),
+        );
+    }
+    if export && screen_saver.is_none() {
+        return Err("export needs an effect, e.g. tarts export matrix".into());
+    }
+    if play && play_file.is_none() {
+        return Err("play needs a file, e.g. tarts play matrix.jsonl".into());
+    }
+    if !export && (out.is_some() || cell.is_some() || stills.is_some()) {
+        return Err("--out, --cell and --stills are options of export".into());
+    }
+    if !export && !output && size.is_some() {
+        return Err("--size is an option of export and --output".into());
+    }
+    if output && screen_saver.is_none() {
+        return Err(
+            "--output needs an effect, e.g. tarts matrix --output jsonl".into()
+        );
+    }
+    if resume.is_some() && screen_saver.is_none() {
+        return Err(
+            "--resume needs an effect, e.g. tarts life --resume life.toml".into(),
+        );
+    }
+    if resume.is_some() && (export || output || record_frames.is_some()) {
+        return Err(
+            "--resume works with effects running in the terminal only".into()
+        );
+    }
+    if !output && keyframes.is_some() {
+        return Err("--keyframes needs --output jsonl".into());
+    }
+    let export = match screen_saver.as_deref() {
+        Some(name) if export => {
+            // exports repeat unless asked otherwise
+            seed = seed.or(Some(export::DEFAULT_SEED));
+            let out = out.unwrap_or_else(|| PathBuf::from(format!("{}.gif", name)));
+            if export::format_of(&out).is_none() {
+                return Err(format!(
+                    "--out {} must end with .gif or .png",
+                    out.display()
+                ));
+            }
+            if frames == Some(0) {
+                return Err("export requires --frames from 1".into());
+            }
+            Some(export::ExportOptions {
+                out,
+                frames: frames.unwrap_or(300),
+                screen_size: size.unwrap_or((80, 24)),
+                cell_size: cell.unwrap_or((8, 16)),
+                stills,
+            })
+        }
+        _ => None,
+    };
+    if !effect_overrides.is_empty() {
+        let section = screen_saver
+            .as_deref()
+            .ok_or("-o needs an effect, use --set section.key=value instead")?;
+        for arg in effect_overrides {
+            overrides.push(
+                ConfigOverride::parse_for_section(section, &arg)
+                    .map_err(|e| e.to_string())?,
+            );
+        }
+    }
+
+    if validate_config {
+        // same layers as a running effect, preset and overrides included
+        let mut source = ConfigSource::new(
+            ConfigPaths::discover(config.as_deref()),
+            screen_saver.clone().zip(preset.clone()),
+            overrides.clone(),
+        );
+        match source.load() {
+            Ok((_, status)) => {
+                println!("{}", status);
+                println!("Config is valid");
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     Ok(AppArgs {
         screen_saver,
         check,
         effect,
         frames,
+        metrics,
+        hud,
+        fixed_quality,
+        preset,
+        overrides,
+        config,
+        screenshot_at,
+        record: record.map(|path| record::RecordOptions {
+            path,
+            idle_time_limit,
+        }),
+        record_frames,
+        export,
+        seed,
+        output: output.then_some(stream::StreamOptions { frames, keyframes }),
+        size,
+        play: play_file,
+        resume,
     })
 }
 
+/// `80x24` as `(80, 24)`, both parts from 1
+fn parse_size(value: &str) -> Option<(u16, u16)> {
+    let (width, height) = value.split_once('x')?;
+    let size = (width.parse().ok()?, height.parse().ok()?);
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+fn print_migration(path: &Path, report: &migrate::MigrationReport) {
+    let Some(backup) = &report.backup else {
+        println!(
+            "{} is already at version {}",
+            path.display(),
+            migrate::CURRENT_VERSION
+        );
+        return;
+    };
+    println!(
+        "Migrated {} from version {} to {}",
+        path.display(),
+        report.from,
+        migrate::CURRENT_VERSION
+    );
+    for note in &report.notes {
+        println!("  {}", note);
+    }
+    println!("Original saved as {}", backup.display());
+}
+
+/// Print built-in and user presets of the effect
+fn list_presets(effect: &str, config_path: Option<&Path>) {
+    if registry::find(effect).is_none() {
+        eprintln!("Unknown screen saver: {}", effect);
+        std::process::exit(1);
+    }
+    let config = match Config::load(config_path) {
+        Ok((config, _)) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let presets = config.presets.of(effect);
+    if presets.is_empty() {
+        println!("No presets for {}", effect);
+        return;
+    }
+    for (name, preset) in presets {
+        let origin = if preset.builtin { "built-in" } else { "user" };
+        println!("{:<12} {:<9} {}", name, origin, preset.summary());
+    }
+}
+
+/// Print built-in and user palettes, with a color swatch on terminals
+fn print_palettes(config_path: Option<&Path>) {
+    let config = match Config::load(config_path) {
+        Ok((config, _)) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let builtin = palette::BUILTIN
+        .iter()
+        .filter(|name| !config.palettes.contains_key(**name))
+        .filter_map(|name| {
+            Some((*name, palette::Palette::builtin(name)?, "built-in"))
+        });
+    let user = config
+        .palettes
+        .iter()
+        .map(|(name, palette)| (name.as_str(), palette.clone(), "user"));
+    let swatch = std::io::stdout().is_terminal();
+    for (name, palette, origin) in builtin.chain(user) {
+        let colors = if swatch {
+            palette
+                .ramp(24)
+                .into_iter()
+                .map(|color| format!("{}", style::style('█').with(color)))
+                .collect()
+        } else {
+            format!("{} colors", palette.colors.len())
+        };
+        println!("{:<12} {:<9} {}", name, origin, colors);
+    }
+}
+
 fn print_help() {
     println!("tarts - Terminal screensavers");
     println!();
     println!("USAGE:");
     println!("    tarts [EFFECT] [OPTIONS]");
+    println!("    tarts export EFFECT [--out FILE] [EXPORT OPTIONS]");
+    println!("    tarts play FILE");
     println!();
     println!("EFFECTS:");
-    println!("    matrix      Matrix digital rain");
-    println!("    life        Conway's Game of Life");
-    println!("    maze        Maze generation");
-    println!("    boids       Boids flocking simulation");
-    println!("    cube        3D cube rotation");
-    println!("    crab        ASCII crab animation");
-    println!("    donut       3D donut rotation");
-    println!("    pipes       Pipe maze animation");
-    println!("    plasma      Plasma effect");
-    println!("    fire        Fire simulation");
-    println!("    terrain     Terrain generation");
-    println!("    constellation  Drifting stars and dotted connections");
-    println!("    blank       Blank screen");
+    for info in registry::EFFECTS {
+        println!("    {:<14} {}", info.name, info.description);
+    }
+    println!();
+    println!("    Run without EFFECT to pick one from the gallery.");
     println!();
     println!("OPTIONS:");
     println!("    -h, --help              Show help");
     println!("    -v, --version           Show version");
     println!("        --check             Run test mode");
     println!("        --effect <EFFECT>    Effect to test (with --check)");
-    println!("        --frames <NUM>       Number of frames to run (with --check)");
+    println!(
+        "        --frames <NUM>       Frames to run (--check, export, --output)"
+    );
+    println!("        --config <FILE>      Use FILE instead of the user config");
     println!("        --print-config       Print default config as TOML to stdout");
+    println!(
+        "        --set <KEY=VALUE>    Override config value, e.g. boids.max_speed=3"
+    );
+    println!("    -o, --option <KEY=VALUE> Override value of the running effect");
+    println!(
+        "        --preset <NAME>      Apply named preset of the running effect"
+    );
+    println!("        --list-presets <EFFECT> List presets of the effect");
+    println!(
+        "        --list-palettes      List color palettes for `palette` options"
+    );
+    println!(
+        "        --validate-config    Check config with preset and overrides applied"
+    );
+    println!(
+        "        --migrate-config     Update user config to current layout, keeps .bak"
+    );
+    println!(
+        "        --metrics <FILE>     Dump timings of the last 108000 frames as JSON"
+    );
+    println!(
+        "        --hud                Show debug HUD, F1 toggles it while running"
+    );
+    println!(
+        "        --fixed-quality      Don't scale effect load to keep frame rate"
+    );
+    println!(
+        "        --screenshot-at <N>  Save frame N as .ans, .txt and .html and exit"
+    );
+    println!("        --record <FILE>      Record the effect as asciicast v2");
+    println!(
+        "        --record-frames <N>  Record N frames without a terminal and exit"
+    );
+    println!(
+        "        --idle-time-limit <SECS> Shorten pauses in the recording to SECS"
+    );
+    println!(
+        "        --seed <N>           Seed effect randomness, runs repeat exactly"
+    );
+    println!(
+        "        --output jsonl       Stream frames as JSON lines to stdout in real time"
+    );
+    println!(
+        "        --keyframes <N>      Write all cells every N frames (with --output)"
+    );
+    println!(
+        "        --size <COLSxROWS>   Screen size of --output (default terminal size)"
+    );
+    println!(
+        "        --resume <FILE>      Continue the effect from FILE, save it there at exit"
+    );
+    println!(
+        "    `tarts play FILE` replays a saved --output stream with its timing."
+    );
+    println!();
+    println!("EXPORT OPTIONS:");
+    println!(
+        "        --out <FILE>         .gif animation or .png of the last frame"
+    );
+    println!("                             (default <EFFECT>.gif)");
+    println!("        --frames <NUM>       Frames to render (default 300)");
+    println!("        --size <COLSxROWS>   Virtual terminal size (default 80x24)");
+    println!("        --cell <WxH>         Pixels of one cell (default 8x16)");
+    println!("        --stills <N>         Also save every N-th frame as PNG");
+    println!("    Exports use a fixed seed unless --seed is given.");
+    println!();
+    println!(
+        "    Press `s` while an effect runs to save a screenshot at any time,"
+    );
+    println!("    `w` saves its state (life, boids, maze, pipes, constellation).");
     println!();
     println!("CONFIG:");
-    println!("    Config file (optional): ~/.config/tarts.toml");
+    println!("    Config files (optional), later ones override earlier keys:");
+    println!("      /etc/tarts.toml");
+    println!(
+        "      --config, $TARTS_CONFIG or $XDG_CONFIG_HOME/tarts.toml (~/.config)"
+    );
+    println!("      .tarts.toml in the current directory");
+    println!("      --preset, --set and -o from the command line");
     println!(
         "    Generate one with:      tarts --print-config > ~/.config/tarts.toml"
     );
+    println!("    Presets:                [<effect>.presets.<name>] tables");
+    println!(
+        "    Palettes:               [palettes.<name>] colors = [\"#rrggbb\", ...]"
+    );
     println!();
     println!("EXAMPLES:");
+    println!("    tarts                   Open effects gallery");
     println!("    tarts matrix            Run Matrix effect");
+    println!("    tarts fire --metrics fire.json");
+    println!("    tarts boids --set boids.max_speed=3 --set boids.charset=Arrow");
+    println!("    tarts matrix -o drops_coeff=2.5");
+    println!("    tarts matrix --preset storm");
+    println!("    tarts --list-presets boids");
+    println!("    tarts fire -o palette=ice");
+    println!("    tarts plasma --screenshot-at 120");
+    println!("    tarts matrix --record matrix.cast --record-frames 480");
+    println!("    tarts export matrix --frames 300 --out matrix.gif");
+    println!("    tarts export life --out life.png --seed 7");
+    println!("    tarts fire --output jsonl --keyframes 60 > fire.jsonl");
+    println!("    tarts play fire.jsonl");
+    println!("    tarts life --resume life.toml");
+    println!("    tarts particles --preset sparks");
     println!("    tarts --check            Test with default effect");
     println!("    tarts --check life       Test Life effect");
     println!("    tarts --check --frames 100 life");
@@ -1,8 +1,11 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::{Palette, PaletteChoice};
+use crate::random;
 use crossterm::style;
 use derive_builder::Builder;
-use rand::{RngExt, seq::SliceRandom};
+use rand::{RngExt, rngs::SmallRng, seq::SliceRandom};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet, VecDeque},
@@ -20,16 +23,38 @@ static CHARACTERS_MAP: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| {
 
 /// Characters to draw more interesting view
 static CHARACTERS: LazyLock<Vec<char>> = LazyLock::new(|| {
+    // in label order, seeded runs pick the same characters
+    let mut labels: Vec<&&str> = CHARACTERS_MAP.keys().collect();
+    labels.sort();
     let mut v = Vec::new();
-    for (_, chars) in CHARACTERS_MAP.iter() {
-        v.append(&mut chars.chars().collect());
+    for label in labels {
+        v.append(&mut CHARACTERS_MAP[*label].chars().collect());
     }
     v
 });
 
 #[derive(Builder, Default, Debug, Clone, Serialize, Deserialize)]
 #[builder(public, setter(into))]
-pub struct MazeOptions {}
+pub struct MazeOptions {
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+impl MazeOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.palette("palette", &self.palette);
+    }
+}
+
+/// `snapshot` of the carving, walls are random noise and drawn anew
+#[derive(Serialize, Deserialize)]
+struct MazeState {
+    complete: bool,
+    paths: Vec<(usize, usize)>,
+    stack: Vec<(isize, isize)>,
+}
 
 pub struct Maze {
     pub screen_size: (u16, u16),
@@ -39,7 +64,7 @@ pub struct Maze {
     paths: HashSet<(usize, usize)>,
     stack: VecDeque<(isize, isize)>,
     maze_complete: bool,
-    pub rng: rand::prelude::ThreadRng,
+    pub rng: SmallRng,
 }
 
 impl TerminalEffect for Maze {
@@ -50,19 +75,17 @@ impl TerminalEffect for Maze {
         }
         let mut curr_buffer = self.initial_walls.clone();
         let mut modified_cells = HashSet::new();
-        // Randomly change 5 distinct cells
-        while modified_cells.len() < 3 {
+        // Randomly change 3 distinct cells (if screen has them)
+        let changes = 3.min(curr_buffer.width * curr_buffer.height);
+        while modified_cells.len() < changes {
             let x = self.rng.random_range(0..curr_buffer.width);
             let y = self.rng.random_range(0..curr_buffer.height);
 
             if modified_cells.insert((x, y)) {
                 let random_char =
                     CHARACTERS[self.rng.random_range(0..CHARACTERS.len())];
-                let random_color = style::Color::Rgb {
-                    r: self.rng.random_range(0..200) as u8,
-                    g: self.rng.random_range(0..256) as u8,
-                    b: self.rng.random_range(0..200) as u8,
-                };
+                let random_color =
+                    wall_color(&mut self.rng, self.options.palette.palette(), 200);
                 self.initial_walls.set(
                     x,
                     y,
@@ -126,16 +149,23 @@ impl TerminalEffect for Maze {
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
-        self.screen_size = (width, height);
+        let paths = std::mem::take(&mut self.paths);
+        let stack = std::mem::take(&mut self.stack);
+        let mut maze = Self::new(self.options.clone(), (width, height));
+        maze.carry_over(paths, stack, self.maze_complete);
+        *self = maze;
     }
 
     fn reset(&mut self) {
         let mut new_effect = Self::new(self.options.clone(), self.screen_size);
-        fill_initial_walls(&mut new_effect.initial_walls);
+        fill_initial_walls(
+            &mut new_effect.initial_walls,
+            self.options.palette.palette(),
+        );
         new_effect.maze_complete = false;
         new_effect.paths.clear();
         new_effect.stack.clear();
-        new_effect.rng = rand::rng();
+        new_effect.rng = random::rng();
 
         let start_x = new_effect.rng.random_range(0..self.screen_size.0);
         let start_y = new_effect.rng.random_range(0..self.screen_size.1);
@@ -144,11 +174,47 @@ impl TerminalEffect for Maze {
             .push_back((start_x as isize, start_y as isize));
         *self = new_effect;
     }
+
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        vec![("paths", self.paths.len()), ("stack", self.stack.len())]
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (3, 3)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        self.options = config.get_maze_options(self.screen_size);
+        self.reset();
+    }
+
+    fn snapshot(&self) -> Option<toml::Value> {
+        let mut paths: Vec<_> = self.paths.iter().copied().collect();
+        paths.sort_by_key(|(x, y)| (*y, *x));
+        let state = MazeState {
+            complete: self.maze_complete,
+            paths,
+            stack: self.stack.iter().copied().collect(),
+        };
+        toml::Value::try_from(state).ok()
+    }
+
+    fn restore(
+        &mut self,
+        state: toml::Value,
+        screen_size: (u16, u16),
+    ) -> Result<(), String> {
+        let state: MazeState = state.try_into().map_err(|e| e.to_string())?;
+        let mut maze = Self::new(self.options.clone(), screen_size);
+        maze.carry_over(state.paths, state.stack, state.complete);
+        *self = maze;
+        Ok(())
+    }
 }
 
 impl Maze {
     pub fn new(options: MazeOptions, screen_size: (u16, u16)) -> Self {
-        let mut rng = rand::rng();
+        let mut rng: SmallRng = random::rng();
         let buffer = Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
 
         let paths = HashSet::new();
@@ -158,7 +224,7 @@ impl Maze {
         stack.push_back((start_x as isize, start_y as isize));
 
         let mut initial_walls = buffer.clone();
-        fill_initial_walls(&mut initial_walls);
+        fill_initial_walls(&mut initial_walls, options.palette.palette());
 
         Self {
             screen_size,
@@ -179,21 +245,59 @@ impl Maze {
             && (y as usize) < (self.screen_size.1 as usize)
     }
 
+    /// Continue carving the maze of another screen size, cells outside of
+    /// this screen are dropped
+    fn carry_over(
+        &mut self,
+        paths: impl IntoIterator<Item = (usize, usize)>,
+        stack: impl IntoIterator<Item = (isize, isize)>,
+        complete: bool,
+    ) {
+        self.paths = paths
+            .into_iter()
+            .filter(|&(x, y)| self.is_valid_cell(x as isize, y as isize))
+            .collect();
+        let stack: VecDeque<_> = stack
+            .into_iter()
+            .filter(|&(x, y)| self.is_valid_cell(x, y))
+            .collect();
+        if !stack.is_empty() {
+            self.stack = stack;
+        } else if let Some(&(x, y)) = self.paths.iter().min() {
+            // backtracking went off screen, go on from a carved cell
+            self.stack = VecDeque::from([(x as isize, y as isize)]);
+        }
+        self.maze_complete = complete;
+    }
+
     fn carve_path(&mut self, x: isize, y: isize) {
         self.paths.insert((x as usize, y as usize));
     }
 }
 
-fn fill_initial_walls(buffer: &mut Buffer) {
-    let mut rng = rand::rng();
+/// Random greenish color with red and blue below `max_rb`, or a random
+/// color of the palette
+fn wall_color(
+    rng: &mut impl RngExt,
+    palette: Option<&Palette>,
+    max_rb: u16,
+) -> style::Color {
+    match palette {
+        Some(palette) => palette.color(rng.random_range(0.0..=1.0)),
+        None => style::Color::Rgb {
+            r: rng.random_range(0..max_rb) as u8,
+            g: rng.random_range(0..256) as u8,
+            b: rng.random_range(0..max_rb) as u8,
+        },
+    }
+}
+
+fn fill_initial_walls(buffer: &mut Buffer, palette: Option<&Palette>) {
+    let mut rng = random::rng();
     for y in 0..buffer.height {
         for x in 0..buffer.width {
             let random_char = CHARACTERS[rng.random_range(0..CHARACTERS.len())];
-            let random_color = style::Color::Rgb {
-                r: rng.random_range(0..120) as u8,
-                g: rng.random_range(0..256) as u8,
-                b: rng.random_range(0..120) as u8,
-            };
+            let random_color = wall_color(&mut rng, palette, 120);
             buffer.set(
                 x,
                 y,
@@ -0,0 +1,374 @@
+//! Per-frame timing of the main loop.
+//!
+//! Every frame records time spent in input handling (including waiting for
+//! the next frame), `update`, `get_diff` and writing to the terminal, plus
+//! the number of changed cells and bytes written. At exit the loop prints
+//! percentiles, with `--metrics out.json` the raw series is dumped as well.
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Raw series keeps last 30 minutes at 60 fps, older frames are dropped
+const MAX_SAMPLES: usize = 60 * 60 * 30;
+
+/// Timings of a single frame, durations in microseconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FrameSample {
+    /// Handling input and waiting until the next frame is due
+    pub input_us: u64,
+    pub update_us: u64,
+    pub diff_us: u64,
+    pub write_us: u64,
+    /// Whole frame including sleep to keep target frame rate
+    pub frame_us: u64,
+    pub changed_cells: usize,
+    pub bytes: usize,
+    /// Frames merged into this one because output fell behind
+    pub dropped: usize,
+    /// Effect quality set by the governor, 1.0 when it is off
+    pub quality: f32,
+}
+
+/// 50th, 95th and 99th percentiles of a series
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricsSummary {
+    pub frames: usize,
+    /// Frames computed by the effect but merged away before output
+    pub dropped_frames: usize,
+    /// Oldest frames over `MAX_SAMPLES` left out of percentiles and the
+    /// raw series
+    pub truncated_frames: usize,
+    pub elapsed_secs: f64,
+    pub fps: f64,
+    pub input_ms: Percentiles,
+    pub update_ms: Percentiles,
+    pub diff_ms: Percentiles,
+    pub write_ms: Percentiles,
+    pub frame_ms: Percentiles,
+    pub changed_cells: Percentiles,
+    pub bytes: Percentiles,
+}
+
+/// Collected frame samples of one `run_loop` call
+#[derive(Debug, Clone)]
+pub struct FrameMetrics {
+    started_at: Instant,
+    elapsed: Option<Duration>,
+    frames: usize,
+    dropped_frames: usize,
+    samples: VecDeque<FrameSample>,
+    /// Files written by screenshots, listed in the report
+    screenshots: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct MetricsDump<'a> {
+    summary: MetricsSummary,
+    frames: &'a VecDeque<FrameSample>,
+}
+
+/// Writer which counts bytes passed to the inner writer
+pub struct CountingWriter<W: Write> {
+    inner: W,
+    bytes: usize,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, bytes: 0 }
+    }
+
+    /// Total number of bytes written so far
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Default for FrameSample {
+    fn default() -> Self {
+        Self {
+            input_us: 0,
+            update_us: 0,
+            diff_us: 0,
+            write_us: 0,
+            frame_us: 0,
+            changed_cells: 0,
+            bytes: 0,
+            dropped: 0,
+            quality: 1.0,
+        }
+    }
+}
+
+#[inline]
+pub fn as_micros(duration: Duration) -> u64 {
+    duration.as_micros() as u64
+}
+
+impl Default for FrameMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameMetrics {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            elapsed: None,
+            frames: 0,
+            dropped_frames: 0,
+            samples: VecDeque::new(),
+            screenshots: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, sample: FrameSample) {
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        self.frames += 1;
+        self.dropped_frames += sample.dropped;
+    }
+
+    pub fn record_screenshot(&mut self, paths: Vec<PathBuf>) {
+        self.screenshots.extend(paths);
+    }
+
+    /// Stop the clock, fps is calculated over time between `new` and `finish`
+    pub fn finish(&mut self) {
+        self.elapsed = Some(self.started_at.elapsed());
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.elapsed.unwrap_or_else(|| self.started_at.elapsed())
+    }
+
+    /// Frames rendered per second of wall clock time
+    pub fn fps(&self) -> f64 {
+        let elapsed = self.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.frames as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    pub fn summary(&self) -> MetricsSummary {
+        let millis = |field: fn(&FrameSample) -> u64| {
+            self.percentiles(|sample| field(sample) as f64 / 1000.0)
+        };
+        MetricsSummary {
+            frames: self.frames,
+            dropped_frames: self.dropped_frames,
+            truncated_frames: self.frames - self.samples.len(),
+            elapsed_secs: self.elapsed().as_secs_f64(),
+            fps: self.fps(),
+            input_ms: millis(|sample| sample.input_us),
+            update_ms: millis(|sample| sample.update_us),
+            diff_ms: millis(|sample| sample.diff_us),
+            write_ms: millis(|sample| sample.write_us),
+            frame_ms: millis(|sample| sample.frame_us),
+            changed_cells: self.percentiles(|sample| sample.changed_cells as f64),
+            bytes: self.percentiles(|sample| sample.bytes as f64),
+        }
+    }
+
+    fn percentiles(&self, value: impl Fn(&FrameSample) -> f64) -> Percentiles {
+        let mut values: Vec<f64> = self.samples.iter().map(value).collect();
+        values.sort_by(f64::total_cmp);
+        Percentiles {
+            p50: percentile(&values, 50.0),
+            p95: percentile(&values, 95.0),
+            p99: percentile(&values, 99.0),
+        }
+    }
+
+    /// Human readable report printed at exit
+    pub fn report(&self) -> String {
+        let summary = self.summary();
+        let mut headline = format!(
+            "Frames: {} in {:.1}s, {:.1} fps",
+            summary.frames, summary.elapsed_secs, summary.fps
+        );
+        if summary.dropped_frames > 0 {
+            headline.push_str(&format!(", {} dropped", summary.dropped_frames));
+        }
+        let mut lines = vec![headline];
+        if summary.truncated_frames > 0 {
+            lines.push(format!(
+                "Percentiles of the last {} frames",
+                self.samples.len()
+            ));
+        }
+        lines.push(format!(
+            "{:<14} {:>10} {:>10} {:>10}",
+            "", "p50", "p95", "p99"
+        ));
+        let rows = [
+            ("input, ms", summary.input_ms, 3),
+            ("update, ms", summary.update_ms, 3),
+            ("diff, ms", summary.diff_ms, 3),
+            ("write, ms", summary.write_ms, 3),
+            ("frame, ms", summary.frame_ms, 3),
+            ("changed cells", summary.changed_cells, 0),
+            ("bytes", summary.bytes, 0),
+        ];
+        for (label, value, precision) in rows {
+            lines.push(format!(
+                "{:<14} {:>10.*} {:>10.*} {:>10.*}",
+                label,
+                precision,
+                value.p50,
+                precision,
+                value.p95,
+                precision,
+                value.p99
+            ));
+        }
+        for path in &self.screenshots {
+            lines.push(format!("Screenshot: {}", path.display()));
+        }
+        lines.join("\n")
+    }
+
+    /// Dump summary and raw frame series as JSON
+    pub fn write_json(&self, path: &Path) -> io::Result<()> {
+        let dump = MetricsDump {
+            summary: self.summary(),
+            frames: &self.samples,
+        };
+        let file = io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, &dump).map_err(io::Error::other)
+    }
+}
+
+/// Nearest-rank percentile of sorted values, zero for empty series
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(frame_us: u64) -> FrameSample {
+        FrameSample {
+            frame_us,
+            changed_cells: frame_us as usize,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let mut metrics = FrameMetrics::new();
+        for value in 1..=100 {
+            metrics.record(sample(value * 1000));
+        }
+        let summary = metrics.summary();
+        assert_eq!(summary.frames, 100);
+        assert_eq!(summary.frame_ms.p50, 50.0);
+        assert_eq!(summary.frame_ms.p95, 95.0);
+        assert_eq!(summary.frame_ms.p99, 99.0);
+        assert_eq!(summary.changed_cells.p99, 99000.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[test]
+    fn fps_is_frames_over_elapsed_time() {
+        let mut metrics = FrameMetrics::new();
+        for _ in 0..10 {
+            metrics.record(sample(1000));
+        }
+        metrics.elapsed = Some(Duration::from_secs(2));
+        assert_eq!(metrics.fps(), 5.0);
+        assert!(
+            metrics
+                .report()
+                .starts_with("Frames: 10 in 2.0s, 5.0 fps\n")
+        );
+
+        metrics.record(FrameSample {
+            dropped: 3,
+            ..sample(1000)
+        });
+        assert_eq!(metrics.summary().dropped_frames, 3);
+        assert!(
+            metrics
+                .report()
+                .starts_with("Frames: 11 in 2.0s, 5.5 fps, 3 dropped")
+        );
+    }
+
+    #[test]
+    fn long_runs_keep_last_samples() {
+        let mut metrics = FrameMetrics::new();
+        for value in 0..MAX_SAMPLES + 5 {
+            metrics.record(sample(value as u64));
+        }
+        let summary = metrics.summary();
+        assert_eq!(summary.frames, MAX_SAMPLES + 5);
+        assert_eq!(summary.truncated_frames, 5);
+        assert_eq!(metrics.samples.front().unwrap().frame_us, 5);
+        assert!(
+            metrics
+                .report()
+                .contains("Percentiles of the last 108000 frames")
+        );
+    }
+
+    #[test]
+    fn counting_writer() {
+        let mut writer = CountingWriter::new(Vec::new());
+        writer.write_all(b"hello").unwrap();
+        writer.write_all(b" world").unwrap();
+        assert_eq!(writer.bytes(), 11);
+    }
+
+    #[test]
+    fn json_dump_has_raw_series() {
+        let mut metrics = FrameMetrics::new();
+        metrics.record(sample(1500));
+        metrics.finish();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+        metrics.write_json(&path).unwrap();
+
+        let dump: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(dump["summary"]["frames"], 1);
+        assert_eq!(dump["frames"][0]["frame_us"], 1500);
+    }
+}
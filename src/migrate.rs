@@ -0,0 +1,213 @@
+//! Config schema versions and migrations.
+//!
+//! `tarts.toml` carries a top-level `version`, files without it are version
+//! 0. When a field moves, a migration from the previous version is appended
+//! to `MIGRATIONS`. Config layers are migrated in memory on every load,
+//! `tarts --migrate-config` rewrites the file on disk and keeps a backup.
+use crate::config::Config;
+use crate::error::ConfigError;
+use std::path::{Path, PathBuf};
+
+/// Layout written by this build
+pub const CURRENT_VERSION: i64 = 1;
+
+/// Moves a document one version up, returns notes about moved keys
+type Migration = fn(&mut toml::Table) -> Vec<String>;
+
+/// `MIGRATIONS[n]` turns version `n` into `n + 1`
+const MIGRATIONS: &[Migration] = &[v0_to_v1];
+
+/// Version 1 only adds the `version` key, sections are unchanged
+fn v0_to_v1(_document: &mut toml::Table) -> Vec<String> {
+    Vec::new()
+}
+
+/// Schema version of the document, 0 when it has none
+pub fn version(document: &toml::Table) -> Result<i64, ConfigError> {
+    version_up_to(document, CURRENT_VERSION)
+}
+
+fn version_up_to(document: &toml::Table, latest: i64) -> Result<i64, ConfigError> {
+    match document.get("version") {
+        None => Ok(0),
+        Some(toml::Value::Integer(version)) if *version > latest => {
+            Err(ConfigError::UnsupportedVersion(*version))
+        }
+        Some(toml::Value::Integer(version)) if *version >= 0 => Ok(*version),
+        Some(value) => Err(ConfigError::InvalidValue {
+            path: "version".to_string(),
+            reason: format!("must be 0..={}, got {}", latest, value),
+        }),
+    }
+}
+
+/// Bring document to `CURRENT_VERSION`, returns notes of the migrations
+/// which changed something
+pub fn migrate(document: &mut toml::Table) -> Result<Vec<String>, ConfigError> {
+    migrate_with(document, MIGRATIONS)
+}
+
+fn migrate_with(
+    document: &mut toml::Table,
+    migrations: &[Migration],
+) -> Result<Vec<String>, ConfigError> {
+    let latest = migrations.len() as i64;
+    let from = version_up_to(document, latest)?;
+    let mut notes = Vec::new();
+    for migration in &migrations[from as usize..] {
+        notes.extend(migration(document));
+    }
+    document.insert("version".to_string(), toml::Value::Integer(latest));
+    Ok(notes)
+}
+
+/// Keys of the document no config section or field is called like, as
+/// `section.key`
+pub fn unknown_keys(document: &toml::Table) -> Vec<String> {
+    let known =
+        toml::Table::try_from(Config::default()).expect("config is serializable");
+    let mut unknown = Vec::new();
+    for (name, section) in document {
+        // palette names are up to the user
+        if name == "version" || name == "palettes" {
+            continue;
+        }
+        let Some(toml::Value::Table(fields)) = known.get(name) else {
+            unknown.push(name.clone());
+            continue;
+        };
+        let Some(section) = section.as_table() else {
+            continue;
+        };
+        for key in section.keys() {
+            if key != "presets" && !fields.contains_key(key) {
+                unknown.push(format!("{}.{}", name, key));
+            }
+        }
+    }
+    unknown
+}
+
+/// Outcome of `migrate_file`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    /// Version the file had before
+    pub from: i64,
+    pub notes: Vec<String>,
+    /// Copy of the original file, `None` when it was already up to date
+    pub backup: Option<PathBuf>,
+}
+
+/// Rewrite config file in the current layout, the original is kept next
+/// to it as `<file>.bak`. Comments and key order are not preserved
+pub fn migrate_file(path: &Path) -> Result<MigrationReport, ConfigError> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut document: toml::Table = toml::from_str(&contents)?;
+    let from = version(&document)?;
+    if from == CURRENT_VERSION {
+        return Ok(MigrationReport {
+            from,
+            notes: Vec::new(),
+            backup: None,
+        });
+    }
+
+    let notes = migrate(&mut document)?;
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    std::fs::copy(path, &backup)?;
+    std::fs::write(path, toml::to_string_pretty(&document)?)?;
+    Ok(MigrationReport {
+        from,
+        notes,
+        backup: Some(backup),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml: &str) -> toml::Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    /// Pretend version 2 renamed `boids.speed` to `boids.max_speed`
+    fn rename_speed(document: &mut toml::Table) -> Vec<String> {
+        let Some(boids) = document.get_mut("boids").and_then(|s| s.as_table_mut())
+        else {
+            return Vec::new();
+        };
+        match boids.remove("speed") {
+            Some(speed) => {
+                boids.insert("max_speed".to_string(), speed);
+                vec!["boids.speed is now boids.max_speed".to_string()]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn documents_are_migrated_step_by_step() {
+        let migrations: &[Migration] = &[v0_to_v1, rename_speed];
+        let mut document = table("[boids]\nspeed = 3.0\n");
+        let notes = migrate_with(&mut document, migrations).unwrap();
+        assert_eq!(notes, vec!["boids.speed is now boids.max_speed"]);
+        assert_eq!(document, table("version = 2\n[boids]\nmax_speed = 3.0\n"));
+
+        // already at version 2, nothing to do
+        assert!(migrate_with(&mut document, migrations).unwrap().is_empty());
+
+        let mut current = table("version = 1\n[boids]\nspeed = 3.0\n");
+        migrate_with(&mut current, migrations).unwrap();
+        assert!(current["boids"].get("max_speed").is_some());
+    }
+
+    #[test]
+    fn newer_and_broken_versions_are_rejected() {
+        assert!(matches!(
+            migrate(&mut table("version = 7\n")),
+            Err(ConfigError::UnsupportedVersion(7))
+        ));
+        assert_eq!(
+            migrate(&mut table("version = \"1\"\n"))
+                .unwrap_err()
+                .to_string(),
+            "version: must be 0..=1, got \"1\""
+        );
+        assert_eq!(version(&table("[matrix]\n")).unwrap(), 0);
+    }
+
+    #[test]
+    fn unknown_keys_are_listed() {
+        let document = table(
+            "version = 1\n[boids]\nmax_sped = 3.0\nmax_speed = 3.0\n\
+             [boids.presets.fast]\nmax_speed = 4.0\n[bubbles]\ncount = 3\n",
+        );
+        assert_eq!(unknown_keys(&document), vec!["boids.max_sped", "bubbles"]);
+    }
+
+    #[test]
+    fn file_is_rewritten_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tarts.toml");
+        std::fs::write(&path, "# mine\n[matrix]\ndrops_coeff = 2.0\n").unwrap();
+
+        let report = migrate_file(&path).unwrap();
+        assert_eq!(report.from, 0);
+        let backup = report.backup.unwrap();
+        assert_eq!(backup, dir.path().join("tarts.toml.bak"));
+        assert!(
+            std::fs::read_to_string(&backup)
+                .unwrap()
+                .starts_with("# mine")
+        );
+
+        let migrated = table(&std::fs::read_to_string(&path).unwrap());
+        assert_eq!(migrated["version"].as_integer(), Some(CURRENT_VERSION));
+        assert_eq!(migrated["matrix"]["drops_coeff"].as_float(), Some(2.0));
+
+        assert_eq!(migrate_file(&path).unwrap().backup, None);
+    }
+}
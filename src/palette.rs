@@ -0,0 +1,295 @@
+//! Named color palettes shared by effects.
+//!
+//! A palette is a list of evenly spaced color stops from the darkest to the
+//! brightest color. Effects map an intensity in `0.0..=1.0` onto it. Every
+//! effect section has a `palette` option: `"default"` keeps the effect's own
+//! colors, other names pick a built-in palette or a user palette defined in
+//! `tarts.toml` as `[palettes.<name>]` with a `colors` list.
+use crate::gradient::{ColorSpace, Easing, Gradient, Rgb};
+use crossterm::style;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+/// Value of the `palette` option which keeps effect colors
+pub const DEFAULT: &str = "default";
+
+/// Built-in palettes in the order `--list-palettes` shows them
+pub const BUILTIN: &[&str] = &[
+    "fire",
+    "plasma",
+    "ice",
+    "synthwave",
+    "viridis",
+    "grayscale",
+    "amber",
+    "green",
+    "matrix",
+];
+
+/// Fire palette of the original effect, 256 colors with 6-bit channels
+const FIRE: [u8; 768] = [
+    0, 0, 0, 0, 0, 6, 0, 0, 6, 0, 0, 7, 0, 0, 8, 0, 0, 8, 0, 0, 9, 0, 0, 10, 2, 0,
+    10, 4, 0, 9, 6, 0, 9, 8, 0, 8, 10, 0, 7, 12, 0, 7, 14, 0, 6, 16, 0, 5, 18, 0,
+    5, 20, 0, 4, 22, 0, 4, 24, 0, 3, 26, 0, 2, 28, 0, 2, 30, 0, 1, 32, 0, 0, 32, 0,
+    0, 33, 0, 0, 34, 0, 0, 35, 0, 0, 36, 0, 0, 36, 0, 0, 37, 0, 0, 38, 0, 0, 39, 0,
+    0, 40, 0, 0, 40, 0, 0, 41, 0, 0, 42, 0, 0, 43, 0, 0, 44, 0, 0, 45, 0, 0, 46, 1,
+    0, 47, 1, 0, 48, 2, 0, 49, 2, 0, 50, 3, 0, 51, 3, 0, 52, 4, 0, 53, 4, 0, 54, 5,
+    0, 55, 5, 0, 56, 6, 0, 57, 6, 0, 58, 7, 0, 59, 7, 0, 60, 8, 0, 61, 8, 0, 63, 9,
+    0, 63, 9, 0, 63, 10, 0, 63, 10, 0, 63, 11, 0, 63, 11, 0, 63, 12, 0, 63, 12, 0,
+    63, 13, 0, 63, 13, 0, 63, 14, 0, 63, 14, 0, 63, 15, 0, 63, 15, 0, 63, 16, 0,
+    63, 16, 0, 63, 17, 0, 63, 17, 0, 63, 18, 0, 63, 18, 0, 63, 19, 0, 63, 19, 0,
+    63, 20, 0, 63, 20, 0, 63, 21, 0, 63, 21, 0, 63, 22, 0, 63, 22, 0, 63, 23, 0,
+    63, 24, 0, 63, 24, 0, 63, 25, 0, 63, 25, 0, 63, 26, 0, 63, 26, 0, 63, 27, 0,
+    63, 27, 0, 63, 28, 0, 63, 28, 0, 63, 29, 0, 63, 29, 0, 63, 30, 0, 63, 30, 0,
+    63, 31, 0, 63, 31, 0, 63, 32, 0, 63, 32, 0, 63, 33, 0, 63, 33, 0, 63, 34, 0,
+    63, 34, 0, 63, 35, 0, 63, 35, 0, 63, 36, 0, 63, 36, 0, 63, 37, 0, 63, 38, 0,
+    63, 38, 0, 63, 39, 0, 63, 39, 0, 63, 40, 0, 63, 40, 0, 63, 41, 0, 63, 41, 0,
+    63, 42, 0, 63, 42, 0, 63, 43, 0, 63, 43, 0, 63, 44, 0, 63, 44, 0, 63, 45, 0,
+    63, 45, 0, 63, 46, 0, 63, 46, 0, 63, 47, 0, 63, 47, 0, 63, 48, 0, 63, 48, 0,
+    63, 49, 0, 63, 49, 0, 63, 50, 0, 63, 50, 0, 63, 51, 0, 63, 52, 0, 63, 52, 0,
+    63, 52, 0, 63, 52, 0, 63, 52, 0, 63, 53, 0, 63, 53, 0, 63, 53, 0, 63, 53, 0,
+    63, 54, 0, 63, 54, 0, 63, 54, 0, 63, 54, 0, 63, 54, 0, 63, 55, 0, 63, 55, 0,
+    63, 55, 0, 63, 55, 0, 63, 56, 0, 63, 56, 0, 63, 56, 0, 63, 56, 0, 63, 57, 0,
+    63, 57, 0, 63, 57, 0, 63, 57, 0, 63, 57, 0, 63, 58, 0, 63, 58, 0, 63, 58, 0,
+    63, 58, 0, 63, 59, 0, 63, 59, 0, 63, 59, 0, 63, 59, 0, 63, 60, 0, 63, 60, 0,
+    63, 60, 0, 63, 60, 0, 63, 60, 0, 63, 61, 0, 63, 61, 0, 63, 61, 0, 63, 61, 0,
+    63, 62, 0, 63, 62, 0, 63, 62, 0, 63, 62, 0, 63, 63, 0, 63, 63, 1, 63, 63, 2,
+    63, 63, 3, 63, 63, 4, 63, 63, 5, 63, 63, 6, 63, 63, 7, 63, 63, 8, 63, 63, 9,
+    63, 63, 10, 63, 63, 10, 63, 63, 11, 63, 63, 12, 63, 63, 13, 63, 63, 14, 63, 63,
+    15, 63, 63, 16, 63, 63, 17, 63, 63, 18, 63, 63, 19, 63, 63, 20, 63, 63, 21, 63,
+    63, 21, 63, 63, 22, 63, 63, 23, 63, 63, 24, 63, 63, 25, 63, 63, 26, 63, 63, 27,
+    63, 63, 28, 63, 63, 29, 63, 63, 30, 63, 63, 31, 63, 63, 31, 63, 63, 32, 63, 63,
+    33, 63, 63, 34, 63, 63, 35, 63, 63, 36, 63, 63, 37, 63, 63, 38, 63, 63, 39, 63,
+    63, 40, 63, 63, 41, 63, 63, 42, 63, 63, 42, 63, 63, 43, 63, 63, 44, 63, 63, 45,
+    63, 63, 46, 63, 63, 47, 63, 63, 48, 63, 63, 49, 63, 63, 50, 63, 63, 51, 63, 63,
+    52, 63, 63, 52, 63, 63, 53, 63, 63, 54, 63, 63, 55, 63, 63, 56, 63, 63, 57, 63,
+    63, 58, 63, 63, 59, 63, 63, 60, 63, 63, 61, 63, 63, 62, 63, 63, 63,
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    /// Stops from intensity 0 to 1, evenly spaced
+    pub colors: Vec<Rgb>,
+    #[serde(default)]
+    pub space: ColorSpace,
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<Rgb>) -> Self {
+        Self {
+            colors,
+            space: ColorSpace::default(),
+            easing: Easing::default(),
+        }
+    }
+
+    /// Built-in palette by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        let hex = |stops: &[&str]| {
+            Self::new(stops.iter().filter_map(|hex| Rgb::parse(hex)).collect())
+        };
+        let palette = match name {
+            "fire" => Self::new(
+                FIRE.chunks(3)
+                    .map(|c| {
+                        let scale = |v: u8| (v as f32 * 4.047619) as u8;
+                        Rgb::new(scale(c[0]), scale(c[1]), scale(c[2]))
+                    })
+                    .collect(),
+            ),
+            "plasma" => Self::new(
+                (0..256)
+                    .map(|i| {
+                        let wave = |period: f64| {
+                            (128.0 + 128.0 * (PI * i as f64 / period).sin()) as u8
+                        };
+                        Rgb::new(wave(32.0), wave(64.0), wave(128.0))
+                    })
+                    .collect(),
+            ),
+            "ice" => hex(&[
+                "#000814", "#001d3d", "#0a4d8c", "#4ea8de", "#a9def9", "#ffffff",
+            ]),
+            "synthwave" => hex(&[
+                "#1b0035", "#4a0e78", "#b5179e", "#f72585", "#ff6d00", "#ffd60a",
+            ]),
+            "viridis" => hex(&[
+                "#440154", "#482878", "#3e4989", "#31688e", "#26828e", "#1f9e89",
+                "#35b779", "#6ece58", "#b5de2b", "#fde725",
+            ]),
+            "grayscale" => hex(&["#000000", "#ffffff"]),
+            "amber" => hex(&["#000000", "#4d2e00", "#ffb000", "#ffd080"]),
+            "green" => hex(&["#000000", "#00ff00"]),
+            "matrix" => hex(&["#0a0a0a", "#00ff00", "#ffffff"]),
+            _ => return None,
+        };
+        Some(palette)
+    }
+
+    /// Color at intensity `t`, clamped to `0.0..=1.0`
+    pub fn sample(&self, t: f32) -> Rgb {
+        let Some(last) = self.colors.len().checked_sub(1) else {
+            return Rgb::new(255, 255, 255);
+        };
+        let position = self.easing.apply(t) * last as f32;
+        let index = (position as usize).min(last.saturating_sub(1));
+        match self.colors.get(index + 1) {
+            Some(&next) => {
+                self.space
+                    .mix(self.colors[index], next, position - index as f32)
+            }
+            None => self.colors[index],
+        }
+    }
+
+    pub fn color(&self, t: f32) -> style::Color {
+        self.sample(t).into()
+    }
+
+    /// Same colors as a general gradient
+    pub fn gradient(&self) -> Gradient {
+        Gradient::evenly(&self.colors)
+            .with_space(self.space)
+            .with_easing(self.easing)
+    }
+
+    /// `len` colors from the darkest to the brightest, for lookup tables
+    pub fn ramp(&self, len: usize) -> Vec<style::Color> {
+        self.gradient().ramp(len)
+    }
+}
+
+/// `palette` option of an effect section, written as the palette name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct PaletteChoice {
+    name: String,
+    palette: Option<Palette>,
+}
+
+impl PaletteChoice {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Colors to use, `None` keeps the effect's own colors
+    pub fn palette(&self) -> Option<&Palette> {
+        self.palette.as_ref()
+    }
+
+    /// Chosen palette or the built-in one the effect looks like by default
+    pub fn or_builtin(&self, name: &str) -> Palette {
+        self.palette
+            .clone()
+            .or_else(|| Palette::builtin(name))
+            .expect("built-in palette exists")
+    }
+
+    /// Name is `"default"` or refers to a known palette
+    pub fn is_resolved(&self) -> bool {
+        self.name == DEFAULT || self.palette.is_some()
+    }
+
+    /// Look the name up in user palettes first, then in built-in ones
+    pub fn resolve(&mut self, user: &BTreeMap<String, Palette>) {
+        self.palette = user
+            .get(&self.name)
+            .cloned()
+            .or_else(|| Palette::builtin(&self.name));
+    }
+}
+
+impl Default for PaletteChoice {
+    fn default() -> Self {
+        DEFAULT.into()
+    }
+}
+
+impl From<String> for PaletteChoice {
+    fn from(name: String) -> Self {
+        let mut choice = Self {
+            name,
+            palette: None,
+        };
+        choice.resolve(&BTreeMap::new());
+        choice
+    }
+}
+
+impl From<&str> for PaletteChoice {
+    fn from(name: &str) -> Self {
+        name.to_string().into()
+    }
+}
+
+impl From<PaletteChoice> for String {
+    fn from(choice: PaletteChoice) -> Self {
+        choice.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_interpolate_between_stops() {
+        let palette = Palette::builtin("matrix").unwrap();
+        assert_eq!(palette.sample(0.0), Rgb::new(10, 10, 10));
+        assert_eq!(palette.sample(0.5), Rgb::new(0, 255, 0));
+        assert_eq!(palette.sample(0.75), Rgb::new(128, 255, 128));
+        assert_eq!(palette.sample(7.0), Rgb::new(255, 255, 255));
+        assert_eq!(palette.ramp(3)[1], style::Color::Rgb { r: 0, g: 255, b: 0 });
+    }
+
+    #[test]
+    fn user_palettes_pick_space_and_easing() {
+        let palette: Palette = toml::from_str(
+            "colors = [\"#ff0000\", \"#0000ff\"]\n\
+             space = \"hsv\"\neasing = \"ease-in\"\n",
+        )
+        .unwrap();
+        // a quarter of the way from red to blue, the short way round
+        assert_eq!(palette.sample(0.5), Rgb::new(255, 0, 128));
+        assert_eq!(palette.ramp(3)[1], Rgb::new(255, 0, 128).into());
+
+        let plain: Palette =
+            toml::from_str("colors = [\"#000\", \"#fff\"]\n").unwrap();
+        assert_eq!(plain, Palette::builtin("grayscale").unwrap());
+    }
+
+    #[test]
+    fn builtin_palettes_keep_original_tables() {
+        for name in BUILTIN {
+            assert!(Palette::builtin(name).unwrap().colors.len() >= 2, "{name}");
+        }
+        // lookup tables of 256 colors hit the stops exactly
+        for name in ["fire", "plasma"] {
+            let palette = Palette::builtin(name).unwrap();
+            let ramp = palette.ramp(256);
+            for (stop, color) in palette.colors.iter().zip(ramp) {
+                assert_eq!(style::Color::from(*stop), color, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn choice_resolves_user_palettes_first() {
+        let mut user = BTreeMap::new();
+        user.insert(
+            "fire".to_string(),
+            Palette::new(vec![Rgb::new(0, 0, 0), Rgb::new(9, 9, 9)]),
+        );
+        let mut choice = PaletteChoice::from("fire");
+        assert_eq!(choice.palette().unwrap().colors.len(), 256);
+        choice.resolve(&user);
+        assert_eq!(choice.palette(), user.get("fire"));
+
+        assert!(PaletteChoice::default().is_resolved());
+        assert!(PaletteChoice::default().palette().is_none());
+        assert!(!PaletteChoice::from("neon").is_resolved());
+    }
+}
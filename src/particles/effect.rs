@@ -0,0 +1,606 @@
+//! Particles described entirely by the `[particles]` config section.
+//!
+//! Emitters spawn particles with a speed, direction and lifetime, forces
+//! pull them every step, screen edges wrap, bounce or end them, and glyph
+//! and color ramps draw them by age. Snow, sparks, bubbles and confetti are
+//! built-in presets of this one effect.
+use crate::buffer::{Buffer, Cell};
+use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::governor::scaled_count;
+use crate::gradient::Rgb;
+use crate::palette::{Palette, PaletteChoice};
+use crate::random;
+use crossterm::style;
+use derive_builder::Builder;
+use rand::{RngExt, rngs::SmallRng};
+use serde::{Deserialize, Serialize};
+
+/// One simulation step, the effect runs at `MAX_FPS`
+const DT: f32 = 1.0 / 60.0;
+/// Colors precomputed for each emitter
+const RAMP_LEN: usize = 32;
+
+/// How a glyph or color ramp is read: along the particle's life, or at one
+/// random point picked at birth
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ramp {
+    #[default]
+    Age,
+    Random,
+}
+
+/// What happens to particles crossing the screen edge on one axis
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    /// Come back from the opposite edge
+    Wrap,
+    /// Reflect, losing speed by `restitution`
+    Bounce,
+    /// Disappear
+    #[default]
+    Die,
+}
+
+/// Source of particles, `[[particles.emitters]]` tables. Missing fields
+/// take the defaults, which are falling snow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Emitter {
+    /// Top left corner of the spawn area, `[x, y]` fractions of the screen
+    pub position: (f32, f32),
+    /// Spawn area, fractions of the screen, zero is a line or a point
+    pub size: (f32, f32),
+    /// Particles per second
+    pub rate: f32,
+    /// Particles spawned at once every `burst_interval` seconds
+    pub burst: usize,
+    pub burst_interval: f32,
+    /// Cells per second, `[min, max]`
+    pub speed: (f32, f32),
+    /// Direction in degrees, `[min, max]`, 0 is right and 90 is down
+    pub angle: (f32, f32),
+    /// Seconds, `[min, max]`
+    pub lifetime: (f32, f32),
+    /// Glyphs from birth to death
+    pub glyphs: String,
+    pub glyph_ramp: Ramp,
+    /// Colors from birth to death, the section palette when empty
+    pub colors: Vec<Rgb>,
+    pub color_ramp: Ramp,
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Self {
+            position: (0.0, 0.0),
+            size: (1.0, 0.0),
+            rate: 12.0,
+            burst: 0,
+            burst_interval: 1.0,
+            speed: (1.0, 3.0),
+            angle: (70.0, 110.0),
+            lifetime: (20.0, 30.0),
+            glyphs: "*·•+".to_string(),
+            glyph_ramp: Ramp::Random,
+            colors: vec![Rgb::new(255, 255, 255), Rgb::new(150, 190, 255)],
+            color_ramp: Ramp::Random,
+        }
+    }
+}
+
+impl Emitter {
+    fn validate(&self, check: &mut Validator) {
+        for (field, value) in [
+            ("position[0]", self.position.0),
+            ("position[1]", self.position.1),
+            ("size[0]", self.size.0),
+            ("size[1]", self.size.1),
+        ] {
+            check.in_range(field, value, 0.0..=1.0);
+        }
+        check.non_negative("rate", self.rate);
+        check.positive("burst_interval", self.burst_interval);
+        check.non_negative("speed[0]", self.speed.0);
+        check.not_greater("speed[0]", self.speed.0, "speed[1]", self.speed.1);
+        check.finite("angle[0]", self.angle.0 as f64);
+        check.not_greater("angle[0]", self.angle.0, "angle[1]", self.angle.1);
+        check.positive("lifetime[0]", self.lifetime.0);
+        check.not_greater(
+            "lifetime[0]",
+            self.lifetime.0,
+            "lifetime[1]",
+            self.lifetime.1,
+        );
+        check.not_empty("glyphs", self.glyphs.as_bytes());
+    }
+}
+
+/// Accelerations of every particle, cells per second squared
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Forces {
+    /// Constant pull `[x, y]`, sideways is wind, negative y lifts
+    pub gravity: (f32, f32),
+    /// Fraction of the velocity lost per second
+    pub drag: f32,
+    /// Random push in any direction
+    pub turbulence: f32,
+}
+
+impl Default for Forces {
+    fn default() -> Self {
+        Self {
+            gravity: (0.0, 1.0),
+            drag: 0.3,
+            turbulence: 2.0,
+        }
+    }
+}
+
+/// Screen edges, left and right are `horizontal`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Edges {
+    pub horizontal: Edge,
+    pub vertical: Edge,
+    /// Part of the speed kept after a bounce
+    pub restitution: f32,
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Self {
+            horizontal: Edge::Wrap,
+            vertical: Edge::Die,
+            restitution: 0.5,
+        }
+    }
+}
+
+/// `[particles]` section of `tarts.toml`
+#[derive(Builder, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[builder(public, setter(into))]
+pub struct ParticlesOptions {
+    /// Live particles at most, the quality governor lowers it under load
+    #[builder(default = "1500")]
+    pub max_particles: usize,
+    #[builder(default = "vec![Emitter::default()]")]
+    pub emitters: Vec<Emitter>,
+    #[builder(default)]
+    #[serde(default)]
+    pub forces: Forces,
+    #[builder(default)]
+    #[serde(default)]
+    pub edges: Edges,
+    /// Colors of emitters without their own, brightest at birth
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+impl ParticlesOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.at_least("max_particles", self.max_particles, 1);
+        check.not_empty("emitters", &self.emitters);
+        for (index, emitter) in self.emitters.iter().enumerate() {
+            emitter.validate(&mut check.nested(&format!("emitters[{}]", index)));
+        }
+        let mut forces = check.nested("forces");
+        forces.finite("gravity[0]", self.forces.gravity.0 as f64);
+        forces.finite("gravity[1]", self.forces.gravity.1 as f64);
+        forces.non_negative("drag", self.forces.drag);
+        forces.non_negative("turbulence", self.forces.turbulence);
+        check.nested("edges").in_range(
+            "restitution",
+            self.edges.restitution,
+            0.0..=1.0,
+        );
+        check.palette("palette", &self.palette);
+    }
+}
+
+struct Particle {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    /// Seconds since birth
+    age: f32,
+    lifetime: f32,
+    emitter: usize,
+    /// Glyph and color ramp positions picked at birth, for `Ramp::Random`
+    pick: (f32, f32),
+}
+
+/// Spawn bookkeeping of one emitter
+#[derive(Debug, Clone, Default)]
+struct Clock {
+    /// Fraction of a particle carried over to the next step
+    owed: f32,
+    /// Seconds to the next burst
+    burst_in: f32,
+}
+
+/// Glyphs and colors of one emitter's particles
+struct Look {
+    glyphs: Vec<char>,
+    glyph_ramp: Ramp,
+    colors: Vec<style::Color>,
+    color_ramp: Ramp,
+}
+
+impl Look {
+    fn new(emitter: &Emitter, palette: &PaletteChoice) -> Self {
+        let colors = if !emitter.colors.is_empty() {
+            Palette::new(emitter.colors.clone()).ramp(RAMP_LEN)
+        } else if let Some(palette) = palette.palette() {
+            palette.ramp(RAMP_LEN).into_iter().rev().collect()
+        } else {
+            vec![style::Color::White]
+        };
+        Self {
+            glyphs: emitter.glyphs.chars().collect(),
+            glyph_ramp: emitter.glyph_ramp,
+            colors,
+            color_ramp: emitter.color_ramp,
+        }
+    }
+
+    fn cell(&self, particle: &Particle) -> Cell {
+        let life = particle.age / particle.lifetime;
+        let glyph = match self.glyph_ramp {
+            Ramp::Age => life,
+            Ramp::Random => particle.pick.0,
+        };
+        let color = match self.color_ramp {
+            Ramp::Age => life,
+            Ramp::Random => particle.pick.1,
+        };
+        Cell::new(
+            at(&self.glyphs, glyph).unwrap_or('*'),
+            at(&self.colors, color).unwrap_or(style::Color::White),
+            style::Attribute::Bold,
+        )
+    }
+}
+
+/// Item of a ramp at `t` in `0.0..1.0`
+fn at<T: Copy>(items: &[T], t: f32) -> Option<T> {
+    let last = items.len().checked_sub(1)?;
+    Some(items[((t * items.len() as f32) as usize).min(last)])
+}
+
+/// Keep one coordinate inside `0..size` by the edge rule, `false` when the
+/// particle is gone
+fn cross_edge(
+    position: &mut f32,
+    velocity: &mut f32,
+    size: f32,
+    edge: Edge,
+    restitution: f32,
+) -> bool {
+    if (0.0..size).contains(position) {
+        return true;
+    }
+    match edge {
+        Edge::Die => false,
+        Edge::Wrap => {
+            let wrapped = position.rem_euclid(size);
+            *position = if wrapped < size { wrapped } else { 0.0 };
+            true
+        }
+        Edge::Bounce => {
+            let reflected = if *position < 0.0 {
+                -*position
+            } else {
+                2.0 * size - *position - 0.01
+            };
+            *position = reflected.clamp(0.0, size - 0.01);
+            *velocity = -*velocity * restitution;
+            true
+        }
+    }
+}
+
+/// New particle somewhere in the emitter's area
+fn emit(
+    emitter: &Emitter,
+    index: usize,
+    screen_size: (u16, u16),
+    rng: &mut SmallRng,
+) -> Particle {
+    let width = (screen_size.0 as f32 - 1.0).max(0.0);
+    let height = (screen_size.1 as f32 - 1.0).max(0.0);
+    let x = emitter.position.0 + emitter.size.0 * rng.random_range(0.0..=1.0);
+    let y = emitter.position.1 + emitter.size.1 * rng.random_range(0.0..=1.0);
+    let angle = rng
+        .random_range(emitter.angle.0..=emitter.angle.1)
+        .to_radians();
+    let speed = rng.random_range(emitter.speed.0..=emitter.speed.1);
+    Particle {
+        position: ((x * width).min(width), (y * height).min(height)),
+        velocity: (angle.cos() * speed, angle.sin() * speed),
+        age: 0.0,
+        lifetime: rng.random_range(emitter.lifetime.0..=emitter.lifetime.1),
+        emitter: index,
+        pick: (rng.random_range(0.0..1.0), rng.random_range(0.0..1.0)),
+    }
+}
+
+pub struct Particles {
+    pub screen_size: (u16, u16),
+    options: ParticlesOptions,
+    buffer: Buffer,
+    particles: Vec<Particle>,
+    clocks: Vec<Clock>,
+    looks: Vec<Look>,
+    rng: SmallRng,
+    quality: f32,
+}
+
+impl TerminalEffect for Particles {
+    fn get_diff(&mut self) -> Vec<(usize, usize, Cell)> {
+        let mut curr_buffer =
+            Buffer::new(self.screen_size.0 as usize, self.screen_size.1 as usize);
+
+        // later particles are drawn over earlier ones
+        for particle in &self.particles {
+            let x = particle.position.0 as usize;
+            let y = particle.position.1 as usize;
+            if x < curr_buffer.width && y < curr_buffer.height {
+                curr_buffer.set(x, y, self.looks[particle.emitter].cell(particle));
+            }
+        }
+
+        let diff = self.buffer.diff(&curr_buffer);
+        self.buffer = curr_buffer;
+        diff
+    }
+
+    fn update(&mut self) {
+        self.spawn();
+
+        let width = self.screen_size.0 as f32;
+        let height = self.screen_size.1 as f32;
+        let forces = &self.options.forces;
+        let edges = &self.options.edges;
+        let damping = (1.0 - forces.drag * DT).max(0.0);
+        let rng = &mut self.rng;
+        self.particles.retain_mut(|particle| {
+            particle.age += DT;
+            if particle.age >= particle.lifetime {
+                return false;
+            }
+
+            let mut push = forces.gravity;
+            if forces.turbulence > 0.0 {
+                push.0 += forces.turbulence * rng.random_range(-1.0..=1.0);
+                push.1 += forces.turbulence * rng.random_range(-1.0..=1.0);
+            }
+            particle.velocity.0 = (particle.velocity.0 + push.0 * DT) * damping;
+            particle.velocity.1 = (particle.velocity.1 + push.1 * DT) * damping;
+            particle.position.0 += particle.velocity.0 * DT;
+            particle.position.1 += particle.velocity.1 * DT;
+
+            cross_edge(
+                &mut particle.position.0,
+                &mut particle.velocity.0,
+                width,
+                edges.horizontal,
+                edges.restitution,
+            ) && cross_edge(
+                &mut particle.position.1,
+                &mut particle.velocity.1,
+                height,
+                edges.vertical,
+                edges.restitution,
+            )
+        });
+    }
+
+    fn update_size(&mut self, width: u16, height: u16) {
+        // particles keep flying, positions are stretched to the new screen
+        let scale_x = width as f32 / self.screen_size.0.max(1) as f32;
+        let scale_y = height as f32 / self.screen_size.1.max(1) as f32;
+        for particle in &mut self.particles {
+            particle.position.0 = (particle.position.0 * scale_x)
+                .clamp(0.0, (width as f32 - 0.01).max(0.0));
+            particle.position.1 = (particle.position.1 * scale_y)
+                .clamp(0.0, (height as f32 - 0.01).max(0.0));
+        }
+        self.screen_size = (width, height);
+        self.buffer = Buffer::new(width as usize, height as usize);
+    }
+
+    fn reset(&mut self) {
+        let quality = self.quality;
+        *self = Self::new(self.options.clone(), self.screen_size);
+        self.set_quality(quality);
+    }
+
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        vec![("particles", self.particles.len())]
+    }
+
+    fn set_quality(&mut self, quality: f32) {
+        // the oldest particles stay, emitters respect the lower cap
+        self.quality = quality;
+        let cap = scaled_count(self.options.max_particles, quality);
+        self.particles.truncate(cap);
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        // particles in flight keep going unless their emitter is gone
+        self.options = config.get_particles_options();
+        self.looks = Self::looks(&self.options);
+        self.clocks
+            .resize(self.options.emitters.len(), Clock::default());
+        let emitters = self.options.emitters.len();
+        self.particles
+            .retain(|particle| particle.emitter < emitters);
+        self.set_quality(self.quality);
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
+}
+
+impl Particles {
+    pub fn new(options: ParticlesOptions, screen_size: (u16, u16)) -> Self {
+        Self {
+            screen_size,
+            buffer: Buffer::new(screen_size.0 as usize, screen_size.1 as usize),
+            particles: Vec::new(),
+            clocks: vec![Clock::default(); options.emitters.len()],
+            looks: Self::looks(&options),
+            options,
+            rng: random::rng(),
+            quality: 1.0,
+        }
+    }
+
+    fn looks(options: &ParticlesOptions) -> Vec<Look> {
+        options
+            .emitters
+            .iter()
+            .map(|emitter| Look::new(emitter, &options.palette))
+            .collect()
+    }
+
+    /// Particles owed by emitter rates and bursts, up to the cap
+    fn spawn(&mut self) {
+        let cap = scaled_count(self.options.max_particles, self.quality);
+        for (index, emitter) in self.options.emitters.iter().enumerate() {
+            let clock = &mut self.clocks[index];
+            clock.owed += emitter.rate * DT;
+            let mut count = clock.owed as usize;
+            clock.owed -= count as f32;
+            if emitter.burst > 0 {
+                clock.burst_in -= DT;
+                if clock.burst_in <= 0.0 {
+                    count += emitter.burst;
+                    clock.burst_in += emitter.burst_interval;
+                }
+            }
+
+            let count = count.min(cap.saturating_sub(self.particles.len()));
+            for _ in 0..count {
+                let particle =
+                    emit(emitter, index, self.screen_size, &mut self.rng);
+                self.particles.push(particle);
+            }
+        }
+    }
+}
+
+impl DefaultOptions for Particles {
+    type Options = ParticlesOptions;
+
+    fn default_options(_width: u16, _height: u16) -> Self::Options {
+        ParticlesOptionsBuilder::default().build().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particles_stay_on_screen() {
+        let options = ParticlesOptionsBuilder::default().build().unwrap();
+        let mut effect = Particles::new(options, (40, 20));
+        for _ in 0..600 {
+            effect.get_diff();
+            effect.update();
+        }
+        assert!(!effect.particles.is_empty());
+        assert!(effect.particles.iter().all(|particle| {
+            (0.0..40.0).contains(&particle.position.0)
+                && (0.0..20.0).contains(&particle.position.1)
+        }));
+        let drawn = effect.get_diff();
+        assert!(drawn.iter().all(|(x, y, _)| *x < 40 && *y < 20));
+    }
+
+    #[test]
+    fn bursts_respect_particle_cap() {
+        let emitter = Emitter {
+            rate: 0.0,
+            burst: 50,
+            burst_interval: 0.5,
+            ..Emitter::default()
+        };
+        let options = ParticlesOptionsBuilder::default()
+            .max_particles(20_usize)
+            .emitters(vec![emitter])
+            .build()
+            .unwrap();
+        let mut effect = Particles::new(options, (40, 20));
+        effect.update();
+        assert_eq!(effect.particles.len(), 20);
+
+        effect.set_quality(0.5);
+        assert_eq!(effect.particles.len(), 10);
+    }
+
+    #[test]
+    fn edges_wrap_bounce_and_end_particles() {
+        let (mut position, mut velocity) = (10.5, 2.0);
+        assert!(cross_edge(
+            &mut position,
+            &mut velocity,
+            10.0,
+            Edge::Wrap,
+            0.5
+        ));
+        assert_eq!((position, velocity), (0.5, 2.0));
+
+        let (mut position, mut velocity) = (-1.0, -2.0);
+        assert!(cross_edge(
+            &mut position,
+            &mut velocity,
+            10.0,
+            Edge::Bounce,
+            0.5
+        ));
+        assert_eq!((position, velocity), (1.0, 1.0));
+
+        let (mut position, mut velocity) = (10.0, 1.0);
+        assert!(!cross_edge(
+            &mut position,
+            &mut velocity,
+            10.0,
+            Edge::Die,
+            0.5
+        ));
+    }
+
+    #[test]
+    fn ramps_follow_age() {
+        let emitter = Emitter {
+            glyphs: "ab".to_string(),
+            glyph_ramp: Ramp::Age,
+            colors: vec![Rgb::new(255, 0, 0), Rgb::new(0, 0, 255)],
+            color_ramp: Ramp::Age,
+            ..Emitter::default()
+        };
+        let look = Look::new(&emitter, &PaletteChoice::default());
+        let mut particle = Particle {
+            position: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+            age: 0.0,
+            lifetime: 2.0,
+            emitter: 0,
+            pick: (0.0, 0.0),
+        };
+        let young = look.cell(&particle);
+        assert_eq!(young.symbol, 'a');
+        assert_eq!(young.color, style::Color::Rgb { r: 255, g: 0, b: 0 });
+
+        particle.age = 1.99;
+        let old = look.cell(&particle);
+        assert_eq!(old.symbol, 'b');
+        assert_eq!(old.color, style::Color::Rgb { r: 0, g: 0, b: 255 });
+    }
+}
@@ -0,0 +1,6 @@
+pub mod effect;
+#[allow(unused)]
+pub use effect::{
+    Edge, Edges, Emitter, Forces, Particles, ParticlesOptions,
+    ParticlesOptionsBuilder, Ramp,
+};
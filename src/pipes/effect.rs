@@ -1,8 +1,12 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::PaletteChoice;
+use crate::random;
+use crate::snapshot::SavedColor;
 use crossterm::style;
 use derive_builder::Builder;
-use rand::RngExt;
+use rand::{RngExt, rngs::SmallRng};
 use serde::{Deserialize, Serialize};
 use std::sync::LazyLock;
 
@@ -38,6 +42,40 @@ pub struct PipesOptions {
     pub pipe_type_change: f64,
     #[builder(default = "0.9")]
     pub cleanup_factor: f64,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+impl PipesOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.in_range("turn_probability", self.turn_probability, 0.0..=1.0);
+        check.in_range("line_type", self.line_type, 0..=LINE_CHARS.len() - 1);
+        check.at_least("num_lines", self.num_lines, 1);
+        check.in_range("pipe_type_change", self.pipe_type_change, 0.0..=1.0);
+        check.palette("palette", &self.palette);
+        check.in_range("cleanup_factor", self.cleanup_factor, 0.0..=1.0);
+    }
+}
+
+/// `snapshot` of the canvas, pipes go on from their last segment
+#[derive(Serialize, Deserialize)]
+struct PipesState {
+    started: bool,
+    pipes: Vec<PipeState>,
+    /// Drawn cells as `[x, y, symbol, color]`
+    cells: Vec<(usize, usize, char, SavedColor)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PipeState {
+    line_type: usize,
+    prev_location: (usize, usize),
+    prev_node_type: usize,
+    /// Signed, pipes heading out of the screen point past its edge
+    next_location: (i64, i64),
+    color: SavedColor,
 }
 
 pub struct Pipe {
@@ -48,7 +86,7 @@ pub struct Pipe {
     next_location: (usize, usize),
     curr_color: style::Color,
     pub colors: Vec<style::Color>,
-    pub rng: rand::prelude::ThreadRng,
+    pub rng: SmallRng,
 }
 
 pub struct Pipes {
@@ -56,6 +94,7 @@ pub struct Pipes {
     options: PipesOptions,
     buffer: Buffer,
     pipes_made: bool,
+    redraw: bool,
     pipes: Vec<Pipe>,
 }
 
@@ -96,7 +135,13 @@ impl TerminalEffect for Pipes {
             return diff;
         }
 
-        let diff = self.buffer.diff(&curr_buffer);
+        // after resize screen is blank, whole canvas has to be drawn again
+        let diff = if self.redraw {
+            self.redraw = false;
+            Buffer::new(curr_buffer.width, curr_buffer.height).diff(&curr_buffer)
+        } else {
+            self.buffer.diff(&curr_buffer)
+        };
         self.buffer = curr_buffer;
 
         diff
@@ -107,8 +152,12 @@ impl TerminalEffect for Pipes {
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // keep drawn pipes, pipes heading out of new screen start over
+        let mut buffer = Buffer::new(width as usize, height as usize);
+        buffer.blit(&self.buffer, 0, 0);
+        self.buffer = buffer;
         self.screen_size = (width, height);
-        self.reset();
+        self.redraw = true;
     }
 
     fn reset(&mut self) {
@@ -116,6 +165,126 @@ impl TerminalEffect for Pipes {
             Buffer::new(self.screen_size.0 as usize, self.screen_size.1 as usize);
         self.pipes_made = false;
     }
+
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        vec![("pipes", self.pipes.len())]
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (2, 2)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        let options = config.get_pipes_options();
+        if options.num_lines != self.options.num_lines {
+            *self = Self::new(options, self.screen_size);
+            return;
+        }
+        let colors = Self::pipe_colors(&options.palette);
+        for pipe in &mut self.pipes {
+            pipe.line_type = options.line_type;
+            pipe.turn_probability = options.turn_probability;
+            pipe.colors = colors.clone();
+        }
+        self.options = options;
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
+
+    fn snapshot(&self) -> Option<toml::Value> {
+        let pipes = self
+            .pipes
+            .iter()
+            .map(|pipe| PipeState {
+                line_type: pipe.line_type,
+                prev_location: pipe.prev_location,
+                prev_node_type: pipe.prev_node_type,
+                next_location: (
+                    pipe.next_location.0 as i64,
+                    pipe.next_location.1 as i64,
+                ),
+                color: SavedColor(pipe.curr_color),
+            })
+            .collect();
+        let cells = self
+            .buffer
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.symbol != ' ')
+            .map(|(index, cell)| {
+                let (x, y) = self.buffer.pos_of(index);
+                (x, y, cell.symbol, SavedColor(cell.color))
+            })
+            .collect();
+        let state = PipesState {
+            started: self.pipes_made,
+            pipes,
+            cells,
+        };
+        toml::Value::try_from(state).ok()
+    }
+
+    fn restore(
+        &mut self,
+        state: toml::Value,
+        screen_size: (u16, u16),
+    ) -> Result<(), String> {
+        let state: PipesState = state.try_into().map_err(|e| e.to_string())?;
+        // node 0 is a pipe which has not started yet
+        let first_node = usize::from(state.started);
+        for pipe in &state.pipes {
+            if pipe.line_type >= LINE_CHARS.len() {
+                return Err(format!(
+                    "line_type must be 0..={}, got {}",
+                    LINE_CHARS.len() - 1,
+                    pipe.line_type
+                ));
+            }
+            if !(first_node..LINE_CHARS[0].len()).contains(&pipe.prev_node_type) {
+                return Err(format!(
+                    "prev_node_type must be {}..={}, got {}",
+                    first_node,
+                    LINE_CHARS[0].len() - 1,
+                    pipe.prev_node_type
+                ));
+            }
+        }
+        let mut buffer =
+            Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
+        for (x, y, symbol, color) in state.cells {
+            if x < buffer.width && y < buffer.height {
+                buffer.set(
+                    x,
+                    y,
+                    Cell::new(symbol, color.0, style::Attribute::Bold),
+                );
+            }
+        }
+        let colors = Self::pipe_colors(&self.options.palette);
+        self.pipes = state
+            .pipes
+            .into_iter()
+            .map(|pipe| Pipe {
+                line_type: pipe.line_type,
+                turn_probability: self.options.turn_probability,
+                prev_location: pipe.prev_location,
+                prev_node_type: pipe.prev_node_type,
+                next_location: (
+                    pipe.next_location.0 as usize,
+                    pipe.next_location.1 as usize,
+                ),
+                curr_color: pipe.color.0,
+                colors: colors.clone(),
+                rng: random::rng(),
+            })
+            .collect();
+        self.pipes_made = state.started;
+        self.buffer = buffer;
+        self.screen_size = screen_size;
+        // resumed canvas is drawn at once
+        self.redraw = true;
+        Ok(())
+    }
 }
 
 impl Pipe {
@@ -346,16 +515,26 @@ impl Pipe {
 }
 
 impl Pipes {
+    /// Six colors new pipes pick from, palettes skip their darkest part
+    fn pipe_colors(palette: &PaletteChoice) -> Vec<style::Color> {
+        match palette.palette() {
+            Some(palette) => (0..6)
+                .map(|i| palette.color(0.3 + 0.7 * i as f32 / 5.0))
+                .collect(),
+            None => vec![
+                style::Color::Red,
+                style::Color::Green,
+                style::Color::Blue,
+                style::Color::Yellow,
+                style::Color::Cyan,
+                style::Color::Magenta,
+            ],
+        }
+    }
+
     pub fn new(options: PipesOptions, screen_size: (u16, u16)) -> Self {
         let buffer = Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
-        let colors = vec![
-            style::Color::Red,
-            style::Color::Green,
-            style::Color::Blue,
-            style::Color::Yellow,
-            style::Color::Cyan,
-            style::Color::Magenta,
-        ];
+        let colors = Self::pipe_colors(&options.palette);
 
         let mut pipes = Vec::with_capacity(options.num_lines);
         for _ in 0..options.num_lines {
@@ -367,7 +546,7 @@ impl Pipes {
                 next_location: (0, 0),
                 curr_color: style::Color::White,
                 colors: colors.clone(),
-                rng: rand::rng(),
+                rng: random::rng(),
             });
         }
 
@@ -376,6 +555,7 @@ impl Pipes {
             options,
             buffer,
             pipes_made: false,
+            redraw: false,
             pipes,
         }
     }
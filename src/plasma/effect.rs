@@ -1,9 +1,13 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::PaletteChoice;
 use crossterm::style;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
-use std::f64::consts::PI;
+
+/// Smooth enough for slow color waves and twice cheaper than 60 fps
+const FRAME_RATE: f64 = 30.0;
 
 #[derive(Builder, Default, Debug, Clone, Serialize, Deserialize)]
 #[builder(public, setter(into))]
@@ -14,6 +18,19 @@ pub struct PlasmaOptions {
     pub spatial_scale: f64,
     #[builder(default = "150.0")]
     pub color_speed: f64,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+impl PlasmaOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.finite("time_scale", self.time_scale);
+        check.positive("spatial_scale", self.spatial_scale);
+        check.finite("color_speed", self.color_speed);
+        check.palette("palette", &self.palette);
+    }
 }
 
 pub struct Plasma {
@@ -22,6 +39,10 @@ pub struct Plasma {
     buffer: Buffer,
     time: f64,
     palette: Vec<style::Color>,
+    /// Interlacing on lower quality: every frame only each `step`-th row
+    /// starting from `phase` is redrawn, the rest keeps previous colors
+    step: usize,
+    phase: usize,
 }
 
 impl TerminalEffect for Plasma {
@@ -39,13 +60,19 @@ impl TerminalEffect for Plasma {
     }
 
     fn update(&mut self) {
-        // Advance the time for the animation
-        self.time += self.options.time_scale * 0.1;
+        // Advance the time for the animation, step is doubled since
+        // plasma runs at half of the usual 60 fps
+        self.time += self.options.time_scale * 0.2;
+    }
+
+    fn frame_rate(&self) -> f64 {
+        FRAME_RATE
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // plasma is a function of time and position, only buffer changes
         self.screen_size = (width, height);
-        self.reset();
+        self.buffer = Buffer::new(width as usize, height as usize);
     }
 
     fn reset(&mut self) {
@@ -53,6 +80,17 @@ impl TerminalEffect for Plasma {
             Buffer::new(self.screen_size.0 as usize, self.screen_size.1 as usize);
         self.time = 0.0;
     }
+
+    fn set_quality(&mut self, quality: f32) {
+        self.step = ((1.0 / quality.max(0.25)).round() as usize).clamp(1, 4);
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        self.options = config.get_plasma_options();
+        self.palette = Self::generate_palette(&self.options.palette);
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
 }
 
 impl Plasma {
@@ -61,7 +99,7 @@ impl Plasma {
         let time = 0.0;
 
         // Generate color palette
-        let palette = Self::generate_palette();
+        let palette = Self::generate_palette(&options.palette);
 
         Self {
             screen_size,
@@ -69,38 +107,20 @@ impl Plasma {
             buffer,
             time,
             palette,
+            step: 1,
+            phase: 0,
         }
     }
 
-    /// Generate a color palette of 256 colors
-    fn generate_palette() -> Vec<style::Color> {
-        let mut palette = Vec::with_capacity(256);
-
-        for i in 0..256 {
-            let i_f64 = i as f64;
-            let r = 128.0 + 128.0 * (PI * i_f64 / 32.0).sin();
-            let g = 128.0 + 128.0 * (PI * i_f64 / 64.0).sin();
-            let b = 128.0 + 128.0 * (PI * i_f64 / 128.0).sin();
-
-            palette.push(style::Color::Rgb {
-                r: Self::clamp(r as u8, 0, 255),
-                g: Self::clamp(g as u8, 0, 255),
-                b: Self::clamp(b as u8, 0, 255),
-            });
-        }
-
-        palette
-    }
-
-    /// Clamp a value between min and max
-    fn clamp(val: u8, min: u8, max: u8) -> u8 {
-        if val < min {
-            min
-        } else if val > max {
-            max
-        } else {
-            val
+    /// 256 colors the plasma cycles through. Palettes other than the
+    /// default one are mirrored, so the cycle has no seam
+    fn generate_palette(choice: &PaletteChoice) -> Vec<style::Color> {
+        let palette = choice.or_builtin("plasma");
+        if choice.palette().is_none() {
+            return palette.ramp(256);
         }
+        let half = palette.ramp(128);
+        half.iter().chain(half.iter().rev()).copied().collect()
     }
 
     /// Calculate plasma value using the [AWK script formula](https://rosettacode.org/wiki/Plasma_effect#AWK)
@@ -134,7 +154,8 @@ impl Plasma {
         let h = height as f64;
         let now = self.time;
 
-        for y in 0..height {
+        self.phase = (self.phase + 1) % self.step;
+        for y in (self.phase.min(height)..height).step_by(self.step) {
             for x in 0..width {
                 // For each cell, calculate two plasma values (upper and lower half)
                 let y_f64 = (y * 2) as f64;
@@ -0,0 +1,254 @@
+//! Named presets: partial effect sections merged over the base section.
+//!
+//! Built-in presets ship with the binary, user presets live in `tarts.toml`
+//! under `[<effect>.presets.<name>]` and replace built-in ones of the same
+//! name. A preset only lists the fields it changes. Presets are selected
+//! with `--preset`, tarts has no playlists which could name them.
+use crate::config::ConfigOverride;
+use std::collections::BTreeMap;
+
+const BUILTIN: &str = r##"
+[matrix.storm]
+drops_coeff = 3.0
+speed_coeff = 1.5
+
+[matrix.drizzle]
+drops_coeff = 0.3
+speed_coeff = 0.7
+
+[life.sparse]
+cells_coeff = 0.5
+
+[boids.arrows]
+charset = "Arrow"
+
+[boids.swarm]
+boid_coeff = 2.0
+max_speed = 3.0
+
+[pipes.bold]
+line_type = 1
+
+[pipes.blocks]
+line_type = 4
+num_lines = 3
+
+[plasma.slow]
+time_scale = 0.4
+
+[fire.mono]
+use_colors = false
+
+[cube.ascii]
+use_braille = false
+
+[constellation.dense]
+star_count = 150
+connect_radius = 0.12
+
+[constellation.calm]
+min_speed = 0.1
+max_speed = 0.5
+twinkle = false
+
+[[particles.sparks.emitters]]
+position = [0.5, 1.0]
+size = [0.0, 0.0]
+rate = 0.0
+burst = 60
+burst_interval = 1.2
+speed = [8.0, 30.0]
+angle = [210.0, 330.0]
+lifetime = [0.8, 2.0]
+glyphs = "*+·"
+glyph_ramp = "age"
+colors = ["#ffffff", "#ffd040", "#ff6000", "#401000"]
+color_ramp = "age"
+
+[particles.sparks.forces]
+gravity = [0.0, 25.0]
+drag = 0.5
+turbulence = 0.0
+
+[particles.sparks.edges]
+horizontal = "bounce"
+vertical = "bounce"
+restitution = 0.4
+
+[[particles.bubbles.emitters]]
+position = [0.0, 1.0]
+size = [1.0, 0.0]
+rate = 6.0
+speed = [1.0, 3.0]
+angle = [250.0, 290.0]
+lifetime = [10.0, 20.0]
+glyphs = "·∘o○◯"
+glyph_ramp = "age"
+colors = ["#4080ff", "#c0f0ff"]
+color_ramp = "age"
+
+[particles.bubbles.forces]
+gravity = [0.0, -1.5]
+drag = 0.4
+turbulence = 3.0
+
+[particles.bubbles.edges]
+horizontal = "bounce"
+vertical = "die"
+
+[[particles.confetti.emitters]]
+position = [0.0, 0.0]
+size = [1.0, 0.0]
+rate = 40.0
+speed = [0.0, 2.0]
+angle = [60.0, 120.0]
+lifetime = [6.0, 10.0]
+glyphs = "▪▫◆◇●■"
+glyph_ramp = "random"
+colors = ["#ff4060", "#ffd040", "#40d080", "#40a0ff", "#c060ff"]
+color_ramp = "random"
+
+[particles.confetti.forces]
+gravity = [0.0, 2.0]
+drag = 0.8
+turbulence = 6.0
+"##;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    pub values: toml::Table,
+    pub builtin: bool,
+}
+
+impl Preset {
+    /// Preset fields as overrides of the effect section
+    pub fn overrides(&self, effect: &str) -> Vec<ConfigOverride> {
+        let mut overrides = Vec::new();
+        flatten(effect, &self.values, &mut overrides);
+        overrides
+    }
+
+    /// One line summary, `drops_coeff=3.0, speed_coeff=1.5`
+    pub fn summary(&self) -> String {
+        let mut overrides = Vec::new();
+        flatten("", &self.values, &mut overrides);
+        overrides
+            .iter()
+            .map(|item| item.to_string().trim_start_matches('.').to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn flatten(prefix: &str, table: &toml::Table, out: &mut Vec<ConfigOverride>) {
+    for (key, value) in table {
+        let path = format!("{}.{}", prefix, key);
+        match value {
+            toml::Value::Table(table) => flatten(&path, table, out),
+            value => out.push(ConfigOverride {
+                path,
+                value: value.clone(),
+            }),
+        }
+    }
+}
+
+/// Presets of every effect by name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Presets {
+    effects: BTreeMap<String, BTreeMap<String, Preset>>,
+}
+
+impl Default for Presets {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Presets {
+    pub fn builtin() -> Self {
+        let table: toml::Table =
+            toml::from_str(BUILTIN).expect("built-in presets are valid TOML");
+        let mut presets = Self {
+            effects: BTreeMap::new(),
+        };
+        for (effect, named) in table {
+            let named = named
+                .try_into::<BTreeMap<String, toml::Table>>()
+                .expect("built-in presets are tables");
+            presets.insert_all(&effect, named, true);
+        }
+        presets
+    }
+
+    /// Add presets of one effect, later ones replace earlier with same name
+    pub fn insert_all(
+        &mut self,
+        effect: &str,
+        named: impl IntoIterator<Item = (String, toml::Table)>,
+        builtin: bool,
+    ) {
+        let effect_presets = self.effects.entry(effect.to_string()).or_default();
+        for (name, values) in named {
+            effect_presets.insert(name, Preset { values, builtin });
+        }
+    }
+
+    pub fn get(&self, effect: &str, name: &str) -> Option<&Preset> {
+        self.effects.get(effect)?.get(name)
+    }
+
+    /// Presets of the effect sorted by name
+    pub fn of(&self, effect: &str) -> Vec<(&str, &Preset)> {
+        self.effects
+            .get(effect)
+            .map(|named| {
+                named
+                    .iter()
+                    .map(|(name, preset)| (name.as_str(), preset))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Every preset as (effect, name, preset)
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &Preset)> {
+        self.effects.iter().flat_map(|(effect, named)| {
+            named
+                .iter()
+                .map(move |(name, preset)| (effect.as_str(), name.as_str(), preset))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_presets_are_listed_by_name() {
+        let presets = Presets::builtin();
+        let names: Vec<&str> =
+            presets.of("matrix").iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["drizzle", "storm"]);
+        assert!(presets.of("blank").is_empty());
+
+        let storm = presets.get("matrix", "storm").unwrap();
+        assert!(storm.builtin);
+        assert_eq!(storm.summary(), "drops_coeff=3.0, speed_coeff=1.5");
+        assert_eq!(storm.overrides("matrix")[0].path, "matrix.drops_coeff");
+    }
+
+    #[test]
+    fn user_presets_replace_builtin_ones() {
+        let mut presets = Presets::builtin();
+        let named: BTreeMap<String, toml::Table> =
+            toml::from_str("storm = { drops_coeff = 5.0 }\nred = {}").unwrap();
+        presets.insert_all("matrix", named, false);
+
+        let storm = presets.get("matrix", "storm").unwrap();
+        assert!(!storm.builtin);
+        assert_eq!(storm.summary(), "drops_coeff=5.0");
+        assert_eq!(presets.of("matrix").len(), 3);
+    }
+}
@@ -1,35 +1,82 @@
 use super::draw::{pick_color, pick_style};
-use super::gradient;
 use super::rain_drop::RainDrop;
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::governor::scaled_count;
+use crate::gradient::{Gradient, Rgb};
+use crate::palette::{Palette, PaletteChoice};
+use crate::random;
 
 use derive_builder::Builder;
-use rand::{self, RngExt};
+use rand::{self, RngExt, rngs::SmallRng};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Builder, Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
-pub struct DigitalRainOptions {
-    #[builder(default = "(10, 20)")]
-    #[serde(skip)]
-    pub drops_range: (u16, u16),
-    #[builder(default = "(2, 16)")]
-    #[serde(skip)]
-    pub speed_range: (u16, u16),
+/// User-facing settings, `[matrix]` section of `tarts.toml`
+#[derive(Builder, Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[builder(public, setter(into))]
+pub struct DigitalRainConfig {
     #[builder(default = "1.0")]
     pub drops_coeff: f32,
     #[builder(default = "1.0")]
     pub speed_coeff: f32,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+/// Runtime options resolved from `DigitalRainConfig` for a screen size
+#[derive(Builder, Debug, PartialEq, Clone)]
+pub struct DigitalRainOptions {
+    pub drops_range: (u16, u16),
+    pub speed_range: (u16, u16),
+    #[builder(default)]
+    pub palette: PaletteChoice,
+}
+
+impl Default for DigitalRainConfig {
+    fn default() -> Self {
+        DigitalRainConfigBuilder::default().build().unwrap()
+    }
+}
+
+impl DigitalRainConfig {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.positive("drops_coeff", self.drops_coeff);
+        check.positive("speed_coeff", self.speed_coeff);
+        check.palette("palette", &self.palette);
+    }
+
+    /// Scale drop count with screen area and speed with its height
+    pub fn to_runtime(&self, screen_size: (u16, u16)) -> DigitalRainOptions {
+        let (w, h) = screen_size;
+        let area = w as f32 * h as f32;
+        DigitalRainOptions {
+            drops_range: {
+                let min = (area / 160.0 * self.drops_coeff) as u16;
+                let max = (area / 80.0 * self.drops_coeff) as u16;
+                (min.max(10), max.max(20))
+            },
+            speed_range: {
+                let min = ((h as f32 / 20.0 * self.speed_coeff) as u16).max(2);
+                let max = ((h as f32 / 10.0 * self.speed_coeff) as u16).max(16);
+                (min, max)
+            },
+            palette: self.palette.clone(),
+        }
+    }
 }
 
 pub struct DigitalRain {
     pub screen_size: (u16, u16),
     options: DigitalRainOptions,
-    gradients: Vec<Vec<gradient::Color>>,
+    gradients: Vec<Vec<Rgb>>,
     rain_drops: Vec<RainDrop>,
     buffer: Buffer,
-    rng: rand::prelude::ThreadRng,
+    rng: SmallRng,
+    quality: f32,
 }
 
 impl TerminalEffect for DigitalRain {
@@ -40,7 +87,12 @@ impl TerminalEffect for DigitalRain {
 
         // fill current buffer
         // first draw drops with bigger fy
-        Self::fill_buffer(&mut self.rain_drops, &mut curr_buffer, &self.gradients);
+        Self::fill_buffer(
+            &mut self.rain_drops,
+            &mut curr_buffer,
+            &self.gradients,
+            self.options.palette.palette(),
+        );
 
         let diff = self.buffer.diff(&curr_buffer);
         self.buffer = curr_buffer;
@@ -62,13 +114,43 @@ impl TerminalEffect for DigitalRain {
     }
 
     fn update_size(&mut self, width: u16, height: u16) {
+        // drops keep falling, ones beyond the right edge move inside
+        for rain_drop in self.rain_drops.iter_mut() {
+            rain_drop.fx %= width.max(1);
+        }
         self.screen_size = (width, height);
+        self.gradients = Self::make_gradients(height);
+        self.buffer = Buffer::new(width as usize, height as usize);
     }
 
     fn reset(&mut self) {
-        let new_effect = DigitalRain::new(self.options.clone(), self.screen_size);
+        let mut new_effect =
+            DigitalRain::new(self.options.clone(), self.screen_size);
+        new_effect.set_quality(self.quality);
         *self = new_effect;
     }
+
+    fn stats(&self) -> Vec<(&'static str, usize)> {
+        vec![("drops", self.rain_drops.len())]
+    }
+
+    fn set_quality(&mut self, quality: f32) {
+        self.quality = quality;
+        let max_drops = self.max_drops();
+        self.rain_drops.truncate(max_drops);
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        (4, 4)
+    }
+
+    fn reconfigure(&mut self, config: &Config) {
+        // drops keep falling, count and speed of new drops follow options
+        self.options = config.get_matrix_options(self.screen_size);
+        self.set_quality(self.quality);
+        let (width, height) = self.screen_size;
+        self.update_size(width, height);
+    }
 }
 
 /// Process digital rain effect.
@@ -77,7 +159,7 @@ impl TerminalEffect for DigitalRain {
 impl DigitalRain {
     // Initialize screensaver
     pub fn new(options: DigitalRainOptions, screen_size: (u16, u16)) -> Self {
-        let mut rng = rand::rng();
+        let mut rng: SmallRng = random::rng();
         let mut rain_drops: Vec<RainDrop> = vec![];
         let mut buffer: Buffer =
             Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
@@ -90,56 +172,14 @@ impl DigitalRain {
             ));
         }
 
-        // fill gradients
-        let gradients = vec![
-            gradient::two_step_color_gradient(
-                gradient::Color {
-                    r: 255,
-                    g: 255,
-                    b: 255,
-                },
-                gradient::Color { r: 0, g: 255, b: 0 },
-                gradient::Color {
-                    r: 10,
-                    g: 10,
-                    b: 10,
-                },
-                4,
-                3 * screen_size.1 as usize / 2,
-            ),
-            gradient::two_step_color_gradient(
-                gradient::Color {
-                    r: 200,
-                    g: 200,
-                    b: 200,
-                },
-                gradient::Color { r: 0, g: 250, b: 0 },
-                gradient::Color {
-                    r: 10,
-                    g: 10,
-                    b: 10,
-                },
-                6,
-                3 * screen_size.1 as usize / 2,
-            ),
-            gradient::two_step_color_gradient(
-                gradient::Color {
-                    r: 200,
-                    g: 200,
-                    b: 200,
-                },
-                gradient::Color { r: 0, g: 200, b: 0 },
-                gradient::Color {
-                    r: 10,
-                    g: 10,
-                    b: 10,
-                },
-                screen_size.1 as usize / 2,
-                3 * screen_size.1 as usize / 2,
-            ),
-        ];
+        let gradients = Self::make_gradients(screen_size.1);
 
-        Self::fill_buffer(&mut rain_drops, &mut buffer, &gradients);
+        Self::fill_buffer(
+            &mut rain_drops,
+            &mut buffer,
+            &gradients,
+            options.palette.palette(),
+        );
 
         Self {
             screen_size,
@@ -148,13 +188,15 @@ impl DigitalRain {
             rain_drops,
             buffer,
             rng,
+            quality: 1.0,
         }
     }
 
     pub fn fill_buffer(
         rain_drops: &mut [RainDrop],
         buffer: &mut Buffer,
-        gradients: &[Vec<gradient::Color>],
+        gradients: &[Vec<Rgb>],
+        palette: Option<&Palette>,
     ) {
         rain_drops.sort_by(|a, b| a.speed.partial_cmp(&b.speed).unwrap());
         for rain_drop in rain_drops.iter().rev() {
@@ -167,7 +209,7 @@ impl DigitalRain {
                         *y as usize,
                         Cell::new(
                             *character,
-                            pick_color(&rain_drop.style, index, gradients),
+                            pick_color(&rain_drop.style, index, gradients, palette),
                             pick_style(&rain_drop.style, index),
                         ),
                     );
@@ -176,18 +218,47 @@ impl DigitalRain {
         }
     }
 
+    /// Color gradients of drop bodies, their length depends on screen height
+    fn make_gradients(height: u16) -> Vec<Vec<Rgb>> {
+        let length = 3 * height as usize / 2;
+        // head color, body color reached at `middle` cells, dark tail
+        let body = |head: Rgb, body: Rgb, middle: usize| {
+            let gradient = Gradient::new([
+                (0.0, head),
+                (middle as f32 / length.max(1) as f32, body),
+                (1.0, Rgb::new(10, 10, 10)),
+            ]);
+            (1..=length)
+                .map(|i| gradient.sample(i as f32 / length as f32))
+                .collect()
+        };
+        vec![
+            body(Rgb::new(255, 255, 255), Rgb::new(0, 255, 0), 4),
+            body(Rgb::new(200, 200, 200), Rgb::new(0, 250, 0), 6),
+            body(
+                Rgb::new(200, 200, 200),
+                Rgb::new(0, 200, 0),
+                height as usize / 2,
+            ),
+        ]
+    }
+
+    /// Upper limit of drops scaled by quality
+    fn max_drops(&self) -> usize {
+        scaled_count(self.options.get_max_drops_number() as usize, self.quality)
+    }
+
     /// Add one more worm with decent chance
     pub fn add_one(&mut self) {
-        if self.rain_drops.len() >= self.options.get_max_drops_number() as usize {
+        if self.rain_drops.len() >= self.max_drops() {
             return;
         };
-        let mut rng = rand::rng();
-        if rng.random_range(0.0..=1.0) <= 0.3 {
+        if self.rng.random_range(0.0..=1.0) <= 0.3 {
             self.rain_drops.push(RainDrop::new(
                 self.screen_size,
                 &self.options,
                 self.rain_drops.len() + 1,
-                &mut rng,
+                &mut self.rng,
             ));
         };
     }
@@ -219,23 +290,7 @@ impl DefaultOptions for DigitalRain {
     type Options = DigitalRainOptions;
 
     fn default_options(width: u16, height: u16) -> Self::Options {
-        let drops_range = {
-            let min_drops = (width * height) / 160; // Approximately 0.6% of screen space
-            let max_drops = (width * height) / 80; // Approximately 1.2% of screen space
-            (min_drops.max(10), max_drops.max(20)) // Ensure minimum values
-        };
-
-        let speed_range = {
-            let min_speed = (height / 20).max(2); // Faster for larger screens
-            let max_speed = (height / 10).max(16); // But not too fast
-            (min_speed, max_speed)
-        };
-
-        DigitalRainOptionsBuilder::default()
-            .drops_range(drops_range)
-            .speed_range(speed_range)
-            .build()
-            .unwrap()
+        DigitalRainConfig::default().to_runtime((width, height))
     }
 }
 
@@ -251,6 +306,29 @@ mod tests {
             .unwrap()
     }
 
+    #[test]
+    fn options_follow_screen_size() {
+        let config = DigitalRainConfig::default();
+        assert_eq!(
+            config.to_runtime((80, 24)),
+            DigitalRainOptions {
+                drops_range: (12, 24),
+                speed_range: (2, 16),
+                palette: Default::default(),
+            }
+        );
+        assert_eq!(
+            DigitalRain::default_options(400, 200),
+            config.to_runtime((400, 200))
+        );
+
+        let dense = DigitalRainConfigBuilder::default()
+            .drops_coeff(2.0)
+            .build()
+            .unwrap();
+        assert_eq!(dense.to_runtime((400, 200)).drops_range, (1000, 2000));
+    }
+
     #[test]
     fn create_new() {
         let foo = DigitalRain::new(get_sane_default_options(), (100, 100));
@@ -1,4 +1,5 @@
-use crate::rain::gradient;
+use crate::gradient::Rgb;
+use crate::palette::Palette;
 use crate::rain::rain_drop::RainDropStyle;
 use crossterm::style;
 
@@ -17,8 +18,12 @@ pub fn pick_style(vw_style: &RainDropStyle, pos: usize) -> style::Attribute {
 pub fn pick_color(
     vw_style: &RainDropStyle,
     pos: usize,
-    gradients: &[Vec<gradient::Color>],
+    gradients: &[Vec<Rgb>],
+    palette: Option<&Palette>,
 ) -> style::Color {
+    if let Some(palette) = palette {
+        return pick_palette_color(vw_style, pos, gradients[2].len(), palette);
+    }
     match vw_style {
         RainDropStyle::Gradient => match pos {
             0 => style::Color::White,
@@ -36,18 +41,39 @@ pub fn pick_color(
                 b: 0,
             },
         },
+        // drops on tiny screens may be longer than the gradient
         RainDropStyle::Back => {
-            let color = gradients[2][pos];
-            style::Color::Rgb {
-                r: color.r,
-                g: color.g,
-                b: color.b,
+            match gradients[2].get(pos).or(gradients[2].last()) {
+                Some(&color) => color.into(),
+                None => style::Color::DarkGrey,
             }
         }
         _ => style::Color::DarkGrey,
     }
 }
 
+/// Same shape as the default colors: head at the bright end of the palette,
+/// bodies fade towards the dark end, back drops stay in the lower part
+fn pick_palette_color(
+    vw_style: &RainDropStyle,
+    pos: usize,
+    back_len: usize,
+    palette: &Palette,
+) -> style::Color {
+    let fade = |dimmed: usize| 0.5 * (1.0 - dimmed.clamp(10, 255) as f32 / 255.0);
+    match vw_style {
+        RainDropStyle::Gradient | RainDropStyle::Front if pos == 0 => {
+            palette.color(1.0)
+        }
+        RainDropStyle::Gradient => palette.color(fade(pos * 12)),
+        RainDropStyle::Front => palette.color(fade(pos.pow(2))),
+        RainDropStyle::Back => {
+            palette.color(0.4 * (1.0 - pos as f32 / back_len.max(1) as f32))
+        }
+        _ => palette.color(0.15),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::rain::digital_rain::{DigitalRain, DigitalRainOptionsBuilder};
@@ -68,7 +94,11 @@ mod tests {
     fn run_loop_10_iterations() {
         let mut stdout = Vec::new();
         let mut digital_rain = get_default_rain();
-        let _ = crate::common::run_loop(&mut stdout, &mut digital_rain, Some(10));
+        let options = crate::common::LoopOptions {
+            iterations: Some(10),
+            ..Default::default()
+        };
+        let _ = crate::common::run_loop(&mut stdout, &mut digital_rain, &options);
     }
 
     /* // NOTE: this test failed on github CI pipeline
@@ -76,14 +106,19 @@ mod tests {
     fn run_loop_fps_gte_0() {
         let mut stdout = Vec::new();
         let mut digital_rain = get_default_rain();
+        let options = crate::common::LoopOptions {
+            iterations: Some(10),
+            ..Default::default()
+        };
         // let mut fps: f64 = 0.0;
-        let fps = crate::common::run_loop(&mut stdout, &mut digital_rain, Some(10))
-            .unwrap();
+        let fps = crate::common::run_loop(&mut stdout, &mut digital_rain, &options)
+            .unwrap()
+            .fps();
 
         /*
         for _ in 0..10 {
             let fps_res =
-                crate::common::run_loop(&mut stdout, &mut digital_rain, Some(10));
+                crate::common::run_loop(&mut stdout, &mut digital_rain, &options);
             if let Ok(f) = fps_res {
                 fps = f;
                 break;
@@ -1,4 +1,3 @@
 pub mod digital_rain;
 pub mod draw;
-pub mod gradient;
 pub mod rain_drop;
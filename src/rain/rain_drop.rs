@@ -24,9 +24,12 @@ static CHARACTERS_MAP: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| {
 
 /// Characters used to form kinda-canonical matrix effect
 static CHARACTERS: LazyLock<Vec<char>> = LazyLock::new(|| {
+    // in label order, seeded runs pick the same characters
+    let mut labels: Vec<&&str> = CHARACTERS_MAP.keys().collect();
+    labels.sort();
     let mut v = Vec::new();
-    for (_, chars) in CHARACTERS_MAP.iter() {
-        v.append(&mut chars.chars().collect());
+    for label in labels {
+        v.append(&mut CHARACTERS_MAP[*label].chars().collect());
     }
     v
 });
@@ -69,19 +72,20 @@ impl RainDrop {
         screen_size: (u16, u16),
         options: &DigitalRainOptions,
         drop_id: usize,
-        rng: &mut rand::prelude::ThreadRng,
+        rng: &mut impl Rng,
     ) -> Self {
         // pick random first character
-        let style: RainDropStyle = rand::random();
-        let fx: u16 = rng.random_range(0..screen_size.0);
-        let fy: f32 = rng.random_range(0..screen_size.1 / 4) as f32;
+        // ranges are widened on tiny screens, they would be empty otherwise
+        let style: RainDropStyle = rng.random();
+        let fx: u16 = rng.random_range(0..screen_size.0.max(1));
+        let fy: f32 = rng.random_range(0..(screen_size.1 / 4).max(1)) as f32;
         let max_length: usize =
-            rng.random_range(4..=(2 * screen_size.1 / 3)) as usize;
+            rng.random_range(4..=(2 * screen_size.1 / 3).max(4)) as usize;
 
         let speed: u16 =
             rng.random_range(options.get_min_speed()..=options.get_max_speed());
 
-        let init_length = rng.random_range(1..max_length / 2);
+        let init_length = rng.random_range(1..(max_length / 2).max(2));
         let mut body: Vec<char> = vec![*CHARACTERS.choose(rng).unwrap()];
         for _ in 1..init_length {
             body.push(*CHARACTERS.choose(rng).unwrap());
@@ -140,17 +144,19 @@ impl RainDrop {
         &mut self,
         screen_size: (u16, u16),
         options: &DigitalRainOptions,
-        rng: &mut rand::prelude::ThreadRng,
+        rng: &mut impl Rng,
     ) {
         self.body.clear();
         self.body.insert(0, *CHARACTERS.choose(rng).unwrap());
-        self.style = rand::random();
+        self.style = rng.random();
         self.fy = 0.0;
-        self.fx = rng.random_range(0..screen_size.0);
+        self.fx = rng.random_range(0..screen_size.0.max(1));
         self.speed =
             rng.random_range(options.get_min_speed()..=options.get_max_speed());
-        self.max_length =
-            rng.random_range(screen_size.1 / 4 + 1..=(screen_size.1 / 2)) as usize;
+        let min_length = screen_size.1 / 4 + 1;
+        self.max_length = rng
+            .random_range(min_length..=(screen_size.1 / 2).max(min_length))
+            as usize;
     }
 
     /// Grow condition
@@ -159,7 +165,7 @@ impl RainDrop {
     }
 
     /// Grow up matrix worm characters array
-    fn grow(&mut self, head_y: u16, rng: &mut rand::prelude::ThreadRng) {
+    fn grow(&mut self, head_y: u16, rng: &mut impl Rng) {
         if self.body.len() >= self.max_length {
             self.body.truncate(self.max_length);
             return;
@@ -201,7 +207,7 @@ impl RainDrop {
         screen_size: (u16, u16),
         options: &DigitalRainOptions,
         dt: Duration,
-        rng: &mut rand::prelude::ThreadRng,
+        rng: &mut impl Rng,
     ) {
         // NOTE: looks like guard, but why i even need it here?
         if self.body.is_empty() {
@@ -463,6 +469,23 @@ mod tests {
         assert_eq!(new_drop.fy, 33.0); // should be reset there
     }
 
+    #[test]
+    fn tiny_screen() {
+        let mut rng = rand::rng();
+        for height in 1..8 {
+            let mut drop =
+                RainDrop::new((1, height), &get_sane_options(), 1, &mut rng);
+            for _ in 1..=100 {
+                drop.update(
+                    (1, height),
+                    &get_sane_options(),
+                    Duration::from_millis(100),
+                    &mut rng,
+                );
+            }
+        }
+    }
+
     #[test]
     fn out_of_bounds() {
         let mut rng = rand::rng();
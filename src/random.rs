@@ -0,0 +1,24 @@
+//! Random generators of effects.
+//!
+//! Effects take their generators from `rng`. Normally each one is seeded
+//! from the thread generator, after `set_seed` all of them are derived
+//! from one seeded root in the order they are made, so an effect run
+//! without a terminal repeats exactly.
+use rand::{SeedableRng, rngs::SmallRng};
+use std::sync::{Mutex, PoisonError};
+
+static ROOT: Mutex<Option<SmallRng>> = Mutex::new(None);
+
+/// Derive every following generator from `seed`
+pub fn set_seed(seed: u64) {
+    *ROOT.lock().unwrap_or_else(PoisonError::into_inner) =
+        Some(SmallRng::seed_from_u64(seed));
+}
+
+/// New generator, seeded from the root when `set_seed` was called
+pub fn rng() -> SmallRng {
+    match ROOT.lock().unwrap_or_else(PoisonError::into_inner).as_mut() {
+        Some(root) => SmallRng::from_rng(root),
+        None => rand::make_rng(),
+    }
+}
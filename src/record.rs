@@ -0,0 +1,268 @@
+//! Native asciicast v2 recordings.
+//!
+//! `Recorder` sits between the loop and the terminal, everything written
+//! to the terminal is copied into the cast and every flush becomes one
+//! output event with the time it happened. Headless recordings take frames
+//! at the effect frame rate instead. The header carries only the terminal
+//! size and start time, no environment variables.
+use crate::common::{TerminalEffect, draw_diff, run_headless};
+use crate::error::Result;
+use crate::theme::Theme;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Where and how to record, `--record` and `--idle-time-limit`
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    pub path: PathBuf,
+    /// Pauses longer than this many seconds are shortened to it
+    pub idle_time_limit: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Header {
+    version: u8,
+    width: u16,
+    height: u16,
+    timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    idle_time_limit: Option<f64>,
+}
+
+/// Writes header and events of an asciicast v2 file
+pub struct CastWriter<W: Write> {
+    out: W,
+    idle_time_limit: Option<f64>,
+    /// Time of the previous event as it happened
+    last: f64,
+    /// Idle time cut out so far
+    skipped: f64,
+}
+
+impl CastWriter<BufWriter<File>> {
+    pub fn create(options: &RecordOptions, size: (u16, u16)) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(&options.path)?);
+        Self::new(file, size, options.idle_time_limit)
+    }
+}
+
+impl<W: Write> CastWriter<W> {
+    /// Write the header, the cursor is hidden like in the terminal
+    pub fn new(
+        mut out: W,
+        (width, height): (u16, u16),
+        idle_time_limit: Option<f64>,
+    ) -> io::Result<Self> {
+        let header = Header {
+            version: 2,
+            width,
+            height,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            idle_time_limit,
+        };
+        serde_json::to_writer(&mut out, &header).map_err(io::Error::other)?;
+        writeln!(out)?;
+        let mut cast = Self {
+            out,
+            idle_time_limit,
+            last: 0.0,
+            skipped: 0.0,
+        };
+        cast.output(Duration::ZERO, "\x1b[?25l")?;
+        Ok(cast)
+    }
+
+    /// Terminal output at `at` since the start of the recording
+    pub fn output(&mut self, at: Duration, data: &str) -> io::Result<()> {
+        self.event(at, "o", data)
+    }
+
+    pub fn resize(
+        &mut self,
+        at: Duration,
+        (width, height): (u16, u16),
+    ) -> io::Result<()> {
+        self.event(at, "r", &format!("{}x{}", width, height))
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    fn event(&mut self, at: Duration, code: &str, data: &str) -> io::Result<()> {
+        let time = self.time(at);
+        serde_json::to_writer(&mut self.out, &(time, code, data))
+            .map_err(io::Error::other)?;
+        writeln!(self.out)
+    }
+
+    /// Seconds since the start with idle time over the limit cut out,
+    /// rounded to microseconds like asciinema does
+    fn time(&mut self, at: Duration) -> f64 {
+        let now = at.as_secs_f64();
+        if let Some(limit) = self.idle_time_limit {
+            let idle = now - self.last;
+            if idle > limit {
+                self.skipped += idle - limit;
+            }
+        }
+        self.last = now;
+        ((now - self.skipped) * 1e6).round() / 1e6
+    }
+}
+
+/// Writer which passes everything to `inner` and copies it into the cast,
+/// each flush with pending output is one event
+pub struct Recorder<W: Write> {
+    inner: W,
+    cast: Option<CastWriter<BufWriter<File>>>,
+    pending: Vec<u8>,
+    started_at: Instant,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(inner: W, cast: Option<CastWriter<BufWriter<File>>>) -> Self {
+        Self {
+            inner,
+            cast,
+            pending: Vec::new(),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Note the new terminal size in the recording
+    pub fn resize(&mut self, size: (u16, u16)) -> io::Result<()> {
+        match self.cast.as_mut() {
+            Some(cast) => cast.resize(self.started_at.elapsed(), size),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Write for Recorder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if self.cast.is_some() {
+            self.pending.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if let Some(cast) = self.cast.as_mut()
+            && !self.pending.is_empty()
+        {
+            cast.output(
+                self.started_at.elapsed(),
+                &String::from_utf8_lossy(&self.pending),
+            )?;
+            cast.flush()?;
+            self.pending.clear();
+        }
+        Ok(())
+    }
+}
+
+/// Record `frames` frames without a terminal, frames are spaced by the
+/// effect frame rate
+pub fn record_headless<TE>(
+    effect: &mut TE,
+    screen_size: (u16, u16),
+    frames: usize,
+    theme: &Theme,
+    options: &RecordOptions,
+) -> Result<()>
+where
+    TE: TerminalEffect + ?Sized,
+{
+    let mut cast = CastWriter::create(options, screen_size)?;
+    let mut out = Vec::new();
+    if *theme != Theme::default() {
+        theme.clear(&mut out)?;
+    }
+    run_headless(effect, frames, |at, diff| {
+        draw_diff(&mut out, diff, theme)?;
+        if !out.is_empty() {
+            cast.output(at, &String::from_utf8_lossy(&out))?;
+            out.clear();
+        }
+        Ok(())
+    })?;
+    cast.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(cast: &[u8]) -> Vec<serde_json::Value> {
+        String::from_utf8_lossy(cast)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn cast_has_header_and_timed_events() {
+        let mut out = Vec::new();
+        let mut cast = CastWriter::new(&mut out, (80, 24), None).unwrap();
+        cast.output(Duration::from_millis(1500), "\x1b[1;1Hx")
+            .unwrap();
+        cast.resize(Duration::from_secs(2), (100, 30)).unwrap();
+
+        let events = events(&out);
+        assert_eq!(events[0]["version"], 2);
+        assert_eq!(events[0]["width"], 80);
+        assert_eq!(events[0]["height"], 24);
+        assert!(events[0].get("env").is_none());
+        assert_eq!(events[1], serde_json::json!([0.0, "o", "\x1b[?25l"]));
+        assert_eq!(events[2], serde_json::json!([1.5, "o", "\x1b[1;1Hx"]));
+        assert_eq!(events[3], serde_json::json!([2.0, "r", "100x30"]));
+    }
+
+    #[test]
+    fn idle_time_is_cut_to_the_limit() {
+        let mut out = Vec::new();
+        let mut cast = CastWriter::new(&mut out, (80, 24), Some(1.0)).unwrap();
+        cast.output(Duration::from_millis(500), "a").unwrap();
+        cast.output(Duration::from_secs(10), "b").unwrap();
+        cast.output(Duration::from_millis(10_250), "c").unwrap();
+
+        let times: Vec<f64> = events(&out)[2..]
+            .iter()
+            .map(|event| event[0].as_f64().unwrap())
+            .collect();
+        assert_eq!(times, vec![0.5, 1.5, 1.75]);
+        assert_eq!(events(&out)[0]["idle_time_limit"], 1.0);
+    }
+
+    #[test]
+    fn recorder_turns_flushes_into_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = RecordOptions {
+            path: dir.path().join("out.cast"),
+            idle_time_limit: None,
+        };
+        let mut terminal = Vec::new();
+        {
+            let cast = CastWriter::create(&options, (10, 5)).unwrap();
+            let mut recorder = Recorder::new(&mut terminal, Some(cast));
+            write!(recorder, "ab").unwrap();
+            write!(recorder, "c").unwrap();
+            recorder.flush().unwrap();
+            recorder.flush().unwrap();
+        }
+        assert_eq!(terminal, b"abc");
+        let events = events(&std::fs::read(&options.path).unwrap());
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2][2], "abc");
+    }
+}
@@ -0,0 +1,428 @@
+//! Registry of available effects: names, short descriptions and a single
+//! place which knows how to construct any effect from the `Config`.
+use crate::{
+    blank, boids, common::TerminalEffect, config::Config, constellation, crab,
+    cube, donut, fire, life, maze, particles, pipes, plasma, rain, terrain,
+};
+
+/// Name and one line description of the effect
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EffectInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// All effects in the order they are shown in help and gallery
+pub const EFFECTS: &[EffectInfo] = &[
+    EffectInfo {
+        name: "matrix",
+        description: "Matrix digital rain",
+    },
+    EffectInfo {
+        name: "life",
+        description: "Conway's Game of Life",
+    },
+    EffectInfo {
+        name: "maze",
+        description: "Maze generation",
+    },
+    EffectInfo {
+        name: "boids",
+        description: "Boids flocking simulation",
+    },
+    EffectInfo {
+        name: "cube",
+        description: "3D cube rotation",
+    },
+    EffectInfo {
+        name: "crab",
+        description: "ASCII crab animation",
+    },
+    EffectInfo {
+        name: "donut",
+        description: "3D donut rotation",
+    },
+    EffectInfo {
+        name: "pipes",
+        description: "Pipe maze animation",
+    },
+    EffectInfo {
+        name: "plasma",
+        description: "Plasma effect",
+    },
+    EffectInfo {
+        name: "fire",
+        description: "Fire simulation",
+    },
+    EffectInfo {
+        name: "terrain",
+        description: "Terrain generation",
+    },
+    EffectInfo {
+        name: "constellation",
+        description: "Drifting stars and dotted connections",
+    },
+    EffectInfo {
+        name: "particles",
+        description: "Particles defined in the config: snow, sparks, bubbles",
+    },
+    EffectInfo {
+        name: "blank",
+        description: "Blank screen",
+    },
+];
+
+/// Find effect description by name
+pub fn find(name: &str) -> Option<&'static EffectInfo> {
+    EFFECTS.iter().find(|info| info.name == name)
+}
+
+/// Build effect by name using options from config, `None` for unknown names
+pub fn create(
+    name: &str,
+    config: &Config,
+    screen_size: (u16, u16),
+) -> Option<Box<dyn TerminalEffect>> {
+    // terminal may report zero size, effects expect at least one cell
+    let screen_size = (screen_size.0.max(1), screen_size.1.max(1));
+    let effect: Box<dyn TerminalEffect> = match name {
+        "matrix" => Box::new(rain::digital_rain::DigitalRain::new(
+            config.get_matrix_options(screen_size),
+            screen_size,
+        )),
+        "life" => Box::new(life::ConwayLife::new(
+            config.get_life_options(screen_size),
+            screen_size,
+        )),
+        "maze" => Box::new(maze::Maze::new(
+            config.get_maze_options(screen_size),
+            screen_size,
+        )),
+        "boids" => Box::new(boids::Boids::new(
+            config.get_boids_options(screen_size),
+            screen_size,
+        )),
+        "blank" => {
+            Box::new(blank::Blank::new(config.get_blank_options(), screen_size))
+        }
+        "cube" => Box::new(cube::Cube::new(config.get_cube_options(), screen_size)),
+        "crab" => Box::new(crab::Crab::new(
+            config.get_crab_options(screen_size),
+            screen_size,
+        )),
+        "donut" => Box::new(donut::Donut::new(
+            config.get_donut_options(screen_size),
+            screen_size,
+        )),
+        "pipes" => {
+            Box::new(pipes::Pipes::new(config.get_pipes_options(), screen_size))
+        }
+        "plasma" => Box::new(plasma::Plasma::new(
+            config.get_plasma_options(),
+            screen_size,
+        )),
+        "fire" => Box::new(fire::Fire::new(config.get_fire_options(), screen_size)),
+        "terrain" => Box::new(terrain::Terrain::new(
+            config.get_terrain_options(),
+            screen_size,
+        )),
+        "particles" => Box::new(particles::Particles::new(
+            config.get_particles_options(),
+            screen_size,
+        )),
+        "constellation" => Box::new(constellation::Constellation::new(
+            config.get_constellation_options(),
+            screen_size,
+        )),
+        _ => return None,
+    };
+    Some(effect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{LoopOptions, run_loop};
+    use crate::snapshot::Snapshot;
+
+    #[test]
+    fn every_effect_is_constructible() {
+        let config = Config::default();
+        for info in EFFECTS {
+            let mut effect = create(info.name, &config, (80, 24))
+                .unwrap_or_else(|| panic!("{} is not registered", info.name));
+            for _ in 0..3 {
+                effect.get_diff();
+                effect.update();
+            }
+        }
+    }
+
+    fn survives_screen_size(
+        config: &Config,
+        name: &str,
+        (width, height): (u16, u16),
+    ) {
+        let mut effect =
+            create(name, config, (width, height)).expect("effect is registered");
+        for _ in 0..2 {
+            for (x, y, _) in effect.get_diff() {
+                assert!(
+                    x < width as usize && y < height as usize,
+                    "{} drew ({}, {}) on {}x{}",
+                    name,
+                    x,
+                    y,
+                    width,
+                    height
+                );
+            }
+            effect.update();
+        }
+    }
+
+    #[test]
+    fn any_screen_size_is_survivable() {
+        // every size below 9 and the sizes around typical terminals, the
+        // full sweep is `every_screen_size_is_survivable`
+        let config = Config::default();
+        let widths = [1, 2, 3, 4, 5, 6, 7, 8, 10, 13, 16, 24, 47, 80, 181, 300];
+        let heights = [1, 2, 3, 4, 5, 6, 7, 8, 10, 13, 24, 47, 100];
+
+        for info in EFFECTS {
+            for &width in &widths {
+                for &height in &heights {
+                    survives_screen_size(&config, info.name, (width, height));
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "30000 sizes per effect, CI runs it in release mode"]
+    fn every_screen_size_is_survivable() {
+        // built once per width and resized through every height, one frame
+        // per size; sizes below the minimum the loop never resizes to are
+        // built anew. Effects are swept in parallel, donut is the slowest
+        let config = Config::default();
+        std::thread::scope(|scope| {
+            for info in EFFECTS {
+                let config = &config;
+                scope.spawn(move || sweep_screen_sizes(config, info.name));
+            }
+        });
+    }
+
+    fn sweep_screen_sizes(config: &Config, name: &str) {
+        for width in 1..=300 {
+            let mut effect = create(name, config, (width, 100)).unwrap();
+            let (min_width, min_height) = effect.min_size();
+            for height in (1..=100).rev() {
+                if width < min_width || height < min_height {
+                    effect = create(name, config, (width, height)).unwrap();
+                } else {
+                    effect.update_size(width, height);
+                }
+                for (x, y, _) in effect.get_diff() {
+                    assert!(
+                        x < width as usize && y < height as usize,
+                        "{} drew ({}, {}) on {}x{}",
+                        name,
+                        x,
+                        y,
+                        width,
+                        height
+                    );
+                }
+                effect.update();
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_resize_is_survivable() {
+        let config = Config::default();
+        let sizes = [(120, 40), (20, 10), (200, 60), (33, 7), (80, 24), (1, 1)];
+
+        for info in EFFECTS {
+            let mut effect = create(info.name, &config, (80, 24)).unwrap();
+            for _ in 0..3 {
+                for &(width, height) in &sizes {
+                    // loop never resizes effect below its minimum size
+                    let (min_width, min_height) = effect.min_size();
+                    let (width, height) =
+                        (width.max(min_width), height.max(min_height));
+                    effect.update_size(width, height);
+                    for _ in 0..3 {
+                        for (x, y, _) in effect.get_diff() {
+                            assert!(
+                                x < width as usize && y < height as usize,
+                                "{} drew ({}, {}) after resize to {}x{}",
+                                info.name,
+                                x,
+                                y,
+                                width,
+                                height
+                            );
+                        }
+                        effect.update();
+                    }
+                }
+            }
+            effect.reset();
+            effect.get_diff();
+        }
+    }
+
+    #[test]
+    fn every_effect_stops_after_iterations() {
+        // `run_loop` reads the terminal size and input, without a terminal
+        // it returns an error right away and only the exit is checked
+        let size = crossterm::terminal::size().unwrap_or((80, 24));
+        let config = Config::default();
+        for info in EFFECTS {
+            let mut effect = create(info.name, &config, size).unwrap();
+            let options = LoopOptions {
+                iterations: Some(2),
+                name: info.name.to_string(),
+                ..Default::default()
+            };
+            let mut stdout: Vec<u8> = Vec::new();
+            if let Ok(metrics) = run_loop(&mut stdout, effect.as_mut(), &options) {
+                assert_eq!(metrics.summary().frames, 3, "{}", info.name);
+            }
+        }
+    }
+
+    #[test]
+    fn snapshots_restore_the_same_state() {
+        let config = Config::default();
+        for name in ["life", "boids", "maze", "pipes", "constellation"] {
+            let mut effect = create(name, &config, (60, 20)).unwrap();
+            for _ in 0..10 {
+                effect.get_diff();
+                effect.update();
+            }
+            let state = effect.snapshot().expect("effect saves its state");
+
+            let mut resumed = create(name, &config, (30, 10)).unwrap();
+            resumed.restore(state.clone(), (60, 20)).unwrap();
+            assert_eq!(resumed.snapshot(), Some(state.clone()), "{}", name);
+            resumed.get_diff();
+            resumed.update();
+
+            // resumed on a smaller terminal, state is fitted instead of lost
+            let mut smaller = create(name, &config, (30, 10)).unwrap();
+            Snapshot::new(name, (60, 20), state.clone())
+                .restore(smaller.as_mut(), name, (40, 15))
+                .unwrap();
+            for (x, y, _) in smaller.get_diff() {
+                assert!(x < 40 && y < 15, "{} drew ({}, {})", name, x, y);
+            }
+            smaller.update();
+        }
+
+        // half carved maze keeps the paths which fit the new screen
+        let mut maze = create("maze", &config, (60, 20)).unwrap();
+        for _ in 0..300 {
+            maze.update();
+        }
+        let state = maze.snapshot().unwrap();
+        let fitting = |state: &toml::Value| -> Vec<toml::Value> {
+            state["paths"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|path| {
+                    path[0].as_integer().unwrap() < 40
+                        && path[1].as_integer().unwrap() < 15
+                })
+                .cloned()
+                .collect()
+        };
+        let mut resumed = create("maze", &config, (40, 15)).unwrap();
+        Snapshot::new("maze", (60, 20), state.clone())
+            .restore(resumed.as_mut(), "maze", (40, 15))
+            .unwrap();
+        let resumed = resumed.snapshot().unwrap();
+        assert!(!fitting(&state).is_empty());
+        assert_eq!(resumed["paths"].as_array().unwrap(), &fitting(&state));
+        assert!(!resumed["stack"].as_array().unwrap().is_empty());
+
+        // hand edited pipes must not index past the line characters
+        let mut pipes = create("pipes", &config, (60, 20)).unwrap();
+        pipes.get_diff();
+        let mut state = pipes.snapshot().unwrap();
+        let pipe = state["pipes"].as_array_mut().unwrap()[0]
+            .as_table_mut()
+            .unwrap();
+        pipe.insert("line_type".into(), 7.into());
+        assert!(pipes.restore(state, (60, 20)).is_err());
+    }
+
+    #[test]
+    fn quality_scales_load() {
+        let config = Config::default();
+        for info in EFFECTS {
+            let mut effect = create(info.name, &config, (120, 40)).unwrap();
+            let full = effect.stats();
+            for quality in [0.1, 0.5, 1.0, 0.3] {
+                effect.set_quality(quality);
+                for _ in 0..3 {
+                    effect.get_diff();
+                    effect.update();
+                }
+            }
+            effect.set_quality(0.1);
+            for ((label, low), (_, high)) in effect.stats().iter().zip(&full) {
+                if matches!(*label, "boids" | "stars") {
+                    assert!(
+                        low < high,
+                        "{} {}: {} >= {}",
+                        info.name,
+                        label,
+                        low,
+                        high
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reconfigure_keeps_effects_drawable() {
+        let defaults = Config::default();
+        let mut config = defaults.clone();
+        for (effect, name, _) in defaults.presets.iter() {
+            config.apply_preset(effect, name).unwrap();
+        }
+        for info in EFFECTS {
+            let mut effect = create(info.name, &defaults, (60, 20)).unwrap();
+            for _ in 0..3 {
+                effect.get_diff();
+                effect.update();
+            }
+            effect.reconfigure(&config);
+            for _ in 0..3 {
+                for (x, y, _) in effect.get_diff() {
+                    assert!(x < 60 && y < 20, "{} drew ({}, {})", info.name, x, y);
+                }
+                effect.update();
+            }
+        }
+    }
+
+    #[test]
+    fn zero_size_is_clamped() {
+        let mut effect = create("matrix", &Config::default(), (0, 0)).unwrap();
+        effect.update();
+        assert!(effect.get_diff().iter().all(|(x, y, _)| *x == 0 && *y == 0));
+    }
+
+    #[test]
+    fn find_by_name() {
+        assert_eq!(find("matrix").map(|info| info.name), Some("matrix"));
+        assert!(find("unknown").is_none());
+        assert!(create("unknown", &Config::default(), (80, 24)).is_none());
+    }
+}
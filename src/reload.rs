@@ -0,0 +1,168 @@
+//! Config hot reload.
+//!
+//! `ConfigSource` remembers where the running config came from: file
+//! layers, preset and command line overrides. `run_loop` polls it, and when
+//! one of the files changes on disk the config is built again the same way
+//! and handed to the effect. Changes are found by modification time, so no
+//! file watching dependency is needed.
+use crate::config::{Config, ConfigOverride, ConfigPaths};
+use crate::error::Result;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Time between two checks of config files
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    pub paths: ConfigPaths,
+    /// Effect and name of the preset applied over file layers
+    pub preset: Option<(String, String)>,
+    pub overrides: Vec<ConfigOverride>,
+    modified: Vec<Option<SystemTime>>,
+    checked_at: Option<Instant>,
+    /// Last config loaded without errors
+    current: Option<Config>,
+}
+
+impl ConfigSource {
+    pub fn new(
+        paths: ConfigPaths,
+        preset: Option<(String, String)>,
+        overrides: Vec<ConfigOverride>,
+    ) -> Self {
+        Self {
+            modified: modified(&paths),
+            paths,
+            preset,
+            overrides,
+            checked_at: None,
+            current: None,
+        }
+    }
+
+    /// Load file layers, apply preset and overrides. Returns the config and
+    /// a status message like `Config::load`
+    pub fn load(&mut self) -> Result<(Config, String)> {
+        let (mut config, mut status) = Config::load_from(&self.paths)?;
+        if let Some((effect, name)) = &self.preset {
+            config.apply_preset(effect, name)?;
+            status.push_str(&format!("\nPreset: {}", name));
+        }
+        config.apply_overrides(&self.overrides)?;
+        if !self.overrides.is_empty() {
+            let overrides: Vec<String> =
+                self.overrides.iter().map(ToString::to_string).collect();
+            status.push_str(&format!("\nOverrides: {}", overrides.join(", ")));
+        }
+        self.current = Some(config.clone());
+        Ok((config, status))
+    }
+
+    /// Config with changed `effect` section, palettes or theme once a file has
+    /// changed since the last check, files are looked at no more often than
+    /// `POLL_INTERVAL`
+    pub fn poll(&mut self, now: Instant, effect: &str) -> Option<Result<Config>> {
+        if self.checked_at.is_some_and(|checked_at| {
+            now.duration_since(checked_at) < POLL_INTERVAL
+        }) {
+            return None;
+        }
+        self.checked_at = Some(now);
+
+        let modified = modified(&self.paths);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        // user palettes and theme may change colors without touching the
+        // section
+        let seen = |config: &Config| {
+            (
+                config.effect_options_toml(effect),
+                config.palettes.clone(),
+                config.theme.clone(),
+            )
+        };
+        let previous = self.current.as_ref().map(seen);
+        match self.load() {
+            Ok((config, _)) => {
+                (previous != Some(seen(&config))).then_some(Ok(config))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+fn modified(paths: &ConfigPaths) -> Vec<Option<SystemTime>> {
+    paths
+        .layers()
+        .iter()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_section_is_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("tarts.toml");
+        let paths = ConfigPaths {
+            system: dir.path().join("system.toml"),
+            user: user.clone(),
+            project: dir.path().join("project.toml"),
+            explicit: false,
+        };
+        let overrides =
+            vec![ConfigOverride::parse("matrix.speed_coeff=2").unwrap()];
+        let mut source = ConfigSource::new(paths, None, overrides);
+        source.load().unwrap();
+        let now = Instant::now();
+        assert!(source.poll(now, "matrix").is_none());
+
+        // new file, overrides still win over it
+        std::fs::write(&user, "[matrix]\ndrops_coeff = 2.0\nspeed_coeff = 5.0\n")
+            .unwrap();
+        assert!(source.poll(now, "matrix").is_none(), "checked too early");
+        let config = source
+            .poll(now + POLL_INTERVAL, "matrix")
+            .expect("file has changed")
+            .unwrap();
+        assert_eq!(config.matrix.drops_coeff, 2.0);
+        assert_eq!(config.matrix.speed_coeff, 2.0);
+
+        // broken file is reported, running config stays
+        std::fs::write(&user, "[matrix\n").unwrap();
+        bump_modified(&user, 1);
+        assert!(
+            source
+                .poll(now + POLL_INTERVAL * 2, "matrix")
+                .unwrap()
+                .is_err()
+        );
+
+        // other sections don't bother the running effect
+        std::fs::write(
+            &user,
+            "[matrix]\ndrops_coeff = 2.0\n[fire]\nuse_colors = false\n",
+        )
+        .unwrap();
+        bump_modified(&user, 2);
+        assert!(source.poll(now + POLL_INTERVAL * 3, "matrix").is_none());
+    }
+
+    /// Move modification time forward, file systems may keep whole seconds
+    fn bump_modified(path: &std::path::Path, secs: u64) {
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        let modified = file.metadata().unwrap().modified().unwrap();
+        file.set_modified(modified + Duration::from_secs(secs))
+            .unwrap();
+    }
+}
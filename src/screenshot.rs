@@ -0,0 +1,231 @@
+//! Still frames of a running effect.
+//!
+//! The screen is saved three times: ANSI escapes for `cat`, plain text and
+//! a standalone HTML page with colored spans. Colors go through the theme,
+//! so files look like the terminal did.
+use crate::buffer::{Buffer, Cell};
+use crate::gradient::Rgb;
+use crate::theme::{self, Background, Theme};
+use crossterm::{Command, style};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name without extension, `tarts-<effect>-<unix millis>`
+pub fn timestamped_stem(effect: &str) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    PathBuf::from(format!("tarts-{}-{}", effect, timestamp))
+}
+
+/// Write `<stem>.ans`, `<stem>.txt` and `<stem>.html`, returns their paths
+pub fn save(
+    buffer: &Buffer,
+    theme: &Theme,
+    stem: &Path,
+) -> io::Result<Vec<PathBuf>> {
+    let files = [
+        ("ans", to_ansi(buffer, theme)),
+        ("txt", to_text(buffer)),
+        ("html", to_html(buffer, theme)),
+    ];
+    let mut paths = Vec::new();
+    for (extension, contents) in files {
+        let path = stem.with_extension(extension);
+        std::fs::write(&path, contents)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Lines of escape sequences, colors are reset at the end of every run
+pub fn to_ansi(buffer: &Buffer, theme: &Theme) -> String {
+    let mut out = String::new();
+    for y in 0..buffer.height {
+        for (cell, text) in runs(buffer, y) {
+            let styled = theme.style(&cell);
+            style::PrintStyledContent(style::StyledContent::new(
+                *styled.style(),
+                text,
+            ))
+            .write_ansi(&mut out)
+            .expect("writing to string");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Symbols only, trailing spaces of lines are dropped
+pub fn to_text(buffer: &Buffer) -> String {
+    let mut out = String::new();
+    for y in 0..buffer.height {
+        let line: String = (0..buffer.width)
+            .map(|x| symbol(&buffer.get(x, y)))
+            .collect();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Standalone page with the frame in a `<pre>`
+pub fn to_html(buffer: &Buffer, theme: &Theme) -> String {
+    let (background, foreground) = page_colors(theme);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>tarts</title>\n</head>\n\
+         <body style=\"margin:0;background:{}\">\n\
+         <pre style=\"margin:0;padding:1em;color:{};\
+         font-family:monospace;line-height:1.2\">",
+        String::from(background),
+        String::from(foreground)
+    );
+    for y in 0..buffer.height {
+        for (cell, text) in runs(buffer, y) {
+            let text = escape(&text);
+            match css(theme, &cell) {
+                Some(css) => out
+                    .push_str(&format!("<span style=\"{}\">{}</span>", css, text)),
+                None => out.push_str(&text),
+            }
+        }
+        out.push('\n');
+    }
+    out.push_str("</pre>\n</body>\n</html>\n");
+    out
+}
+
+/// Cells of the row grouped by color and attribute
+fn runs(buffer: &Buffer, y: usize) -> Vec<(Cell, String)> {
+    let mut runs: Vec<(Cell, String)> = Vec::new();
+    for x in 0..buffer.width {
+        let cell = buffer.get(x, y);
+        match runs.last_mut() {
+            Some((last, text))
+                if last.color == cell.color && last.attr == cell.attr =>
+            {
+                text.push(symbol(&cell))
+            }
+            _ => runs.push((cell, symbol(&cell).to_string())),
+        }
+    }
+    runs
+}
+
+fn symbol(cell: &Cell) -> char {
+    match cell.symbol {
+        '\0' => ' ',
+        symbol => symbol,
+    }
+}
+
+/// Background and text color of the page when cells don't set them
+pub fn page_colors(theme: &Theme) -> (Rgb, Rgb) {
+    let background = match theme.background {
+        Background::Color(color) => color,
+        Background::Terminal if theme.light => Rgb::new(255, 255, 255),
+        Background::Terminal => Rgb::new(0, 0, 0),
+    };
+    let foreground = if theme.light {
+        Rgb::new(0, 0, 0)
+    } else {
+        Rgb::new(192, 192, 192)
+    };
+    (background, foreground)
+}
+
+/// Inline style of a run, `None` when it is printed in page colors
+fn css(theme: &Theme, cell: &Cell) -> Option<String> {
+    let styled = theme.style(cell);
+    let style = styled.style();
+    // reset after the colors, terminal shows its own colors
+    if style.attributes.has(style::Attribute::Reset) {
+        return None;
+    }
+    let mut css = Vec::new();
+    if let Some(color) = style.foreground_color.and_then(theme::to_rgb) {
+        css.push(format!("color:{}", String::from(color)));
+    }
+    if let Some(color) = style.background_color.and_then(theme::to_rgb) {
+        css.push(format!("background:{}", String::from(color)));
+    }
+    let attributes = [
+        (style::Attribute::Bold, "font-weight:bold"),
+        (style::Attribute::Dim, "opacity:0.6"),
+        (style::Attribute::Italic, "font-style:italic"),
+        (style::Attribute::Underlined, "text-decoration:underline"),
+    ];
+    for (attribute, property) in attributes {
+        if style.attributes.has(attribute) {
+            css.push(property.to_string());
+        }
+    }
+    (!css.is_empty()).then(|| css.join(";"))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::ThemeBuilder;
+
+    fn frame() -> Buffer {
+        let mut buffer = Buffer::new(4, 2);
+        buffer.put_str(
+            0,
+            0,
+            "a<",
+            style::Color::Rgb { r: 0, g: 255, b: 0 },
+            style::Attribute::Bold,
+        );
+        buffer
+    }
+
+    #[test]
+    fn text_keeps_symbols_only() {
+        assert_eq!(to_text(&frame()), "a<\n\n");
+    }
+
+    #[test]
+    fn ansi_groups_runs_by_style() {
+        let ansi = to_ansi(&frame(), &Theme::default());
+        assert_eq!(ansi.matches("\x1b[38;2;0;255;0m").count(), 1);
+        assert!(ansi.contains("a<"));
+        assert_eq!(ansi.lines().count(), 2);
+    }
+
+    #[test]
+    fn html_has_colored_spans_in_theme_colors() {
+        let html = to_html(&frame(), &Theme::default());
+        assert!(html.contains(
+            "<span style=\"color:#00ff00;font-weight:bold\">a&lt;</span>  \n"
+        ));
+        assert!(html.contains("background:#000000"));
+
+        let light = ThemeBuilder::default().light(true).build().unwrap();
+        let html = to_html(&frame(), &light);
+        assert!(html.contains("background:#ffffff"));
+        assert!(!html.contains("color:#00ff00"));
+    }
+
+    #[test]
+    fn files_are_written_next_to_each_other() {
+        let dir = tempfile::tempdir().unwrap();
+        let stem = dir.path().join(timestamped_stem("matrix"));
+        let paths = save(&frame(), &Theme::default(), &stem).unwrap();
+        let names: Vec<String> = paths
+            .iter()
+            .map(|path| path.extension().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["ans", "txt", "html"]);
+        assert_eq!(std::fs::read_to_string(&paths[1]).unwrap(), "a<\n\n");
+    }
+}
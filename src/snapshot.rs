@@ -0,0 +1,218 @@
+//! Effect state saved to a file, `--resume` and the `w` key.
+//!
+//! The file is TOML with the effect name, the screen size the state was
+//! taken at and a `[state]` table each effect fills on its own:
+//!
+//! ```toml
+//! version = 1
+//! effect = "life"
+//! width = 80
+//! height = 24
+//!
+//! [state]
+//! generation = 42
+//! cells = [[3, 5, "ﾊ", "#00ff00"], ...]
+//! ```
+use crate::common::TerminalEffect;
+use crate::gradient::Rgb;
+use crossterm::style;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Layout of the snapshot file, bumped on incompatible changes
+pub const VERSION: u32 = 1;
+
+/// Simulation state of one effect and where it was taken
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub effect: String,
+    pub width: u16,
+    pub height: u16,
+    pub state: toml::Value,
+}
+
+impl Snapshot {
+    pub fn new(effect: &str, screen_size: (u16, u16), state: toml::Value) -> Self {
+        Self {
+            version: VERSION,
+            effect: effect.to_string(),
+            width: screen_size.0,
+            height: screen_size.1,
+            state,
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let snapshot: Self = toml::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if snapshot.version > VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "snapshot version {} is newer than supported {}, update tarts",
+                    snapshot.version, VERSION
+                ),
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        std::fs::write(path, text)
+    }
+
+    /// Continue `effect` from this state, then fit it to `screen_size`
+    pub fn restore<TE>(
+        self,
+        effect: &mut TE,
+        name: &str,
+        screen_size: (u16, u16),
+    ) -> io::Result<()>
+    where
+        TE: TerminalEffect + ?Sized,
+    {
+        if self.effect != name {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("snapshot of {} can't resume {}", self.effect, name),
+            ));
+        }
+        effect
+            .restore(self.state, (self.width, self.height))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if (self.width, self.height) != screen_size {
+            effect.update_size(screen_size.0, screen_size.1);
+        }
+        Ok(())
+    }
+}
+
+/// Continue `effect` from `path` if the file is there, returns whether it
+/// did. Effects without snapshots are an error, nothing could be saved
+pub fn resume<TE>(
+    effect: &mut TE,
+    name: &str,
+    path: &Path,
+    screen_size: (u16, u16),
+) -> io::Result<bool>
+where
+    TE: TerminalEffect + ?Sized,
+{
+    if effect.snapshot().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} can't save its state", name),
+        ));
+    }
+    if !path.exists() {
+        return Ok(false);
+    }
+    Snapshot::load(path)?.restore(effect, name, screen_size)?;
+    Ok(true)
+}
+
+/// `tarts-<effect>-<unix millis>.toml` next to the current directory
+pub fn timestamped_path(effect: &str) -> PathBuf {
+    crate::screenshot::timestamped_stem(effect).with_extension("toml")
+}
+
+/// Terminal color kept in a snapshot, `#rrggbb` or the name of a terminal
+/// color so the theme still applies to it after resume
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SavedColor(pub style::Color);
+
+const NAMED: [(&str, style::Color); 17] = [
+    ("reset", style::Color::Reset),
+    ("black", style::Color::Black),
+    ("dark_grey", style::Color::DarkGrey),
+    ("red", style::Color::Red),
+    ("dark_red", style::Color::DarkRed),
+    ("green", style::Color::Green),
+    ("dark_green", style::Color::DarkGreen),
+    ("yellow", style::Color::Yellow),
+    ("dark_yellow", style::Color::DarkYellow),
+    ("blue", style::Color::Blue),
+    ("dark_blue", style::Color::DarkBlue),
+    ("magenta", style::Color::Magenta),
+    ("dark_magenta", style::Color::DarkMagenta),
+    ("cyan", style::Color::Cyan),
+    ("dark_cyan", style::Color::DarkCyan),
+    ("white", style::Color::White),
+    ("grey", style::Color::Grey),
+];
+
+impl TryFrom<String> for SavedColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Some((_, color)) = NAMED.iter().find(|(name, _)| *name == value) {
+            return Ok(Self(*color));
+        }
+        if let Some(index) = value.strip_prefix("ansi_") {
+            return index
+                .parse()
+                .map(|index| Self(style::Color::AnsiValue(index)))
+                .map_err(|_| format!("invalid color `{}`", value));
+        }
+        Rgb::try_from(value).map(|rgb| Self(rgb.into()))
+    }
+}
+
+impl From<SavedColor> for String {
+    fn from(color: SavedColor) -> Self {
+        match color.0 {
+            style::Color::Rgb { r, g, b } => Rgb::new(r, g, b).into(),
+            style::Color::AnsiValue(index) => format!("ansi_{}", index),
+            named => NAMED
+                .iter()
+                .find(|(_, color)| *color == named)
+                .map(|(name, _)| name.to_string())
+                .unwrap_or_else(|| "reset".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blank::{Blank, BlankOptions};
+
+    #[test]
+    fn snapshot_file_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.toml");
+        let mut state = toml::Table::new();
+        state.insert("generation".into(), 7.into());
+        let snapshot = Snapshot::new("life", (80, 24), toml::Value::Table(state));
+        snapshot.save(&path).unwrap();
+        assert_eq!(Snapshot::load(&path).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn saved_colors_keep_names() {
+        for color in [
+            style::Color::Red,
+            style::Color::DarkGrey,
+            style::Color::AnsiValue(42),
+            style::Color::Rgb { r: 1, g: 2, b: 255 },
+        ] {
+            let text = String::from(SavedColor(color));
+            assert_eq!(SavedColor::try_from(text).unwrap().0, color);
+        }
+        assert!(SavedColor::try_from("mauve".to_string()).is_err());
+    }
+
+    #[test]
+    fn effects_without_state_refuse_resume() {
+        let mut effect = Blank::new(BlankOptions::default(), (10, 10));
+        let error =
+            resume(&mut effect, "blank", Path::new("missing.toml"), (10, 10))
+                .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+}
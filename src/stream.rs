@@ -0,0 +1,427 @@
+//! Frame streams as JSON lines, `--output jsonl` and `tarts play`.
+//!
+//! The first line is a header with the screen size, effect and frame rate,
+//! every next line is one frame: its time in seconds and the cells which
+//! changed, or all cells on key frames. Colors are `#rrggbb` after the
+//! theme, `null` is the terminal's own color.
+//!
+//! ```text
+//! {"version":1,"width":80,"height":24,"effect":"matrix","frame_rate":60.0}
+//! {"frame":0,"t":0.0,"full":true,"cells":[{"x":0,"y":0,"symbol":" ","fg":null},...]}
+//! {"frame":1,"t":0.016667,"cells":[{"x":12,"y":3,"symbol":"ﾊ","fg":"#00ff00","attr":"bold"}]}
+//! ```
+use crate::buffer::{Buffer, Cell};
+use crate::common::{TerminalEffect, draw_diff, is_exit_key, run_headless};
+use crate::crash;
+use crate::error::Result;
+use crate::gradient::Rgb;
+use crate::theme::{self, Background, Theme, ThemeBuilder};
+use crossterm::{
+    event::{self, Event},
+    style,
+    style::Attribute,
+    terminal,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const VERSION: u8 = 1;
+
+/// Attributes by their name in the stream, others are written as reset
+const ATTRIBUTES: [(&str, Attribute); 6] = [
+    ("bold", Attribute::Bold),
+    ("dim", Attribute::Dim),
+    ("italic", Attribute::Italic),
+    ("underlined", Attribute::Underlined),
+    ("reverse", Attribute::Reverse),
+    ("crossed_out", Attribute::CrossedOut),
+];
+
+/// How much to stream, `--frames` and `--keyframes`
+#[derive(Debug, Clone, Default)]
+pub struct StreamOptions {
+    /// Stop after this many frames, streams until interrupted otherwise
+    pub frames: Option<usize>,
+    /// Write all cells every this many frames, only the first frame
+    /// otherwise
+    pub keyframes: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    pub version: u8,
+    pub width: u16,
+    pub height: u16,
+    pub effect: String,
+    pub frame_rate: f64,
+    /// Painted background of the theme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<Rgb>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub frame: usize,
+    /// Seconds since the first frame
+    pub t: f64,
+    /// All cells of the screen, not only changed ones
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub full: bool,
+    pub cells: Vec<StreamCell>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamCell {
+    pub x: usize,
+    pub y: usize,
+    pub symbol: char,
+    pub fg: Option<Rgb>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attr: Option<String>,
+}
+
+impl StreamCell {
+    fn new(x: usize, y: usize, cell: &Cell, theme: &Theme) -> Self {
+        let attr = ATTRIBUTES
+            .iter()
+            .find(|(_, attribute)| *attribute == cell.attr)
+            .map(|(name, _)| name.to_string());
+        Self {
+            x,
+            y,
+            symbol: cell.symbol,
+            fg: theme::to_rgb(theme.color(cell.color)),
+            attr,
+        }
+    }
+
+    pub fn to_cell(&self) -> Cell {
+        let color = match self.fg {
+            Some(Rgb { r, g, b }) => style::Color::Rgb { r, g, b },
+            None => style::Color::Reset,
+        };
+        let attr = ATTRIBUTES
+            .iter()
+            .find(|(name, _)| Some(*name) == self.attr.as_deref())
+            .map_or(Attribute::Reset, |(_, attribute)| *attribute);
+        Cell::new(self.symbol, color, attr)
+    }
+}
+
+/// Writes the header and frames, keeps the screen for key frames
+pub struct FrameWriter<W: Write> {
+    out: W,
+    theme: Theme,
+    keyframes: Option<usize>,
+    screen: Buffer,
+    frame: usize,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(
+        mut out: W,
+        header: &Header,
+        theme: &Theme,
+        keyframes: Option<usize>,
+    ) -> io::Result<Self> {
+        writeln!(out, "{}", to_line(header)?)?;
+        Ok(Self {
+            out,
+            theme: theme.clone(),
+            keyframes,
+            screen: Buffer::new(header.width as usize, header.height as usize),
+            frame: 0,
+        })
+    }
+
+    /// Write the diff of the frame due at `at`, flushed for readers on the
+    /// other end of a pipe
+    pub fn frame(
+        &mut self,
+        at: Duration,
+        diff: &[(usize, usize, Cell)],
+    ) -> io::Result<()> {
+        self.screen.apply_diff(diff);
+        let full = self.frame == 0
+            || self.keyframes.is_some_and(|every| self.frame % every == 0);
+        let cells = if full {
+            all_cells(&self.screen)
+        } else {
+            diff.to_vec()
+        };
+        let frame = Frame {
+            frame: self.frame,
+            t: (at.as_secs_f64() * 1e6).round() / 1e6,
+            full,
+            cells: cells
+                .iter()
+                .map(|(x, y, cell)| StreamCell::new(*x, *y, cell, &self.theme))
+                .collect(),
+        };
+        writeln!(self.out, "{}", to_line(&frame)?)?;
+        self.out.flush()?;
+        self.frame += 1;
+        Ok(())
+    }
+}
+
+/// Reads a stream written by `FrameWriter`
+pub struct FrameReader<R: BufRead> {
+    lines: io::Lines<R>,
+    line: usize,
+    pub header: Header,
+}
+
+impl FrameReader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead> FrameReader<R> {
+    pub fn new(input: R) -> io::Result<Self> {
+        let mut lines = input.lines();
+        let first = lines.next().transpose()?.unwrap_or_default();
+        let header: Header = serde_json::from_str(&first)
+            .map_err(|e| invalid(format!("line 1: {}", e)))?;
+        if header.version != VERSION {
+            return Err(invalid(format!(
+                "stream version {} is not supported, expected {}",
+                header.version, VERSION
+            )));
+        }
+        Ok(Self {
+            lines,
+            line: 1,
+            header,
+        })
+    }
+
+    /// Next frame, `None` at the end of the stream
+    pub fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        for line in self.lines.by_ref() {
+            let line = line?;
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            return serde_json::from_str(&line)
+                .map(Some)
+                .map_err(|e| invalid(format!("line {}: {}", self.line, e)));
+        }
+        Ok(None)
+    }
+}
+
+/// JSON of one line, serialized before writing so a closed pipe is still
+/// reported as one
+fn to_line<T: Serialize>(value: &T) -> io::Result<String> {
+    serde_json::to_string(value).map_err(io::Error::other)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn all_cells(buffer: &Buffer) -> Vec<(usize, usize, Cell)> {
+    buffer
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| {
+            let (x, y) = buffer.pos_of(index);
+            (x, y, *cell)
+        })
+        .collect()
+}
+
+/// Stream the effect into `out` in real time, returns the number of
+/// frames written. A closed pipe ends the stream like an interrupt does.
+pub fn stream<TE, W>(
+    effect: &mut TE,
+    name: &str,
+    screen_size: (u16, u16),
+    theme: &Theme,
+    options: &StreamOptions,
+    out: W,
+) -> Result<usize>
+where
+    TE: TerminalEffect + ?Sized,
+    W: Write,
+{
+    let header = Header {
+        version: VERSION,
+        width: screen_size.0,
+        height: screen_size.1,
+        effect: name.to_string(),
+        frame_rate: effect.frame_rate(),
+        background: match theme.background {
+            Background::Color(color) => Some(color),
+            Background::Terminal => None,
+        },
+    };
+    let mut writer = FrameWriter::new(out, &header, theme, options.keyframes)?;
+    let started_at = Instant::now();
+    let result =
+        run_headless(effect, options.frames.unwrap_or(usize::MAX), |at, diff| {
+            if let Some(wait) =
+                (started_at + at).checked_duration_since(Instant::now())
+            {
+                std::thread::sleep(wait);
+            }
+            writer.frame(at, diff)?;
+            Ok(())
+        });
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(writer.frame),
+        result => Ok(result.map(|_| writer.frame)?),
+    }
+}
+
+/// Replay a stream to the terminal with its timing, returns the number of
+/// frames shown. Exit keys stop it, cells outside the terminal are cut.
+pub fn play<W: Write, R: BufRead>(
+    stdout: &mut W,
+    mut reader: FrameReader<R>,
+) -> Result<usize> {
+    let header = reader.header.clone();
+    let mut theme = ThemeBuilder::default();
+    if let Some(color) = header.background {
+        theme.background(Background::Color(color));
+    }
+    let theme = theme.build().expect("theme has defaults");
+    let mut screen = Buffer::new(header.width as usize, header.height as usize);
+    let mut size = terminal::size()?;
+    theme.clear(stdout)?;
+
+    let started_at = Instant::now();
+    let mut shown = 0;
+    while let Some(frame) = reader.next_frame()? {
+        let due = started_at + Duration::from_secs_f64(frame.t.max(0.0));
+        loop {
+            if crash::terminate_requested() {
+                return Ok(shown);
+            }
+            let wait = due.saturating_duration_since(Instant::now());
+            if !event::poll(wait)? {
+                break;
+            }
+            match event::read()? {
+                Event::Key(key) if is_exit_key(&key) => return Ok(shown),
+                Event::Resize(width, height) => {
+                    size = (width, height);
+                    theme.clear(stdout)?;
+                    draw_diff(stdout, &visible(all_cells(&screen), size), &theme)?;
+                    stdout.flush()?;
+                }
+                _ => {}
+            }
+        }
+
+        let diff: Vec<(usize, usize, Cell)> = frame
+            .cells
+            .iter()
+            .filter(|cell| cell.x < screen.width && cell.y < screen.height)
+            .map(|cell| (cell.x, cell.y, cell.to_cell()))
+            .collect();
+        screen.apply_diff(&diff);
+        draw_diff(stdout, &visible(diff, size), &theme)?;
+        stdout.flush()?;
+        shown += 1;
+    }
+    Ok(shown)
+}
+
+/// Cells which fit the terminal
+fn visible(
+    cells: Vec<(usize, usize, Cell)>,
+    (width, height): (u16, u16),
+) -> Vec<(usize, usize, Cell)> {
+    cells
+        .into_iter()
+        .filter(|(x, y, _)| *x < width as usize && *y < height as usize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Header {
+        Header {
+            version: VERSION,
+            width: 3,
+            height: 2,
+            effect: "matrix".to_string(),
+            frame_rate: 60.0,
+            background: None,
+        }
+    }
+
+    fn green(symbol: char) -> Cell {
+        Cell::new(
+            symbol,
+            style::Color::Rgb { r: 0, g: 255, b: 0 },
+            Attribute::Bold,
+        )
+    }
+
+    #[test]
+    fn cells_keep_symbol_color_and_attribute() {
+        let cell = StreamCell::new(1, 2, &green('x'), &Theme::default());
+        assert_eq!(
+            serde_json::to_string(&cell).unwrap(),
+            r##"{"x":1,"y":2,"symbol":"x","fg":"#00ff00","attr":"bold"}"##
+        );
+        assert_eq!(cell.to_cell(), green('x'));
+
+        let plain = Cell::new('y', style::Color::Reset, Attribute::Reset);
+        let cell = StreamCell::new(0, 0, &plain, &Theme::default());
+        assert_eq!(cell.fg, None);
+        assert_eq!(cell.attr, None);
+        assert_eq!(cell.to_cell(), plain);
+    }
+
+    #[test]
+    fn stream_has_diffs_between_key_frames() {
+        let mut out = Vec::new();
+        let mut writer =
+            FrameWriter::new(&mut out, &header(), &Theme::default(), Some(2))
+                .unwrap();
+        writer.frame(Duration::ZERO, &[(0, 0, green('a'))]).unwrap();
+        writer
+            .frame(Duration::from_millis(500), &[(1, 1, green('b'))])
+            .unwrap();
+        writer.frame(Duration::from_secs(1), &[]).unwrap();
+
+        let mut reader = FrameReader::new(out.as_slice()).unwrap();
+        assert_eq!(reader.header, header());
+        let frames: Vec<Frame> =
+            std::iter::from_fn(|| reader.next_frame().unwrap()).collect();
+        let summary: Vec<(usize, f64, bool, usize)> = frames
+            .iter()
+            .map(|f| (f.frame, f.t, f.full, f.cells.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(0, 0.0, true, 6), (1, 0.5, false, 1), (2, 1.0, true, 6)]
+        );
+        assert_eq!(frames[1].cells[0].to_cell(), green('b'));
+        assert_eq!(frames[2].cells[4].symbol, 'b');
+    }
+
+    #[test]
+    fn reader_names_the_bad_line() {
+        let input = "{\"version\":1,\"width\":1,\"height\":1,\"effect\":\"x\",\
+                     \"frame_rate\":1.0}\n\n{\"frame\":0}\n";
+        let mut reader = FrameReader::new(input.as_bytes()).unwrap();
+        let error = reader.next_frame().unwrap_err();
+        assert!(error.to_string().starts_with("line 3:"), "{}", error);
+
+        let newer = "{\"version\":2,\"width\":1,\"height\":1,\"effect\":\"x\",\
+                     \"frame_rate\":1.0}\n";
+        assert!(FrameReader::new(newer.as_bytes()).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::buffer::{Buffer, Cell};
 use crate::common::{DefaultOptions, TerminalEffect};
+use crate::config::{Config, Validator};
+use crate::palette::{Palette, PaletteChoice};
 use crate::terrain::noise::PerlinNoise;
 use crossterm::style;
 use derive_builder::Builder;
@@ -16,6 +18,19 @@ pub struct TerrainOptions {
     pub octaves: i32,
     #[builder(default = "0.5")]
     pub persistence: f64,
+    #[builder(default)]
+    #[serde(default)]
+    pub palette: PaletteChoice,
+}
+
+impl TerrainOptions {
+    /// Report values the effect can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.positive("scale", self.scale);
+        check.at_least("octaves", self.octaves, 1);
+        check.positive("persistence", self.persistence);
+        check.palette("palette", &self.palette);
+    }
 }
 
 pub struct Terrain {
@@ -23,6 +38,7 @@ pub struct Terrain {
     options: TerrainOptions,
     buffer: Buffer,
     noise: PerlinNoise,
+    palette: Palette,
     generated: bool, // Only generate once
 }
 
@@ -49,7 +65,13 @@ impl TerminalEffect for Terrain {
         // No updates needed for static noise
     }
 
+    fn frame_rate(&self) -> f64 {
+        // drawn once, nothing changes afterwards
+        0.0
+    }
+
     fn update_size(&mut self, width: u16, height: u16) {
+        // same seed, so terrain is generated again for the new screen
         self.screen_size = (width, height);
         self.reset();
     }
@@ -59,18 +81,25 @@ impl TerminalEffect for Terrain {
             Buffer::new(self.screen_size.0 as usize, self.screen_size.1 as usize);
         self.generated = false;
     }
+
+    fn reconfigure(&mut self, config: &Config) {
+        // static picture, noise depends on the seed
+        *self = Self::new(config.get_terrain_options(), self.screen_size);
+    }
 }
 
 impl Terrain {
     pub fn new(options: TerrainOptions, screen_size: (u16, u16)) -> Self {
         let buffer = Buffer::new(screen_size.0 as usize, screen_size.1 as usize);
         let noise = PerlinNoise::new(options.seed);
+        let palette = options.palette.or_builtin("grayscale");
 
         Self {
             screen_size,
             options,
             buffer,
             noise,
+            palette,
             generated: false,
         }
     }
@@ -105,9 +134,7 @@ impl Terrain {
     }
 
     fn get_noise_visualization(&self, value: f64) -> (char, style::Color) {
-        // Simple grayscale visualization of noise
-        let intensity = (value * 255.0) as u8;
-
+        // grayscale unless another palette is chosen
         let character = match value {
             v if v < 0.1 => ' ',
             v if v < 0.2 => '.',
@@ -121,14 +148,7 @@ impl Terrain {
             _ => '@',
         };
 
-        (
-            character,
-            style::Color::Rgb {
-                r: intensity,
-                g: intensity,
-                b: intensity,
-            },
-        )
+        (character, self.palette.color(value as f32))
     }
 }
 
@@ -0,0 +1,267 @@
+//! Global color theme applied to every cell on its way to the terminal.
+//!
+//! Effects keep drawing with their own colors, `draw_diff` passes each cell
+//! through the `[theme]` section: hue shift, saturation and brightness
+//! scale, lightness inversion for light terminals and a painted background.
+//! The default theme leaves cells untouched.
+use crate::buffer::Cell;
+use crate::config::Validator;
+use crate::gradient::Rgb;
+use crossterm::{QueueableCommand, style, style::Stylize, terminal};
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+/// Background behind the effect
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Background {
+    /// Whatever the terminal has, written as `"terminal"`
+    #[default]
+    Terminal,
+    /// Painted under every cell, written as `"#rrggbb"`
+    Color(Rgb),
+}
+
+impl TryFrom<String> for Background {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value == "terminal" {
+            return Ok(Self::Terminal);
+        }
+        Rgb::parse(&value).map(Self::Color).ok_or_else(|| {
+            format!(
+                "invalid background `{}`, expected terminal or #rrggbb",
+                value
+            )
+        })
+    }
+}
+
+impl From<Background> for String {
+    fn from(background: Background) -> Self {
+        match background {
+            Background::Terminal => "terminal".to_string(),
+            Background::Color(color) => color.into(),
+        }
+    }
+}
+
+#[derive(Builder, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[builder(public, setter(into))]
+pub struct Theme {
+    #[builder(default)]
+    pub background: Background,
+    /// Degrees added to the hue of every color
+    #[builder(default = "0.0")]
+    pub hue_shift: f32,
+    /// Saturation multiplier, 0 turns effects gray
+    #[builder(default = "1.0")]
+    pub saturation: f32,
+    /// Brightness multiplier
+    #[builder(default = "1.0")]
+    pub brightness: f32,
+    /// Light terminal: lightness is inverted, so bright heads and dark
+    /// trails stay visible on a white background
+    #[builder(default = "false")]
+    pub light: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        ThemeBuilder::default().build().unwrap()
+    }
+}
+
+impl Theme {
+    /// Report values the theme can't work with
+    pub fn validate(&self, check: &mut Validator) {
+        check.non_negative("saturation", self.saturation);
+        check.non_negative("brightness", self.brightness);
+    }
+
+    /// Colors are changed by the theme
+    fn recolors(&self) -> bool {
+        self.hue_shift != 0.0
+            || self.saturation != 1.0
+            || self.brightness != 1.0
+            || self.light
+    }
+
+    /// Theme version of a foreground color, `Reset` stays the terminal's
+    pub fn color(&self, color: style::Color) -> style::Color {
+        if !self.recolors() {
+            return color;
+        }
+        let Some(rgb) = to_rgb(color) else {
+            return color;
+        };
+        let (hue, saturation, value) = rgb.to_hsv();
+        let mut rgb = Rgb::from_hsv(
+            hue + self.hue_shift,
+            saturation * self.saturation,
+            value * self.brightness,
+        );
+        if self.light {
+            let (lightness, a, b) = rgb.to_oklab();
+            rgb = Rgb::from_oklab(1.0 - lightness, a, b);
+        }
+        rgb.into()
+    }
+
+    /// Cell as it is printed
+    pub fn style(&self, cell: &Cell) -> style::StyledContent<char> {
+        if !self.recolors() && self.background == Background::Terminal {
+            return cell.symbol.with(cell.color).attribute(cell.attr);
+        }
+        // `Attribute::Reset` is written after colors and would drop them
+        let attr = match cell.attr {
+            style::Attribute::Reset => style::Attribute::NormalIntensity,
+            attr => attr,
+        };
+        let styled = cell.symbol.with(self.color(cell.color)).attribute(attr);
+        match self.background {
+            Background::Terminal => styled,
+            Background::Color(color) => styled.on(color.into()),
+        }
+    }
+
+    /// Queue clearing the whole screen in the background color
+    pub fn clear<W: Write>(&self, stdout: &mut W) -> io::Result<()> {
+        if let Background::Color(color) = self.background {
+            stdout.queue(style::SetBackgroundColor(color.into()))?;
+        }
+        stdout.queue(terminal::Clear(terminal::ClearType::All))?;
+        if self.background != Background::Terminal {
+            stdout.queue(style::SetBackgroundColor(style::Color::Reset))?;
+        }
+        Ok(())
+    }
+}
+
+/// RGB of terminal colors as xterm shows them, `None` for `Reset`
+pub fn to_rgb(color: style::Color) -> Option<Rgb> {
+    const ANSI: [Rgb; 16] = [
+        Rgb::new(0, 0, 0),
+        Rgb::new(128, 0, 0),
+        Rgb::new(0, 128, 0),
+        Rgb::new(128, 128, 0),
+        Rgb::new(0, 0, 128),
+        Rgb::new(128, 0, 128),
+        Rgb::new(0, 128, 128),
+        Rgb::new(192, 192, 192),
+        Rgb::new(128, 128, 128),
+        Rgb::new(255, 0, 0),
+        Rgb::new(0, 255, 0),
+        Rgb::new(255, 255, 0),
+        Rgb::new(0, 0, 255),
+        Rgb::new(255, 0, 255),
+        Rgb::new(0, 255, 255),
+        Rgb::new(255, 255, 255),
+    ];
+    let index = match color {
+        style::Color::Reset => return None,
+        style::Color::Rgb { r, g, b } => return Some(Rgb::new(r, g, b)),
+        style::Color::AnsiValue(value) => value,
+        style::Color::Black => 0,
+        style::Color::DarkRed => 1,
+        style::Color::DarkGreen => 2,
+        style::Color::DarkYellow => 3,
+        style::Color::DarkBlue => 4,
+        style::Color::DarkMagenta => 5,
+        style::Color::DarkCyan => 6,
+        style::Color::Grey => 7,
+        style::Color::DarkGrey => 8,
+        style::Color::Red => 9,
+        style::Color::Green => 10,
+        style::Color::Yellow => 11,
+        style::Color::Blue => 12,
+        style::Color::Magenta => 13,
+        style::Color::Cyan => 14,
+        style::Color::White => 15,
+    };
+    Some(match index {
+        0..=15 => ANSI[index as usize],
+        // 6x6x6 color cube
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let n = index - 16;
+            Rgb::new(level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            Rgb::new(gray, gray, gray)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(toml: &str) -> Theme {
+        let mut document = toml::Table::try_from(Theme::default()).unwrap();
+        document.extend(toml::from_str::<toml::Table>(toml).unwrap());
+        document.try_into().unwrap()
+    }
+
+    #[test]
+    fn default_theme_leaves_cells_alone() {
+        let cell = Cell::new('#', style::Color::DarkGrey, style::Attribute::Reset);
+        let plain = Theme::default();
+        assert_eq!(plain, theme(""));
+        assert_eq!(plain.color(style::Color::DarkGrey), style::Color::DarkGrey);
+        assert_eq!(
+            plain.style(&cell),
+            '#'.with(style::Color::DarkGrey)
+                .attribute(style::Attribute::Reset)
+        );
+    }
+
+    #[test]
+    fn colors_are_shifted_scaled_and_inverted() {
+        let shifted = theme("hue_shift = 120.0");
+        assert_eq!(shifted.color(style::Color::Red), Rgb::new(0, 255, 0).into());
+        assert_eq!(shifted.color(style::Color::Reset), style::Color::Reset);
+
+        let gray = theme("saturation = 0.0\nbrightness = 0.5");
+        assert_eq!(
+            gray.color(style::Color::Rgb { r: 0, g: 0, b: 255 }),
+            Rgb::new(128, 128, 128).into()
+        );
+
+        // dark trails turn light and the other way round
+        let light = theme("light = true");
+        assert_eq!(
+            light.color(style::Color::Black),
+            Rgb::new(255, 255, 255).into()
+        );
+        assert_eq!(light.color(style::Color::White), Rgb::new(0, 0, 0).into());
+        let style::Color::Rgb { r, .. } = light.color(style::Color::DarkGrey)
+        else {
+            panic!("light theme gives RGB colors");
+        };
+        assert!(r < 128, "dark grey becomes darker, got {}", r);
+    }
+
+    #[test]
+    fn background_is_painted_under_cells() {
+        let painted = theme("background = \"#fdf6e3\"");
+        assert_eq!(
+            painted.background,
+            Background::Color(Rgb::new(253, 246, 227))
+        );
+        let styled = painted.style(&Cell::default());
+        assert_eq!(
+            styled.style().background_color,
+            Some(style::Color::Rgb {
+                r: 253,
+                g: 246,
+                b: 227
+            })
+        );
+        assert!(!styled.style().attributes.has(style::Attribute::Reset));
+        assert!(toml::from_str::<Theme>("background = \"white\"").is_err());
+    }
+}